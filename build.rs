@@ -1,7 +1,13 @@
 use std::env;
 
 fn main() {
-    enable_simd_optimizations();
+    let simd = enable_simd_optimizations();
+    enable_avx2();
+    let neon = enable_neon();
+    let simd128 = enable_wasm_simd128();
+    if !simd && !neon && !simd128 {
+        enable_portable_simd();
+    }
     enable_libc();
 }
 
@@ -11,19 +17,98 @@ fn main() {
 // this is generally only intended for testing.
 //
 // On targets which don't feature SSE2, this is disabled, as LLVM wouln't know
-// how to work with SSE2 operands. Enabling SSE4.2 and AVX on SSE2-only targets
-// is not a problem. In that case, the fastest option will be chosen at
-// runtime.
-fn enable_simd_optimizations() {
+// how to work with SSE2 operands. This includes older 32-bit x86 targets, such
+// as i586-unknown-linux-gnu, while i686 and x86_64 targets always have it.
+// Enabling SSE4.2 and AVX on SSE2-only targets is not a problem. In that case,
+// the fastest option will be chosen at runtime.
+fn enable_simd_optimizations() -> bool {
     if is_env_set("CARGO_CFG_MEMCHR_DISABLE_AUTO_SIMD")
         || !target_has_feature("sse2")
     {
-        return;
+        return false;
     }
     println!("cargo:rustc-cfg=memchr_runtime_simd");
     println!("cargo:rustc-cfg=memchr_runtime_sse2");
     println!("cargo:rustc-cfg=memchr_runtime_sse42");
     println!("cargo:rustc-cfg=memchr_runtime_avx");
+    true
+}
+
+// This adds a `memchr_avx2` cfg if and only if the AVX2 routines may be used,
+// which requires either detecting AVX2 at runtime or enabling it at compile
+// time. Detection is done by the standard library, or by this crate when the
+// `cpuid` feature is enabled. The latter isn't possible inside SGX enclaves,
+// where the CPUID instruction isn't permitted.
+//
+// This is consolidated into one cfg knob for the same reason as `memchr_libc`.
+fn enable_avx2() {
+    let sgx = env::var("CARGO_CFG_TARGET_ENV")
+        .map(|env| env == "sgx")
+        .unwrap_or(false);
+    if is_feature_set("STD")
+        || (is_feature_set("CPUID") && !sgx)
+        || target_has_feature("avx2")
+    {
+        println!("cargo:rustc-cfg=memchr_avx2");
+    }
+}
+
+// This adds a `memchr_runtime_neon` cfg on aarch64 targets with NEON, which
+// includes all of the common ones. Unlike SSE2 and AVX, the NEON intrinsics
+// were only stabilized in Rust 1.59, which is newer than our minimum supported
+// Rust version, so older compilers use the fallback routines instead.
+//
+// As with the x86 cfgs, this can be disabled with
+// RUSTFLAGS="--cfg memchr_disable_auto_simd".
+fn enable_neon() -> bool {
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    if arch != "aarch64"
+        || is_env_set("CARGO_CFG_MEMCHR_DISABLE_AUTO_SIMD")
+        || !target_has_feature("neon")
+        || rustc_minor_version().unwrap_or(0) < 59
+    {
+        return false;
+    }
+    println!("cargo:rustc-cfg=memchr_runtime_neon");
+    true
+}
+
+// This adds a `memchr_runtime_simd128` cfg on wasm32 targets when the
+// `simd128` target feature is enabled, e.g., with
+// RUSTFLAGS="-C target-feature=+simd128". WebAssembly has no runtime feature
+// detection, so the feature must be enabled at compile time. The intrinsics
+// were stabilized in Rust 1.54, which is newer than our minimum supported Rust
+// version, so older compilers use the fallback routines instead.
+//
+// As with the x86 cfgs, this can be disabled with
+// RUSTFLAGS="--cfg memchr_disable_auto_simd".
+fn enable_wasm_simd128() -> bool {
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    if arch != "wasm32"
+        || is_env_set("CARGO_CFG_MEMCHR_DISABLE_AUTO_SIMD")
+        || !target_has_feature("simd128")
+        || rustc_minor_version().unwrap_or(0) < 54
+    {
+        return false;
+    }
+    println!("cargo:rustc-cfg=memchr_runtime_simd128");
+    true
+}
+
+// This adds a `memchr_portable_simd` cfg when the `portable-simd` feature is
+// enabled and none of the cfgs above were added, i.e., when there is no
+// hand-tuned vector implementation for the target. In that case, the memchr
+// family of routines and substring search use the kernels built on
+// `core::simd`, which requires a nightly compiler.
+//
+// Unlike the cfgs above, this isn't disabled by
+// RUSTFLAGS="--cfg memchr_disable_auto_simd". Instead, that makes it possible
+// to test the portable kernels in place of the hand-tuned ones.
+fn enable_portable_simd() {
+    if !is_feature_set("PORTABLE_SIMD") {
+        return;
+    }
+    println!("cargo:rustc-cfg=memchr_portable_simd");
 }
 
 // This adds a `memchr_libc` cfg if and only if libc can be used, if no other
@@ -67,6 +152,21 @@ fn is_env_set(name: &str) -> bool {
     env::var_os(name).is_some()
 }
 
+// Returns the minor version of the compiler used to build this crate, e.g.,
+// `59` for Rust 1.59.0, or `None` if it can't be determined.
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output =
+        std::process::Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    // The output looks like "rustc 1.59.0 (9d1b2106e 2022-02-23)".
+    let mut parts = version.split_whitespace().nth(1)?.split('.');
+    if parts.next()? != "1" {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
 fn target_has_feature(feature: &str) -> bool {
     env::var("CARGO_CFG_TARGET_FEATURE")
         .map(|features| features.contains(feature))
@@ -0,0 +1,92 @@
+/*
+ * A C API for the memchr crate's byte and substring search routines.
+ *
+ * Every function that takes a pointer to bytes also takes their length. When
+ * the length is zero, the pointer may be NULL. A NULL pointer with a non-zero
+ * length is invalid: searches report no match and memchr_finder_new returns
+ * NULL. Otherwise, the pointer must point to at least that many readable
+ * bytes for the duration of the call.
+ *
+ * Searches return the offset of the match from the start of the haystack, or
+ * MEMCHR_NONE when there is no match.
+ *
+ * A memchr_finder is created by memchr_finder_new, which copies the needle,
+ * and owned by the caller until it is passed to memchr_finder_free. It is
+ * immutable, so it may be used by several threads at once.
+ *
+ * This header must be kept in sync with capi/src/lib.rs.
+ */
+
+#ifndef MEMCHR_H
+#define MEMCHR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The value returned by searches that don't find a match. */
+#define MEMCHR_NONE SIZE_MAX
+
+/* A prebuilt substring searcher, which can search in either direction. */
+typedef struct memchr_finder memchr_finder;
+
+/* Returns the offset of the first occurrence of n1 in the haystack. */
+size_t memchr_find(uint8_t n1, const uint8_t *haystack, size_t len);
+
+/* Returns the offset of the first occurrence of n1 or n2 in the haystack. */
+size_t memchr_find2(uint8_t n1, uint8_t n2, const uint8_t *haystack,
+                    size_t len);
+
+/* Returns the offset of the first occurrence of n1, n2 or n3 in the
+ * haystack. */
+size_t memchr_find3(uint8_t n1, uint8_t n2, uint8_t n3,
+                    const uint8_t *haystack, size_t len);
+
+/* Returns the offset of the last occurrence of n1 in the haystack. */
+size_t memchr_rfind(uint8_t n1, const uint8_t *haystack, size_t len);
+
+/* Returns the offset of the last occurrence of n1 or n2 in the haystack. */
+size_t memchr_rfind2(uint8_t n1, uint8_t n2, const uint8_t *haystack,
+                     size_t len);
+
+/* Returns the offset of the last occurrence of n1, n2 or n3 in the
+ * haystack. */
+size_t memchr_rfind3(uint8_t n1, uint8_t n2, uint8_t n3,
+                     const uint8_t *haystack, size_t len);
+
+/* Returns the offset of the first occurrence of the needle in the haystack.
+ * An empty needle matches at offset 0. */
+size_t memchr_memmem_find(const uint8_t *haystack, size_t haystack_len,
+                          const uint8_t *needle, size_t needle_len);
+
+/* Returns the offset of the last occurrence of the needle in the haystack.
+ * An empty needle matches at offset haystack_len. */
+size_t memchr_memmem_rfind(const uint8_t *haystack, size_t haystack_len,
+                           const uint8_t *needle, size_t needle_len);
+
+/* Creates a finder for the given needle, which is copied. Returns NULL when
+ * needle is NULL and needle_len isn't zero. The finder must be freed with
+ * memchr_finder_free. */
+memchr_finder *memchr_finder_new(const uint8_t *needle, size_t needle_len);
+
+/* Returns the offset of the first occurrence of the finder's needle in the
+ * haystack. Returns MEMCHR_NONE when finder is NULL. */
+size_t memchr_finder_find(const memchr_finder *finder,
+                          const uint8_t *haystack, size_t len);
+
+/* Returns the offset of the last occurrence of the finder's needle in the
+ * haystack. Returns MEMCHR_NONE when finder is NULL. */
+size_t memchr_finder_rfind(const memchr_finder *finder,
+                           const uint8_t *haystack, size_t len);
+
+/* Frees a finder. Passing NULL does nothing. */
+void memchr_finder_free(memchr_finder *finder);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MEMCHR_H */
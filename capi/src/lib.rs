@@ -0,0 +1,280 @@
+/*!
+This crate exports a C API for the byte and substring search routines in the
+`memchr` crate. It is built as both a shared and a static library, and its
+declarations are in `include/memchr.h`, which must be kept in sync with this
+file.
+
+# Conventions
+
+Every function that takes a pointer to bytes also takes their length. When the
+length is zero, the pointer may be `NULL`. A `NULL` pointer with a non-zero
+length is invalid: searches report no match and `memchr_finder_new` returns
+`NULL`. Otherwise, the pointer must point to at least that many readable
+bytes for the duration of the call.
+
+Searches return the offset of the match from the start of the haystack, or
+`MEMCHR_NONE` (`SIZE_MAX`) when there is no match. Since a haystack can't be
+larger than `PTRDIFF_MAX` bytes, no match is ever reported at `SIZE_MAX`.
+
+A `memchr_finder` is created by `memchr_finder_new`, which copies the needle,
+and owned by the caller until it is passed to `memchr_finder_free`. It is
+immutable, so it may be used by several threads at once.
+
+No function in this crate panics, except when memory can't be allocated, in
+which case the process is aborted.
+*/
+
+#![deny(missing_docs)]
+
+use std::{ptr, slice};
+
+use memchr::memmem::{Finder, FinderRev};
+
+/// The value returned by searches that don't find a match.
+pub const MEMCHR_NONE: usize = usize::MAX;
+
+/// A prebuilt substring searcher, which can search for its needle in either
+/// direction.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct memchr_finder {
+    forward: Finder<'static>,
+    reverse: FinderRev<'static>,
+}
+
+/// Returns the offset of the first occurrence of `n1` in the haystack.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on `haystack` and `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_find(
+    n1: u8,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    search(haystack, len, |h| memchr::memchr(n1, h))
+}
+
+/// Returns the offset of the first occurrence of `n1` or `n2` in the
+/// haystack.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on `haystack` and `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_find2(
+    n1: u8,
+    n2: u8,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    search(haystack, len, |h| memchr::memchr2(n1, n2, h))
+}
+
+/// Returns the offset of the first occurrence of `n1`, `n2` or `n3` in the
+/// haystack.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on `haystack` and `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_find3(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    search(haystack, len, |h| memchr::memchr3(n1, n2, n3, h))
+}
+
+/// Returns the offset of the last occurrence of `n1` in the haystack.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on `haystack` and `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_rfind(
+    n1: u8,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    search(haystack, len, |h| memchr::memrchr(n1, h))
+}
+
+/// Returns the offset of the last occurrence of `n1` or `n2` in the
+/// haystack.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on `haystack` and `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_rfind2(
+    n1: u8,
+    n2: u8,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    search(haystack, len, |h| memchr::memrchr2(n1, n2, h))
+}
+
+/// Returns the offset of the last occurrence of `n1`, `n2` or `n3` in the
+/// haystack.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on `haystack` and `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_rfind3(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    search(haystack, len, |h| memchr::memrchr3(n1, n2, n3, h))
+}
+
+/// Returns the offset of the first occurrence of the needle in the haystack.
+///
+/// An empty needle matches at offset `0`.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on pointers and lengths.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_memmem_find(
+    haystack: *const u8,
+    haystack_len: usize,
+    needle: *const u8,
+    needle_len: usize,
+) -> usize {
+    match to_slice(needle, needle_len) {
+        None => MEMCHR_NONE,
+        Some(needle) => {
+            search(haystack, haystack_len, |h| memchr::memmem::find(h, needle))
+        }
+    }
+}
+
+/// Returns the offset of the last occurrence of the needle in the haystack.
+///
+/// An empty needle matches at offset `haystack_len`.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on pointers and lengths.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_memmem_rfind(
+    haystack: *const u8,
+    haystack_len: usize,
+    needle: *const u8,
+    needle_len: usize,
+) -> usize {
+    match to_slice(needle, needle_len) {
+        None => MEMCHR_NONE,
+        Some(needle) => search(haystack, haystack_len, |h| {
+            memchr::memmem::rfind(h, needle)
+        }),
+    }
+}
+
+/// Create a finder for the given needle, which is copied.
+///
+/// This returns `NULL` when `needle` is `NULL` and `needle_len` isn't zero.
+/// The finder must be freed with `memchr_finder_free`.
+///
+/// # Safety
+///
+/// See the crate documentation for the rules on `needle` and `needle_len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_finder_new(
+    needle: *const u8,
+    needle_len: usize,
+) -> *mut memchr_finder {
+    let needle = match to_slice(needle, needle_len) {
+        None => return ptr::null_mut(),
+        Some(needle) => needle,
+    };
+    let finder = memchr_finder {
+        forward: Finder::new(needle).into_owned(),
+        reverse: FinderRev::new(needle).into_owned(),
+    };
+    Box::into_raw(Box::new(finder))
+}
+
+/// Returns the offset of the first occurrence of the finder's needle in the
+/// haystack, or `MEMCHR_NONE` if `finder` is `NULL`.
+///
+/// # Safety
+///
+/// `finder` must be `NULL`, or have been returned by `memchr_finder_new` and
+/// not yet freed. See the crate documentation for the rules on `haystack` and
+/// `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_finder_find(
+    finder: *const memchr_finder,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    match finder.as_ref() {
+        None => MEMCHR_NONE,
+        Some(finder) => search(haystack, len, |h| finder.forward.find(h)),
+    }
+}
+
+/// Returns the offset of the last occurrence of the finder's needle in the
+/// haystack, or `MEMCHR_NONE` if `finder` is `NULL`.
+///
+/// # Safety
+///
+/// `finder` must be `NULL`, or have been returned by `memchr_finder_new` and
+/// not yet freed. See the crate documentation for the rules on `haystack` and
+/// `len`.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_finder_rfind(
+    finder: *const memchr_finder,
+    haystack: *const u8,
+    len: usize,
+) -> usize {
+    match finder.as_ref() {
+        None => MEMCHR_NONE,
+        Some(finder) => search(haystack, len, |h| finder.reverse.rfind(h)),
+    }
+}
+
+/// Free a finder. Passing `NULL` does nothing.
+///
+/// # Safety
+///
+/// `finder` must be `NULL`, or have been returned by `memchr_finder_new` and
+/// not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn memchr_finder_free(finder: *mut memchr_finder) {
+    if !finder.is_null() {
+        drop(Box::from_raw(finder));
+    }
+}
+
+/// Convert a pointer and length from C into a slice, or `None` if the pointer
+/// is `NULL` and the length isn't zero.
+unsafe fn to_slice<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if !ptr.is_null() {
+        Some(slice::from_raw_parts(ptr, len))
+    } else if len == 0 {
+        Some(&[])
+    } else {
+        None
+    }
+}
+
+/// Run the given search on a haystack from C, converting its result to an
+/// offset or `MEMCHR_NONE`.
+unsafe fn search<F: FnOnce(&[u8]) -> Option<usize>>(
+    haystack: *const u8,
+    len: usize,
+    find: F,
+) -> usize {
+    to_slice(haystack, len).and_then(find).unwrap_or(MEMCHR_NONE)
+}
@@ -0,0 +1,63 @@
+// This compiles the C test programs in `tests/c` against the static library
+// and the header, and runs them. It is skipped when no C compiler is found.
+
+#![cfg(unix)]
+
+use std::{
+    env,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Returns the directory containing the test binary, which is
+/// `target/<profile>/deps`. The static library built for this test run is
+/// put there too.
+fn deps_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+/// Compile the given C program and return the path to the executable, or
+/// `None` if there is no C compiler.
+fn compile(src: &Path) -> Option<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out = deps_dir().join(src.file_stem().unwrap());
+    let cc = env::var_os("CC").unwrap_or_else(|| "cc".into());
+    let result = Command::new(&cc)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(root.join("include"))
+        .arg(src)
+        .arg(deps_dir().join("libmemchr_capi.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&out)
+        .status();
+    match result {
+        Err(ref err) if err.kind() == ErrorKind::NotFound => {
+            eprintln!("skipping C tests, {:?} not found", cc);
+            None
+        }
+        Err(err) => panic!("failed to run {:?}: {}", cc, err),
+        Ok(status) => {
+            assert!(status.success(), "failed to compile {}", src.display());
+            Some(out)
+        }
+    }
+}
+
+#[test]
+fn c_programs() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("c");
+    for entry in dir.read_dir().unwrap() {
+        let src = entry.unwrap().path();
+        if src.extension().and_then(|ext| ext.to_str()) != Some("c") {
+            continue;
+        }
+        let exe = match compile(&src) {
+            None => return,
+            Some(exe) => exe,
+        };
+        let status = Command::new(&exe).status().unwrap();
+        assert!(status.success(), "{} failed", src.display());
+    }
+}
@@ -0,0 +1,113 @@
+/*
+ * Tests for the C API. Each failed check prints its location and makes the
+ * program exit with a non-zero status.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "memchr.h"
+
+static int failures = 0;
+
+#define CHECK_EQ(expected, got)                                           \
+    do {                                                                  \
+        size_t e_ = (expected), g_ = (got);                               \
+        if (e_ != g_) {                                                   \
+            fprintf(stderr, "%s:%d: expected %zu, got %zu\n", __FILE__,   \
+                    __LINE__, e_, g_);                                    \
+            failures++;                                                   \
+        }                                                                 \
+    } while (0)
+
+#define CHECK(cond)                                                       \
+    do {                                                                  \
+        if (!(cond)) {                                                    \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,        \
+                    __LINE__, #cond);                                     \
+            failures++;                                                   \
+        }                                                                 \
+    } while (0)
+
+#define BYTES(s) ((const uint8_t *)(s)), (sizeof(s) - 1)
+
+static void test_bytes(void) {
+    CHECK_EQ(1, memchr_find('b', BYTES("abcabc")));
+    CHECK_EQ(1, memchr_find2('c', 'b', BYTES("abcabc")));
+    CHECK_EQ(0, memchr_find3('z', 'b', 'a', BYTES("abcabc")));
+    CHECK_EQ(4, memchr_rfind('b', BYTES("abcabc")));
+    CHECK_EQ(5, memchr_rfind2('c', 'b', BYTES("abcabc")));
+    CHECK_EQ(5, memchr_rfind3('z', 'b', 'c', BYTES("abcabc")));
+    CHECK_EQ(MEMCHR_NONE, memchr_find('z', BYTES("abcabc")));
+    CHECK_EQ(MEMCHR_NONE, memchr_rfind3('x', 'y', 'z', BYTES("abcabc")));
+}
+
+static void test_long_haystack(void) {
+    size_t len = 1 << 20;
+    uint8_t *haystack = malloc(len);
+    CHECK(haystack != NULL);
+    memset(haystack, 'a', len);
+    haystack[len - 3] = 'z';
+    CHECK_EQ(len - 3, memchr_find('z', haystack, len));
+    CHECK_EQ(len - 3, memchr_memmem_find(haystack, len, BYTES("zaa")));
+    CHECK_EQ(len - 4, memchr_memmem_rfind(haystack, len, BYTES("aza")));
+    free(haystack);
+}
+
+static void test_memmem(void) {
+    CHECK_EQ(4, memchr_memmem_find(BYTES("foo bar foo"), BYTES("bar")));
+    CHECK_EQ(8, memchr_memmem_rfind(BYTES("foo bar foo"), BYTES("foo")));
+    CHECK_EQ(MEMCHR_NONE,
+             memchr_memmem_find(BYTES("foo bar foo"), BYTES("quux")));
+    /* An empty needle matches at the start or the end. */
+    CHECK_EQ(0, memchr_memmem_find(BYTES("abc"), BYTES("")));
+    CHECK_EQ(3, memchr_memmem_rfind(BYTES("abc"), BYTES("")));
+}
+
+static void test_null(void) {
+    /* NULL with a zero length is an empty slice. */
+    CHECK_EQ(MEMCHR_NONE, memchr_find('a', NULL, 0));
+    CHECK_EQ(MEMCHR_NONE, memchr_rfind2('a', 'b', NULL, 0));
+    CHECK_EQ(0, memchr_memmem_find(NULL, 0, NULL, 0));
+    CHECK_EQ(2, memchr_memmem_rfind(BYTES("ab"), NULL, 0));
+    /* NULL with a non-zero length is invalid. */
+    CHECK_EQ(MEMCHR_NONE, memchr_find('a', NULL, 5));
+    CHECK_EQ(MEMCHR_NONE, memchr_memmem_find(BYTES("ab"), NULL, 1));
+    CHECK(memchr_finder_new(NULL, 1) == NULL);
+    CHECK_EQ(MEMCHR_NONE, memchr_finder_find(NULL, BYTES("ab")));
+    CHECK_EQ(MEMCHR_NONE, memchr_finder_rfind(NULL, BYTES("ab")));
+    memchr_finder_free(NULL);
+}
+
+static void test_finder(void) {
+    char needle[] = "foo";
+    memchr_finder *finder = memchr_finder_new(BYTES(needle));
+    CHECK(finder != NULL);
+    /* The needle is copied, so changing it doesn't affect the finder. */
+    memset(needle, 'x', 3);
+    CHECK_EQ(0, memchr_finder_find(finder, BYTES("foo bar foo")));
+    CHECK_EQ(8, memchr_finder_rfind(finder, BYTES("foo bar foo")));
+    CHECK_EQ(MEMCHR_NONE, memchr_finder_find(finder, BYTES("xxx")));
+    CHECK_EQ(MEMCHR_NONE, memchr_finder_rfind(finder, NULL, 0));
+    memchr_finder_free(finder);
+
+    finder = memchr_finder_new(NULL, 0);
+    CHECK(finder != NULL);
+    CHECK_EQ(0, memchr_finder_find(finder, NULL, 0));
+    CHECK_EQ(3, memchr_finder_rfind(finder, BYTES("abc")));
+    memchr_finder_free(finder);
+}
+
+int main(void) {
+    test_bytes();
+    test_long_haystack();
+    test_memmem();
+    test_null();
+    test_finder();
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    return 0;
+}
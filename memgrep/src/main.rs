@@ -0,0 +1,541 @@
+/*!
+`memgrep` searches files for a fixed string or byte using the public `memchr`
+and `memmem` APIs.
+
+It is meant as a reference for how those APIs fit together, and as a quick
+way to time searches on real data. A pattern of a single byte is searched for
+with `memchr`, a case insensitive pattern with `memmem::ClassFinder`, and
+everything else with `memmem::Finder` and `memmem::FinderRev`.
+*/
+
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    fs,
+    io::{self, Read, Write},
+    process,
+    time::Instant,
+};
+
+use memchr::{
+    memchr, memchr2_iter, memchr_iter,
+    memmem::{ClassFinder, Finder, FinderBuilder, FinderRev, Prefilter},
+    memrchr, memrchr2_iter, memrchr_iter, ByteSet,
+};
+
+const USAGE: &str = "\
+Search files for a fixed string or byte.
+
+USAGE:
+    memgrep [OPTIONS] PATTERN [FILE ...]
+
+When no file is given, or a file is '-', standard input is searched.
+
+PATTERN may contain the escapes \\xNN (any byte), \\n, \\r, \\t, \\0 and \\\\.
+
+OPTIONS:
+    -c, --count              Print the number of matches in each file.
+    -b, --offsets            Print the byte offset of each match.
+    -r, --reverse            Report matches from the end of each file.
+    -i, --ignore-case        Match ASCII letters case insensitively.
+        --overlapping        Report overlapping matches.
+        --prefilter=MODE     Set the substring prefilter: 'auto' or 'none'.
+        --time               Print the time spent searching each file.
+    -h, --help               Print this message.
+
+By default, each line containing a match is printed. The exit status is 0 if
+there was a match, 1 if there wasn't and 2 if an error occurred.
+";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() {
+    match run() {
+        Ok(true) => process::exit(0),
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            eprintln!("memgrep: {}", err);
+            process::exit(2);
+        }
+    }
+}
+
+/// Run the search, returning whether any file contained a match.
+fn run() -> Result<bool> {
+    let args = match Args::parse(env::args_os().skip(1))? {
+        None => {
+            print!("{}", USAGE);
+            return Ok(true);
+        }
+        Some(args) => args,
+    };
+    let searcher =
+        Searcher::new(&args.pattern, args.ignore_case, args.prefilter);
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    let mut matched = false;
+    for path in &args.paths {
+        let (name, haystack) = if path == "-" {
+            let mut haystack = vec![];
+            io::stdin().read_to_end(&mut haystack)?;
+            ("(standard input)".to_string(), haystack)
+        } else {
+            let name = path.to_string_lossy().into_owned();
+            let haystack =
+                fs::read(path).map_err(|err| format!("{}: {}", name, err))?;
+            (name, haystack)
+        };
+        let prefix = if args.paths.len() > 1 {
+            format!("{}:", name)
+        } else {
+            String::new()
+        };
+
+        let start = Instant::now();
+        let count = report(&args, &searcher, &haystack, &prefix, &mut out)?;
+        let elapsed = start.elapsed();
+        if args.time {
+            out.flush()?;
+            eprintln!(
+                "{}: {} matches in {} bytes, searched in {:?}",
+                name,
+                count,
+                haystack.len(),
+                elapsed
+            );
+        }
+        matched = matched || count > 0;
+    }
+    out.flush()?;
+    Ok(matched)
+}
+
+/// Search the haystack and write the results in the requested mode, returning
+/// the number of matches.
+fn report<W: Write>(
+    args: &Args,
+    searcher: &Searcher<'_>,
+    haystack: &[u8],
+    prefix: &str,
+    mut out: W,
+) -> io::Result<usize> {
+    let mut count = 0;
+    let mut result = Ok(());
+    // The start of the last line printed, so that each line is printed once.
+    let mut last_line = None;
+    searcher.for_each(haystack, args.reverse, args.overlapping, |at| {
+        count += 1;
+        if result.is_err() {
+            return;
+        }
+        result = match args.mode {
+            Mode::Count => Ok(()),
+            Mode::Offsets => writeln!(out, "{}{}", prefix, at),
+            Mode::Lines => {
+                let start =
+                    memrchr(b'\n', &haystack[..at]).map_or(0, |i| i + 1);
+                if last_line == Some(start) {
+                    return;
+                }
+                last_line = Some(start);
+                let end = memchr(b'\n', &haystack[at..])
+                    .map_or(haystack.len(), |i| at + i);
+                out.write_all(prefix.as_bytes())
+                    .and_then(|_| out.write_all(&haystack[start..end]))
+                    .and_then(|_| out.write_all(b"\n"))
+            }
+        };
+    });
+    result?;
+    if args.mode == Mode::Count {
+        writeln!(out, "{}{}", prefix, count)?;
+    }
+    Ok(count)
+}
+
+/// What to print for each file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mode {
+    /// Print each line containing a match.
+    Lines,
+    /// Print the number of matches.
+    Count,
+    /// Print the offset of each match.
+    Offsets,
+}
+
+/// The parsed command line arguments.
+#[derive(Debug)]
+struct Args {
+    pattern: Vec<u8>,
+    paths: Vec<OsString>,
+    mode: Mode,
+    reverse: bool,
+    ignore_case: bool,
+    overlapping: bool,
+    prefilter: Prefilter,
+    time: bool,
+}
+
+impl Args {
+    /// Parse the given arguments, returning `None` if help was requested.
+    fn parse<I: Iterator<Item = OsString>>(mut it: I) -> Result<Option<Args>> {
+        let mut args = Args {
+            pattern: vec![],
+            paths: vec![],
+            mode: Mode::Lines,
+            reverse: false,
+            ignore_case: false,
+            overlapping: false,
+            prefilter: Prefilter::Auto,
+            time: false,
+        };
+        let mut positional = vec![];
+        let mut options_done = false;
+        while let Some(arg) = it.next() {
+            let flag = match arg.to_str() {
+                Some(flag)
+                    if !options_done
+                        && flag.starts_with('-')
+                        && flag != "-" =>
+                {
+                    flag
+                }
+                _ => {
+                    positional.push(arg);
+                    continue;
+                }
+            };
+            // Short flags may be combined, as in `-ic`.
+            let flags: Vec<String> =
+                if !flag.starts_with("--") && flag.len() > 2 {
+                    flag[1..].chars().map(|c| format!("-{}", c)).collect()
+                } else {
+                    vec![flag.to_string()]
+                };
+            for flag in &flags {
+                if flag == "-h" || flag == "--help" {
+                    return Ok(None);
+                }
+                args.parse_flag(flag, &mut it, &mut options_done)?;
+            }
+        }
+        let mut positional = positional.into_iter();
+        let pattern = positional
+            .next()
+            .ok_or("no pattern given, see --help")?
+            .into_string()
+            .map_err(|_| "pattern is not valid UTF-8, use \\xNN escapes")?;
+        args.pattern = unescape(&pattern)?;
+        args.paths = positional.collect();
+        if args.paths.is_empty() {
+            args.paths.push(OsString::from("-"));
+        }
+        Ok(Some(args))
+    }
+
+    /// Apply a single flag, taking its value from `it` if it has one.
+    fn parse_flag<I: Iterator<Item = OsString>>(
+        &mut self,
+        flag: &str,
+        it: &mut I,
+        options_done: &mut bool,
+    ) -> Result<()> {
+        match flag {
+            "--" => *options_done = true,
+            "-c" | "--count" => self.mode = Mode::Count,
+            "-b" | "--offsets" => self.mode = Mode::Offsets,
+            "-r" | "--reverse" => self.reverse = true,
+            "-i" | "--ignore-case" => self.ignore_case = true,
+            "--overlapping" => self.overlapping = true,
+            "--time" => self.time = true,
+            "--prefilter" => {
+                let mode = it
+                    .next()
+                    .ok_or("--prefilter requires a value")?
+                    .into_string()
+                    .map_err(|_| "invalid --prefilter value")?;
+                self.prefilter = parse_prefilter(&mode)?;
+            }
+            _ if flag.starts_with("--prefilter=") => {
+                let mode = &flag["--prefilter=".len()..];
+                self.prefilter = parse_prefilter(mode)?;
+            }
+            _ => {
+                return Err(
+                    format!("unknown option '{}', see --help", flag).into()
+                )
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_prefilter(mode: &str) -> Result<Prefilter> {
+    match mode {
+        "auto" => Ok(Prefilter::Auto),
+        "none" => Ok(Prefilter::None),
+        _ => Err(format!(
+            "invalid --prefilter value '{}', expected 'auto' or 'none'",
+            mode
+        )
+        .into()),
+    }
+}
+
+/// Convert a pattern with escape sequences into the bytes it denotes.
+fn unescape(pattern: &str) -> Result<Vec<u8>> {
+    fn hex(b: u8) -> Option<u8> {
+        match b {
+            b'0'..=b'9' => Some(b - b'0'),
+            b'a'..=b'f' => Some(b - b'a' + 10),
+            b'A'..=b'F' => Some(b - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    let mut bytes = vec![];
+    let mut it = pattern.bytes();
+    while let Some(b) = it.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        let b = match it.next() {
+            None => return Err("incomplete escape at end of pattern".into()),
+            Some(b'\\') => b'\\',
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b't') => b'\t',
+            Some(b'0') => b'\0',
+            Some(b'x') => {
+                let hi = it.next().and_then(hex);
+                let lo = it.next().and_then(hex);
+                match (hi, lo) {
+                    (Some(hi), Some(lo)) => (hi << 4) | lo,
+                    _ => {
+                        return Err(
+                            "\\x must be followed by two hex digits".into()
+                        )
+                    }
+                }
+            }
+            Some(b) => {
+                return Err(format!("unknown escape '\\{}'", b as char).into())
+            }
+        };
+        bytes.push(b);
+    }
+    Ok(bytes)
+}
+
+/// The searcher chosen for a pattern.
+#[derive(Debug)]
+enum Searcher<'p> {
+    /// A single byte, searched for with `memchr`.
+    Byte(u8),
+    /// An ASCII letter in either case, searched for with `memchr2`.
+    ByteCaseless(u8, u8),
+    /// A substring, searched for with `memmem`.
+    Substring { forward: Finder<'p>, reverse: FinderRev<'p> },
+    /// A case insensitive substring, searched for with a class finder whose
+    /// sets contain both cases of each letter.
+    Caseless { finder: ClassFinder, len: usize },
+}
+
+impl<'p> Searcher<'p> {
+    fn new(
+        pattern: &'p [u8],
+        ignore_case: bool,
+        prefilter: Prefilter,
+    ) -> Searcher<'p> {
+        let caseless =
+            ignore_case && pattern.iter().any(|b| b.is_ascii_alphabetic());
+        match (pattern, caseless) {
+            (&[b], false) => Searcher::Byte(b),
+            (&[b], true) => Searcher::ByteCaseless(
+                b.to_ascii_lowercase(),
+                b.to_ascii_uppercase(),
+            ),
+            (_, true) => {
+                let needle: Vec<ByteSet> = pattern
+                    .iter()
+                    .map(|b| {
+                        ByteSet::from_bytes(&[
+                            b.to_ascii_lowercase(),
+                            b.to_ascii_uppercase(),
+                        ])
+                    })
+                    .collect();
+                let finder = ClassFinder::new(&needle);
+                Searcher::Caseless { finder, len: pattern.len() }
+            }
+            (_, false) => {
+                let mut builder = FinderBuilder::new();
+                builder.prefilter(prefilter);
+                Searcher::Substring {
+                    forward: builder.build_forward(pattern),
+                    reverse: builder.build_reverse(pattern),
+                }
+            }
+        }
+    }
+
+    /// Call `f` with the offset of each match in `haystack`, in the order
+    /// they're found.
+    fn for_each<F: FnMut(usize)>(
+        &self,
+        haystack: &[u8],
+        reverse: bool,
+        overlapping: bool,
+        mut f: F,
+    ) {
+        // Matches of a single byte never overlap.
+        match (self, reverse, overlapping) {
+            (&Searcher::Byte(b), false, _) => {
+                memchr_iter(b, haystack).for_each(f)
+            }
+            (&Searcher::Byte(b), true, _) => {
+                memrchr_iter(b, haystack).for_each(f)
+            }
+            (&Searcher::ByteCaseless(b1, b2), false, _) => {
+                memchr2_iter(b1, b2, haystack).for_each(f)
+            }
+            (&Searcher::ByteCaseless(b1, b2), true, _) => {
+                memrchr2_iter(b1, b2, haystack).for_each(f)
+            }
+            (Searcher::Substring { forward, .. }, false, false) => {
+                forward.find_iter(haystack).for_each(f)
+            }
+            (Searcher::Substring { forward, .. }, false, true) => {
+                find_overlapping(haystack, |h| forward.find(h), f)
+            }
+            (Searcher::Substring { reverse, .. }, true, false) => {
+                reverse.rfind_iter(haystack).for_each(f)
+            }
+            (Searcher::Substring { reverse, .. }, true, true) => {
+                let len = reverse.needle().len();
+                rfind_overlapping(haystack, len, |h| reverse.rfind(h), f)
+            }
+            (Searcher::Caseless { finder, .. }, false, false) => {
+                finder.find_iter(haystack).for_each(f)
+            }
+            (Searcher::Caseless { finder, .. }, false, true) => {
+                find_overlapping(haystack, |h| finder.find(h), f)
+            }
+            (Searcher::Caseless { finder, len }, true, overlapping) => {
+                // A class finder only searches forward, so find every match
+                // and walk them backwards. Going from the right, a match is
+                // kept unless it overlaps the last match kept.
+                let mut all = vec![];
+                find_overlapping(
+                    haystack,
+                    |h| finder.find(h),
+                    |i| all.push(i),
+                );
+                let mut limit = haystack.len();
+                for &i in all.iter().rev() {
+                    if overlapping || i + *len <= limit {
+                        f(i);
+                        limit = i;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Call `f` with the offset of every match reported by `find`, including
+/// overlapping ones, from left to right.
+fn find_overlapping<S, F>(haystack: &[u8], find: S, mut f: F)
+where
+    S: Fn(&[u8]) -> Option<usize>,
+    F: FnMut(usize),
+{
+    let mut pos = 0;
+    while pos <= haystack.len() {
+        match find(&haystack[pos..]) {
+            None => break,
+            Some(i) => {
+                f(pos + i);
+                pos += i + 1;
+            }
+        }
+    }
+}
+
+/// Call `f` with the offset of every match of a needle of length `len`
+/// reported by `rfind`, including overlapping ones, from right to left.
+fn rfind_overlapping<S, F>(haystack: &[u8], len: usize, rfind: S, mut f: F)
+where
+    S: Fn(&[u8]) -> Option<usize>,
+    F: FnMut(usize),
+{
+    let mut end = haystack.len();
+    while let Some(i) = rfind(&haystack[..end]) {
+        f(i);
+        // The next match must end before this one does.
+        end = match (i + len).checked_sub(1) {
+            None => break,
+            Some(end) => end,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use memchr::memmem::Prefilter;
+
+    use super::{unescape, Searcher};
+
+    fn find(
+        pattern: &str,
+        haystack: &str,
+        ignore_case: bool,
+        reverse: bool,
+        overlapping: bool,
+    ) -> Vec<usize> {
+        let pattern = unescape(pattern).unwrap();
+        let searcher = Searcher::new(&pattern, ignore_case, Prefilter::Auto);
+        let mut all = vec![];
+        searcher.for_each(haystack.as_bytes(), reverse, overlapping, |i| {
+            all.push(i)
+        });
+        all
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            b"a\n\x00\xFF\\".to_vec(),
+            unescape(r"a\n\0\xff\\").unwrap()
+        );
+        assert!(unescape(r"\x0").is_err());
+        assert!(unescape(r"\q").is_err());
+        assert!(unescape("\\").is_err());
+    }
+
+    #[test]
+    fn modes() {
+        let h = "aaaa";
+        assert_eq!(vec![0, 2], find("aa", h, false, false, false));
+        assert_eq!(vec![0, 1, 2], find("aa", h, false, false, true));
+        assert_eq!(vec![2, 0], find("aa", h, false, true, false));
+        assert_eq!(vec![2, 1, 0], find("aa", h, false, true, true));
+        assert_eq!(vec![3, 2, 1, 0], find("a", h, false, true, true));
+        assert_eq!(vec![4, 3, 2, 1, 0], find("", h, false, true, true));
+        assert_eq!(vec![0, 1, 2, 3, 4], find("", h, false, false, true));
+    }
+
+    #[test]
+    fn caseless() {
+        let h = "aAaA bB";
+        assert_eq!(vec![0, 2], find("Aa", h, true, false, false));
+        assert_eq!(vec![0, 1, 2], find("Aa", h, true, false, true));
+        assert_eq!(vec![2, 0], find("Aa", h, true, true, false));
+        assert_eq!(vec![2, 1, 0], find("Aa", h, true, true, true));
+        assert_eq!(vec![6, 5], find("b", h, true, true, false));
+        assert_eq!(vec![4], find(" ", h, true, false, false));
+    }
+}
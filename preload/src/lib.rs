@@ -0,0 +1,157 @@
+/*!
+This crate builds a shared library that exports `memchr`, `memrchr`,
+`rawmemchr` and `memmem` with the same signatures and semantics as the C
+library's versions, implemented with the `memchr` crate. Loading it with
+`LD_PRELOAD` replaces the C library's versions in programs that can't be
+rebuilt:
+
+```text
+$ cargo build --release -p memchr-preload
+$ LD_PRELOAD=target/release/libmemchr_preload.so some-program
+```
+
+# Semantics
+
+These follow POSIX for `memchr` and the GNU C library for the others:
+
+* The byte searched for is the `int` argument converted to `unsigned char`.
+* A length of zero never matches, and the pointer isn't read. In particular,
+  `memrchr` with a length of zero returns `NULL` without reading anything.
+* `memmem` with an empty needle returns the haystack pointer, even if the
+  haystack is empty. A needle longer than the haystack never matches.
+* `rawmemchr` has no length, and the byte must occur in the object pointed
+  to. It reads the object one aligned machine word at a time through raw
+  pointers, so the last word read may include bytes past the end of the
+  object. Since an aligned word never crosses a page boundary, those bytes
+  are always in a page that the object extends into.
+
+Nothing in this crate or in the `memchr` crate calls these functions through
+the C library, so there is no risk of a replacement calling itself.
+*/
+
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr, slice,
+};
+
+/// The size of the words read by `rawmemchr`.
+const USIZE_BYTES: usize = mem::size_of::<usize>();
+/// A word with every byte set to `0x01`.
+const LO_USIZE: usize = !0 / 255;
+/// A word with every byte set to `0x80`.
+const HI_USIZE: usize = LO_USIZE << 7;
+
+/// Returns a pointer to the first occurrence of `c` in the `n` bytes at `s`,
+/// or `NULL`.
+///
+/// # Safety
+///
+/// `s` must point to `n` readable bytes, unless `n` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn memchr(
+    s: *const c_void,
+    c: c_int,
+    n: usize,
+) -> *mut c_void {
+    if n == 0 {
+        return ptr::null_mut();
+    }
+    let haystack = slice::from_raw_parts(s as *const u8, n);
+    at(s, memchr::memchr(c as u8, haystack))
+}
+
+/// Returns a pointer to the last occurrence of `c` in the `n` bytes at `s`,
+/// or `NULL`.
+///
+/// # Safety
+///
+/// `s` must point to `n` readable bytes, unless `n` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn memrchr(
+    s: *const c_void,
+    c: c_int,
+    n: usize,
+) -> *mut c_void {
+    if n == 0 {
+        return ptr::null_mut();
+    }
+    let haystack = slice::from_raw_parts(s as *const u8, n);
+    at(s, memchr::memrchr(c as u8, haystack))
+}
+
+/// Returns a pointer to the first occurrence of `c` at or after `s`.
+///
+/// # Safety
+///
+/// `c` must occur in the object that `s` points into, at or after `s`.
+#[no_mangle]
+pub unsafe extern "C" fn rawmemchr(s: *const c_void, c: c_int) -> *mut c_void {
+    let needle = c as u8;
+    let mut p = s as *const u8;
+    // The bytes before the first aligned word are all in the object, since
+    // the byte occurs at or after them.
+    while p as usize & (USIZE_BYTES - 1) != 0 {
+        if ptr::read_volatile(p) == needle {
+            return p as *mut c_void;
+        }
+        p = p.add(1);
+    }
+    // The length of the object is unknown, so this never forms a slice,
+    // which would claim that every byte in it is part of the object. Only
+    // the word containing the occurrence may extend past the end of the
+    // object, and being aligned, it stays within the same page.
+    let repeated = LO_USIZE * needle as usize;
+    loop {
+        let word = ptr::read_volatile(p as *const usize);
+        if contains_zero_byte(word ^ repeated) {
+            break;
+        }
+        p = p.add(USIZE_BYTES);
+    }
+    // The occurrence is in the word at `p`, so the bytes up to it are all in
+    // the object.
+    while ptr::read_volatile(p) != needle {
+        p = p.add(1);
+    }
+    p as *mut c_void
+}
+
+/// Returns true if any byte of `x` is zero.
+fn contains_zero_byte(x: usize) -> bool {
+    x.wrapping_sub(LO_USIZE) & !x & HI_USIZE != 0
+}
+
+/// Returns a pointer to the first occurrence of the `needle_len` bytes at
+/// `needle` in the `haystack_len` bytes at `haystack`, or `NULL`.
+///
+/// # Safety
+///
+/// Each pointer must point to the given number of readable bytes, unless
+/// that number is zero.
+#[no_mangle]
+pub unsafe extern "C" fn memmem(
+    haystack: *const c_void,
+    haystack_len: usize,
+    needle: *const c_void,
+    needle_len: usize,
+) -> *mut c_void {
+    if needle_len == 0 {
+        return haystack as *mut c_void;
+    }
+    if needle_len > haystack_len {
+        return ptr::null_mut();
+    }
+    let h = slice::from_raw_parts(haystack as *const u8, haystack_len);
+    let n = slice::from_raw_parts(needle as *const u8, needle_len);
+    at(haystack, memchr::memmem::find(h, n))
+}
+
+/// Returns a pointer to the given offset from `s`, or `NULL` if there isn't
+/// one.
+unsafe fn at(s: *const c_void, offset: Option<usize>) -> *mut c_void {
+    match offset {
+        None => ptr::null_mut(),
+        Some(i) => (s as *const u8).add(i) as *mut c_void,
+    }
+}
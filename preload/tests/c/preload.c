@@ -0,0 +1,119 @@
+/*
+ * Checks the semantics of memchr, memrchr, rawmemchr and memmem. When run
+ * with the argument "preloaded", also checks that those functions come from
+ * the preloaded library rather than the C library.
+ *
+ * Built with -fno-builtin so that the compiler calls the functions instead
+ * of evaluating them itself.
+ */
+
+#define _GNU_SOURCE
+
+#include <dlfcn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+static int failures = 0;
+
+#define CHECK(cond)                                                       \
+    do {                                                                  \
+        if (!(cond)) {                                                    \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,        \
+                    __LINE__, #cond);                                     \
+            failures++;                                                   \
+        }                                                                 \
+    } while (0)
+
+/* Checks that the given function is defined in the preloaded library. */
+static void check_preloaded(const char *name, void *fun) {
+    Dl_info info;
+    if (dladdr(fun, &info) == 0 || info.dli_fname == NULL
+        || strstr(info.dli_fname, "memchr_preload") == NULL) {
+        fprintf(stderr, "%s is not from the preloaded library\n", name);
+        failures++;
+    }
+}
+
+static void test_memchr(void) {
+    const char *s = "abcabc";
+    CHECK(memchr(s, 'b', 6) == s + 1);
+    CHECK(memchr(s, 'z', 6) == NULL);
+    CHECK(memchr(s, 'c', 2) == NULL);
+    /* The byte is converted to unsigned char. */
+    CHECK(memchr(s, 'a' + 256, 6) == s);
+    CHECK(memchr("\xff", -1, 1) != NULL);
+    /* A zero length never matches. */
+    CHECK(memchr(s + 6, 'a', 0) == NULL);
+}
+
+static void test_memrchr(void) {
+    const char *s = "abcabc";
+    CHECK(memrchr(s, 'b', 6) == s + 4);
+    CHECK(memrchr(s, 'b', 4) == s + 1);
+    CHECK(memrchr(s, 'z', 6) == NULL);
+    CHECK(memrchr(s, 'a' + 256, 6) == s + 3);
+    CHECK(memrchr(s + 6, 'a', 0) == NULL);
+    CHECK(memrchr(s, 'a', 0) == NULL);
+}
+
+static void test_rawmemchr(void) {
+    const char *s = "abcabc";
+    CHECK(rawmemchr(s, 'c') == s + 2);
+    CHECK(rawmemchr(s, '\0') == s + 6);
+    CHECK(rawmemchr(s + 3, 'a' + 256) == s + 3);
+
+    /* A match far away, crossing several pages. */
+    size_t len = 3 * 4096 + 17;
+    char *big = malloc(len);
+    CHECK(big != NULL);
+    memset(big, 'a', len);
+    big[len - 1] = 'z';
+    CHECK(rawmemchr(big + 5, 'z') == big + len - 1);
+    free(big);
+
+    /* A match in the last byte before an inaccessible page. */
+    long page = sysconf(_SC_PAGESIZE);
+    char *pages = mmap(NULL, 2 * page, PROT_READ | PROT_WRITE,
+                       MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(pages != MAP_FAILED);
+    CHECK(mprotect(pages + page, page, PROT_NONE) == 0);
+    memset(pages, 'a', page);
+    pages[page - 1] = 'z';
+    CHECK(rawmemchr(pages, 'z') == pages + page - 1);
+    CHECK(rawmemchr(pages + page - 3, 'z') == pages + page - 1);
+    munmap(pages, 2 * page);
+}
+
+static void test_memmem(void) {
+    const char *s = "foo bar foo";
+    CHECK(memmem(s, 11, "bar", 3) == s + 4);
+    CHECK(memmem(s, 11, "foo", 3) == s);
+    CHECK(memmem(s + 1, 10, "foo", 3) == s + 8);
+    CHECK(memmem(s, 11, "quux", 4) == NULL);
+    CHECK(memmem(s, 2, "foo", 3) == NULL);
+    /* An empty needle matches at the start, even in an empty haystack. */
+    CHECK(memmem(s, 11, "", 0) == s);
+    CHECK(memmem(s, 0, "", 0) == s);
+    CHECK(memmem(s, 0, "f", 1) == NULL);
+}
+
+int main(int argc, char **argv) {
+    if (argc > 1 && strcmp(argv[1], "preloaded") == 0) {
+        check_preloaded("memchr", (void *)memchr);
+        check_preloaded("memrchr", (void *)memrchr);
+        check_preloaded("rawmemchr", (void *)rawmemchr);
+        check_preloaded("memmem", (void *)memmem);
+    }
+    test_memchr();
+    test_memrchr();
+    test_rawmemchr();
+    test_memmem();
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    return 0;
+}
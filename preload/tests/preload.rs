@@ -0,0 +1,49 @@
+// This compiles a C program that checks the semantics of the replaced
+// functions, and runs it both with and without the library preloaded. Running
+// it without the library checks the program's expectations against the C
+// library. It is skipped when no C compiler is found.
+
+#![cfg(all(target_os = "linux", target_env = "gnu"))]
+
+use std::{env, io::ErrorKind, path::Path, process::Command};
+
+#[test]
+fn preload() {
+    // The test binary is in `target/<profile>/deps`, along with the library
+    // built for this test run.
+    let exe = env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    let lib = deps.join("libmemchr_preload.so");
+    let src = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("c")
+        .join("preload.c");
+    let out = deps.join("preload-c");
+
+    let cc = env::var_os("CC").unwrap_or_else(|| "cc".into());
+    let result = Command::new(&cc)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-fno-builtin"])
+        .arg(&src)
+        .args(["-ldl", "-o"])
+        .arg(&out)
+        .status();
+    match result {
+        Err(ref err) if err.kind() == ErrorKind::NotFound => {
+            eprintln!("skipping preload test, {:?} not found", cc);
+            return;
+        }
+        Err(err) => panic!("failed to run {:?}: {}", cc, err),
+        Ok(status) => {
+            assert!(status.success(), "failed to compile {}", src.display())
+        }
+    }
+
+    let status = Command::new(&out).status().unwrap();
+    assert!(status.success(), "C library doesn't match expectations");
+    let status = Command::new(&out)
+        .arg("preloaded")
+        .env("LD_PRELOAD", &lib)
+        .status()
+        .unwrap();
+    assert!(status.success(), "preloaded library failed");
+}
@@ -0,0 +1,39 @@
+/*!
+The implementation of the `memchr` family of routines using NEON vector
+instructions, which work on 16 bytes at a time.
+*/
+
+use crate::arch::Backend;
+
+/// Returns whether this implementation can be used.
+///
+/// This is `false` when NEON isn't enabled for the target, when SIMD has been
+/// disabled when building this crate, when the compiler is older than Rust
+/// 1.59, and under Miri.
+pub fn is_available() -> bool {
+    Backend::Neon.is_available()
+}
+
+macro_rules! call {
+    ($name:ident, $haystack:ident, $($needle:ident),+) => {{
+        assert!(is_available(), "the NEON memchr backend is not available");
+        if $haystack.is_empty() {
+            return None;
+        }
+        #[cfg(all(not(miri), memchr_runtime_neon))]
+        {
+            // SAFETY: We checked that this crate was built with NEON
+            // enabled.
+            unsafe {
+                crate::memchr::aarch64::neon::$name($($needle),+, $haystack)
+            }
+        }
+        #[cfg(not(all(not(miri), memchr_runtime_neon)))]
+        {
+            let _ = ($($needle),+);
+            unreachable!()
+        }
+    }};
+}
+
+define_kernels!(call);
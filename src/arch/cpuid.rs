@@ -0,0 +1,90 @@
+/*!
+Detection of AVX2 support without the standard library, by executing the
+CPUID and XGETBV instructions directly. This is only used when the `cpuid`
+feature is enabled and the `std` feature isn't.
+*/
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{__cpuid, __cpuid_count, _xgetbv};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__cpuid, __cpuid_count, _xgetbv};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const UNKNOWN: u8 = 0;
+const NO: u8 = 1;
+const YES: u8 = 2;
+
+/// Whether AVX2 can be used: `UNKNOWN` until it has been detected, and then
+/// either `YES` or `NO`.
+static AVX2: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+/// Returns whether both the CPU and the operating system support AVX2.
+///
+/// The result is detected on the first call and cached. Racing threads may
+/// each detect it, but they all find the same result.
+#[inline]
+pub(crate) fn avx2() -> bool {
+    match AVX2.load(Ordering::Relaxed) {
+        YES => true,
+        NO => false,
+        _ => {
+            let avx2 = detect_avx2();
+            AVX2.store(if avx2 { YES } else { NO }, Ordering::Relaxed);
+            avx2
+        }
+    }
+}
+
+// The CPUID intrinsics are only safe to call in newer versions of Rust.
+#[allow(unused_unsafe)]
+fn detect_avx2() -> bool {
+    // SAFETY: The CPUID instruction is available on all x86_64 CPUs, and on
+    // all x86 CPUs with SSE2, which are the only ones that use this module.
+    // (Except inside SGX enclaves, where this module isn't used either.)
+    let max_leaf = unsafe { __cpuid(0) }.eax;
+    if max_leaf < 7 {
+        return false;
+    }
+    // SAFETY: As above.
+    let leaf1 = unsafe { __cpuid(1) };
+    // The CPU must support AVX, and the operating system must have enabled
+    // XSAVE, which it uses to preserve the AVX registers on context switches.
+    // The latter also means that XGETBV is available.
+    let osxsave = leaf1.ecx & (1 << 27) != 0;
+    let avx = leaf1.ecx & (1 << 28) != 0;
+    if !osxsave || !avx {
+        return false;
+    }
+    // SAFETY: We just checked that XGETBV is available.
+    let xcr0 = unsafe { xgetbv0() };
+    // The operating system must preserve both the SSE (bit 1) and the upper
+    // halves of the AVX (bit 2) registers.
+    if xcr0 & 0b110 != 0b110 {
+        return false;
+    }
+    // SAFETY: We checked that leaf 7 exists above.
+    let leaf7 = unsafe { __cpuid_count(7, 0) };
+    leaf7.ebx & (1 << 5) != 0
+}
+
+/// Returns the value of the XCR0 register.
+///
+/// # Safety
+///
+/// Callers must ensure that the CPU supports XGETBV and the operating system
+/// has enabled it, as indicated by the OSXSAVE bit.
+#[target_feature(enable = "xsave")]
+unsafe fn xgetbv0() -> u64 {
+    _xgetbv(0)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[test]
+    fn same_as_std() {
+        let expected = is_x86_feature_detected!("avx2");
+        assert_eq!(expected, super::avx2());
+        // Again, to check the cached result.
+        assert_eq!(expected, super::avx2());
+    }
+}
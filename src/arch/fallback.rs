@@ -0,0 +1,21 @@
+/*!
+The portable implementation of the `memchr` family of routines, which works
+on `usize` sized chunks of the haystack. It is available on every target.
+*/
+
+/// Returns whether this implementation can be used, which is always.
+pub fn is_available() -> bool {
+    true
+}
+
+macro_rules! call {
+    ($name:ident, $haystack:ident, $($needle:ident),+) => {
+        if $haystack.is_empty() {
+            None
+        } else {
+            crate::memchr::fallback::$name($($needle),+, $haystack)
+        }
+    };
+}
+
+define_kernels!(call);
@@ -0,0 +1,776 @@
+/*!
+This module provides control over which implementation the `memchr` family of
+routines uses, along with direct access to each implementation.
+
+On `x86_64` and `x86`, [`memchr`](crate::memchr),
+[`memchr2`](crate::memchr2), [`memchr3`](crate::memchr3) and their reverse
+variants pick an implementation at runtime, based on the CPU features
+available: AVX2 if possible, SSE2 otherwise. The choice is made once, on first
+use. [`backend`] reports the choice, and [`set_backend`] changes it.
+Alternatively, the `MEMCHR_BACKEND` environment variable may be set to
+`fallback`, `sse2` or `avx2` to choose the backend used from the start, which
+is useful for reproducing benchmarks or bisecting bugs that only occur on some
+CPUs. If it names a backend that isn't available, it is ignored.
+
+When this crate is compiled with AVX2 enabled, such as with
+`RUSTFLAGS="-C target-cpu=native"` on a CPU that supports it, there is no
+runtime choice: the routines call the AVX2 implementation directly, which
+also lets it be inlined. In that case, the backend is always
+[`Backend::Avx2`], the environment variable is ignored and [`set_backend`]
+fails for any other backend.
+
+Without the `std` feature, the choice can't be changed, and AVX2 is only
+detected if the `cpuid` feature is enabled. Otherwise, SSE2 is used.
+
+On `x86`, this requires a target with SSE2 enabled at compile time, such as
+`i686-unknown-linux-gnu`. Targets without it, such as `i586-unknown-linux-gnu`,
+only use the fallback.
+
+On `aarch64`, NEON is part of the baseline of all common targets, so there
+is no runtime choice either: the routines always use [`Backend::Neon`]. (This
+requires Rust 1.59 or newer. With older compilers, the fallback is used.)
+
+On `wasm32`, there is no runtime feature detection, so the routines use
+[`Backend::Simd128`] if and only if this crate was compiled with the `simd128`
+target feature enabled, e.g., with `RUSTFLAGS="-C target-feature=+simd128"`.
+(This requires Rust 1.54 or newer.)
+
+On other targets, when the `portable-simd` feature is enabled, the routines
+always use [`Backend::Portable`], which is built on `core::simd` and requires
+a nightly compiler. (This is also the case on the targets above when their
+vector implementation isn't used, such as when the `simd128` target feature
+isn't enabled on `wasm32`.)
+
+Otherwise, there is no runtime choice to make, and only the
+[`Backend::Fallback`] backend is reported. (When the `libc` feature is
+enabled on such targets, `memchr` and `memrchr` call the C library instead.)
+
+The other routines in this crate with SSE2 and AVX2 implementations, such
+as [`find_run`](crate::find_run), follow the same choice. Substring search
+makes its own choices.
+
+The [`fallback`], `x86_64`, `x86`, `aarch64`, `wasm32` and `portable`
+sub-modules expose each implementation directly, regardless of the selected
+backend. (All but the first only exist on the corresponding targets, or with
+the `portable-simd` feature.)
+
+# Example
+
+```
+use memchr::arch::{self, Backend};
+
+let before = arch::backend();
+assert!(before.is_available());
+
+// The fallback is always available, unless this crate was compiled with
+// AVX2 or simd128 enabled, for aarch64, or with the portable-simd feature.
+# #[cfg(feature = "std")]
+if arch::set_backend(Backend::Fallback).is_ok() {
+    assert_eq!(Backend::Fallback, arch::backend());
+}
+assert_eq!(Some(8), memchr::memchr(b'k', b"the quick brown fox"));
+# #[cfg(feature = "std")]
+arch::reset_backend();
+```
+*/
+
+use core::fmt;
+
+/// Define the memchr family of routines for a single implementation.
+///
+/// `$call` is the name of a macro that takes the name of a routine, followed
+/// by the haystack and needle parameters, and calls that routine of this
+/// implementation on a non-empty haystack.
+macro_rules! define_kernels {
+    ($call:ident) => {
+        /// Like [`memchr`](crate::memchr), but always uses this
+        /// implementation.
+        pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+            $call!(memchr, haystack, n1)
+        }
+
+        /// Like [`memchr2`](crate::memchr2), but always uses this
+        /// implementation.
+        pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+            $call!(memchr2, haystack, n1, n2)
+        }
+
+        /// Like [`memchr3`](crate::memchr3), but always uses this
+        /// implementation.
+        pub fn memchr3(
+            n1: u8,
+            n2: u8,
+            n3: u8,
+            haystack: &[u8],
+        ) -> Option<usize> {
+            $call!(memchr3, haystack, n1, n2, n3)
+        }
+
+        /// Like [`memrchr`](crate::memrchr), but always uses this
+        /// implementation.
+        pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+            $call!(memrchr, haystack, n1)
+        }
+
+        /// Like [`memrchr2`](crate::memrchr2), but always uses this
+        /// implementation.
+        pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+            $call!(memrchr2, haystack, n1, n2)
+        }
+
+        /// Like [`memrchr3`](crate::memrchr3), but always uses this
+        /// implementation.
+        pub fn memrchr3(
+            n1: u8,
+            n2: u8,
+            n3: u8,
+            haystack: &[u8],
+        ) -> Option<usize> {
+            $call!(memrchr3, haystack, n1, n2, n3)
+        }
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+pub mod aarch64;
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "cpuid",
+    not(target_env = "sgx")
+))]
+#[cfg_attr(feature = "std", allow(dead_code))]
+mod cpuid;
+pub mod fallback;
+#[cfg(feature = "portable-simd")]
+pub mod portable;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm32;
+// The implementations are the same on both, but they are exposed under the
+// name of the corresponding module in `core::arch`.
+#[cfg(target_arch = "x86")]
+#[path = "x86_64/mod.rs"]
+pub mod x86;
+#[cfg(target_arch = "x86_64")]
+pub mod x86_64;
+
+/// Whether the routines use one of the vector implementations.
+const DISPATCH: bool = cfg!(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+));
+
+/// Whether the routines call the AVX2 implementation directly, since this
+/// crate was compiled with AVX2 enabled.
+#[cfg(feature = "std")]
+const STATIC_AVX2: bool =
+    DISPATCH && cfg!(all(target_feature = "avx2", memchr_runtime_avx));
+
+/// Whether the routines call the NEON implementation directly, which they
+/// always do when it is available.
+const NEON: bool =
+    cfg!(all(not(miri), target_arch = "aarch64", memchr_runtime_neon));
+
+/// Whether the routines call the simd128 implementation directly, which they
+/// always do when it is available.
+const SIMD128: bool =
+    cfg!(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128));
+
+/// Whether the routines call the core::simd implementation directly, which
+/// they always do when it is selected by build.rs.
+const PORTABLE: bool = cfg!(all(not(miri), memchr_portable_simd));
+
+/// An implementation of the `memchr` family of routines.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Backend {
+    /// The portable implementation, which works on `usize` sized chunks.
+    Fallback,
+    /// The implementation using SSE2 vector instructions.
+    Sse2,
+    /// The implementation using AVX2 vector instructions.
+    Avx2,
+    /// The implementation using NEON vector instructions, on `aarch64`.
+    Neon,
+    /// The implementation using the `simd128` vector instructions, on
+    /// `wasm32`.
+    Simd128,
+    /// The implementation using the portable vectors of `core::simd`, with
+    /// the `portable-simd` feature.
+    Portable,
+}
+
+impl Backend {
+    /// Returns the best backend available on the current CPU.
+    pub fn best() -> Backend {
+        if Backend::Avx2.is_available() {
+            Backend::Avx2
+        } else if Backend::Sse2.is_available() {
+            Backend::Sse2
+        } else if Backend::Neon.is_available() {
+            Backend::Neon
+        } else if Backend::Simd128.is_available() {
+            Backend::Simd128
+        } else if Backend::Portable.is_available() {
+            Backend::Portable
+        } else {
+            Backend::Fallback
+        }
+    }
+
+    /// Returns whether the routines can use this backend on the current CPU.
+    ///
+    /// The fallback is always available. AVX2 additionally requires either
+    /// the `std` or `cpuid` feature to detect it, or compiling this crate
+    /// with AVX2 enabled. NEON requires Rust 1.59 or newer. simd128 requires
+    /// compiling this crate with it enabled, and Rust 1.54 or newer. The
+    /// portable implementation requires the `portable-simd` feature, and is
+    /// only available on targets where none of the others are.
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::Fallback => true,
+            Backend::Sse2 => DISPATCH && cfg!(memchr_runtime_sse2),
+            Backend::Avx2 => DISPATCH && avx2_detected(),
+            Backend::Neon => NEON,
+            Backend::Simd128 => SIMD128,
+            Backend::Portable => PORTABLE,
+        }
+    }
+
+    /// Returns the name of this backend, which is one of `fallback`, `sse2`,
+    /// `avx2`, `neon`, `simd128` or `portable`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Fallback => "fallback",
+            Backend::Sse2 => "sse2",
+            Backend::Avx2 => "avx2",
+            Backend::Neon => "neon",
+            Backend::Simd128 => "simd128",
+            Backend::Portable => "portable",
+        }
+    }
+
+    /// Returns the backend with the given name, ignoring ASCII case, or
+    /// `None` if there isn't one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::arch::Backend;
+    ///
+    /// assert_eq!(Some(Backend::Avx2), Backend::from_name("AVX2"));
+    /// assert_eq!(Some(Backend::Neon), Backend::from_name("neon"));
+    /// assert_eq!(None, Backend::from_name("avx512"));
+    /// ```
+    pub fn from_name(name: &str) -> Option<Backend> {
+        let all = [
+            Backend::Fallback,
+            Backend::Sse2,
+            Backend::Avx2,
+            Backend::Neon,
+            Backend::Simd128,
+            Backend::Portable,
+        ];
+        all.iter().cloned().find(|b| b.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The error returned by [`set_backend`] when the backend isn't available on
+/// the current CPU.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnavailableBackend {
+    backend: Backend,
+}
+
+impl UnavailableBackend {
+    /// Returns the backend that isn't available.
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+}
+
+impl fmt::Display for UnavailableBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "memchr backend '{}' is not available", self.backend)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnavailableBackend {}
+
+/// Returns the backend used by the `memchr` family of routines.
+///
+/// If the routines haven't been called yet, this makes the same choice they
+/// would.
+pub fn backend() -> Backend {
+    #[cfg(feature = "std")]
+    {
+        selected()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        Backend::best()
+    }
+}
+
+/// Make the `memchr` family of routines use the given backend from now on.
+///
+/// This may be called at any time. Calls that are in progress on other
+/// threads may complete with the previous backend.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Errors
+///
+/// When the backend isn't available on the current CPU, or when the routines
+/// always use a different backend, which is the case when this crate was
+/// compiled with AVX2 or simd128 enabled, for aarch64, or with the
+/// `portable-simd` feature on targets that use it. The backend in use is
+/// unchanged.
+#[cfg(feature = "std")]
+pub fn set_backend(backend: Backend) -> Result<(), UnavailableBackend> {
+    let fixed = fixed_backend().unwrap_or(backend) != backend;
+    if !backend.is_available() || fixed {
+        return Err(UnavailableBackend { backend });
+    }
+    SELECTED.store(encode(Some(backend)), Ordering::SeqCst);
+    reset_dispatch();
+    Ok(())
+}
+
+/// Undo any call to [`set_backend`], so that the backend is chosen as it is
+/// on first use: from the `MEMCHR_BACKEND` environment variable, or the best
+/// available.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn reset_backend() {
+    SELECTED.store(encode(None), Ordering::SeqCst);
+    reset_dispatch();
+}
+
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The selected backend, encoded with `encode`.
+#[cfg(feature = "std")]
+static SELECTED: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the selected backend, selecting it first if needed.
+#[cfg(feature = "std")]
+pub(crate) fn selected() -> Backend {
+    if let Some(backend) = fixed_backend() {
+        return backend;
+    }
+    if let Some(backend) = decode(SELECTED.load(Ordering::SeqCst)) {
+        return backend;
+    }
+    let backend = std::env::var("MEMCHR_BACKEND")
+        .ok()
+        .and_then(|name| Backend::from_name(name.trim()))
+        .filter(|b| b.is_available())
+        .unwrap_or_else(Backend::best);
+    // If another thread selected a backend in the meantime, use that one.
+    let result = SELECTED.compare_exchange(
+        encode(None),
+        encode(Some(backend)),
+        Ordering::SeqCst,
+        Ordering::SeqCst,
+    );
+    match result {
+        Ok(_) => backend,
+        Err(current) => decode(current).unwrap_or(backend),
+    }
+}
+
+/// Returns the backend that the routines always use, if they can't change it
+/// at runtime.
+#[cfg(feature = "std")]
+fn fixed_backend() -> Option<Backend> {
+    if STATIC_AVX2 {
+        Some(Backend::Avx2)
+    } else if NEON {
+        Some(Backend::Neon)
+    } else if SIMD128 {
+        Some(Backend::Simd128)
+    } else if PORTABLE {
+        Some(Backend::Portable)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+fn encode(backend: Option<Backend>) -> usize {
+    match backend {
+        None => 0,
+        Some(Backend::Fallback) => 1,
+        Some(Backend::Sse2) => 2,
+        Some(Backend::Avx2) => 3,
+        Some(Backend::Neon) => 4,
+        Some(Backend::Simd128) => 5,
+        Some(Backend::Portable) => 6,
+    }
+}
+
+#[cfg(feature = "std")]
+fn decode(value: usize) -> Option<Backend> {
+    match value {
+        1 => Some(Backend::Fallback),
+        2 => Some(Backend::Sse2),
+        3 => Some(Backend::Avx2),
+        4 => Some(Backend::Neon),
+        5 => Some(Backend::Simd128),
+        6 => Some(Backend::Portable),
+        _ => None,
+    }
+}
+
+/// Make the routines select their backend again on their next call.
+#[cfg(feature = "std")]
+fn reset_dispatch() {
+    #[cfg(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(target_feature = "avx2"),
+    ))]
+    {
+        crate::bitmask::x86::reset();
+        crate::escape::x86::reset();
+        crate::memchr::x86::reset();
+        crate::mempair::x86::reset();
+        crate::mismatch::x86::reset();
+        crate::memrun::x86::reset();
+    }
+}
+
+/// Returns whether AVX2 can be used.
+///
+/// When this crate is compiled with AVX2 enabled, this is `true` without any
+/// runtime detection, even without the `std` feature. Otherwise, without the
+/// `std` feature, this uses CPUID when the `cpuid` feature is enabled.
+#[inline(always)]
+pub(crate) fn avx2_detected() -> bool {
+    if cfg!(all(target_feature = "avx2", memchr_runtime_avx)) {
+        return true;
+    }
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        feature = "std"
+    ))]
+    {
+        cfg!(memchr_runtime_avx) && is_x86_feature_detected!("avx2")
+    }
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        not(feature = "std"),
+        feature = "cpuid",
+        not(target_env = "sgx"),
+    ))]
+    {
+        cfg!(memchr_runtime_avx) && cpuid::avx2()
+    }
+    #[cfg(not(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        any(feature = "std", all(feature = "cpuid", not(target_env = "sgx")),),
+    )))]
+    {
+        false
+    }
+}
+
+/// This macro employs a gcc-like "ifunc" trick where by upon first calling
+/// `memchr` (for example), CPU feature detection will be performed at runtime
+/// to determine the best implementation to use. After CPU feature detection
+/// is done, we replace `memchr`'s function pointer with the selection. Upon
+/// subsequent invocations, the CPU-specific routine is invoked directly, which
+/// skips the CPU feature detection and subsequent branch that's required.
+///
+/// While this typically doesn't matter for rare occurrences or when used on
+/// larger haystacks, `memchr` can be called in tight loops where the overhead
+/// of this branch can actually add up *and is measurable*. This trick was
+/// necessary to bring this implementation up to glibc's speeds for the 'tiny'
+/// benchmarks, for example.
+///
+/// At some point, I expect the Rust ecosystem will get a nice macro for doing
+/// exactly this, at which point, we can replace our hand-jammed version of it.
+///
+/// N.B. The ifunc strategy does prevent function inlining of course, but
+/// on modern CPUs, you'll probably end up with the AVX2 implementation,
+/// which probably can't be inlined anyway---unless you've compiled your
+/// entire program with AVX2 enabled. However, even then, the various memchr
+/// implementations aren't exactly small, so inlining might not help anyway!
+///
+/// Unlike a true ifunc, the selection can be changed later with
+/// `set_backend`. To support that, the function pointers are statics at
+/// module level rather than local to each routine, and the macro defines a
+/// `reset` function in the invoking module that points all of them back at
+/// their detection routine. `reset_dispatch` must call it. The detection
+/// routine asks which backend is selected, which is either the best one
+/// available, or one chosen by the `MEMCHR_BACKEND` environment variable or
+/// by `set_backend`. Without std, which is only possible with the cpuid
+/// feature, the selection is always the best backend available.
+///
+/// Every x86 module with SSE2 and AVX2 routines uses this macro, so that they
+/// all follow the same selection. The invoking module must have `avx`, `sse2`
+/// and `fallback` modules in scope, each with a routine for every `$name`.
+///
+/// # Safety
+///
+/// Callers must ensure that the routines with each name have the signature
+/// given for that name. (The vectorized ones are `unsafe` because they
+/// require target features.)
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd,
+    memchr_avx2,
+    not(target_feature = "avx2"),
+))]
+macro_rules! unsafe_ifunc {
+    ($(
+        $fnptr:ident, $detect:ident,
+        fn $name:ident($($arg:ident: $argty:ty),+ $(,)?) $(-> $ret:ty)?;
+    )+) => {
+        $(
+            static $fnptr: core::sync::atomic::AtomicPtr<()> =
+                core::sync::atomic::AtomicPtr::new($detect as *mut ());
+
+            fn $detect($($arg: $argty),+) $(-> $ret)? {
+                use core::sync::atomic::Ordering;
+
+                use $crate::arch::{self, Backend};
+
+                let fun = loop {
+                    let backend = arch::backend();
+                    let fun = match backend {
+                        Backend::Avx2 => avx::$name as *mut (),
+                        Backend::Sse2 => sse2::$name as *mut (),
+                        // Only the backends above and the fallback are
+                        // ever available on x86.
+                        _ => fallback::$name as *mut (),
+                    };
+                    $fnptr.store(fun, Ordering::SeqCst);
+                    // If the backend was changed while we were selecting
+                    // this one, then `reset` may have run before our store,
+                    // in which case we need to select again.
+                    if arch::backend() == backend {
+                        break fun;
+                    }
+                };
+                // SAFETY: By virtue of the caller contract, this is the type
+                // of every routine named $name, and a function pointer is
+                // always safe to transmute with a *mut (). Also, if 'fun is
+                // the AVX routine, then it is guaranteed to be supported
+                // since `arch` only selects available backends.
+                unsafe {
+                    core::mem::transmute::<
+                        *mut (),
+                        unsafe fn($($argty),+) $(-> $ret)?,
+                    >(fun)($($arg),+)
+                }
+            }
+
+            #[inline(always)]
+            pub(crate) fn $name($($arg: $argty),+) $(-> $ret)? {
+                use core::sync::atomic::Ordering;
+
+                // SAFETY: $fnptr only ever holds the detection routine or a
+                // routine stored by it, so the same reasoning applies.
+                unsafe {
+                    let fun = $fnptr.load(Ordering::Relaxed);
+                    core::mem::transmute::<
+                        *mut (),
+                        unsafe fn($($argty),+) $(-> $ret)?,
+                    >(fun)($($arg),+)
+                }
+            }
+        )+
+
+        /// Point every routine back at its detection routine, so that the
+        /// backend is selected again on the next call.
+        #[cfg(feature = "std")]
+        pub(crate) fn reset() {
+            $(
+                $fnptr.store(
+                    $detect as *mut (),
+                    core::sync::atomic::Ordering::SeqCst,
+                );
+            )+
+        }
+    }
+}
+
+/// When AVX2 is enabled at compile time, such as with `-C target-cpu=native`,
+/// then call our AVX2 routine directly. No CPU feature detection is needed,
+/// and the routine can be inlined.
+///
+/// Otherwise, when neither std nor the cpuid feature is available to provide
+/// runtime CPU feature detection, then just call our optimized SSE2 routine
+/// directly. SSE2 is available on all x86_64 targets, and the x86 modules are
+/// only compiled for x86 targets that enable it, so no CPU feature detection
+/// is necessary. (Unless runtime SIMD has been explicitly disabled, in which
+/// case the fallback is called.)
+///
+/// # Safety
+///
+/// There are no safety requirements for this definition of the macro. It is
+/// safe for all inputs since it is restricted to routines whose target
+/// features are enabled at compile time.
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd,
+    any(not(memchr_avx2), target_feature = "avx2"),
+))]
+macro_rules! unsafe_ifunc {
+    ($(
+        $fnptr:ident, $detect:ident,
+        fn $name:ident($($arg:ident: $argty:ty),+ $(,)?) $(-> $ret:ty)?;
+    )+) => {
+        $(
+            #[inline(always)]
+            pub(crate) fn $name($($arg: $argty),+) $(-> $ret)? {
+                #[cfg(all(target_feature = "avx2", memchr_runtime_avx))]
+                {
+                    // SAFETY: AVX2 is enabled at compile time, so the CPU
+                    // running this code must support it.
+                    unsafe { avx::$name($($arg),+) }
+                }
+                #[cfg(not(all(target_feature = "avx2", memchr_runtime_avx)))]
+                {
+                    if cfg!(memchr_runtime_sse2) {
+                        // SAFETY: SSE2 is enabled at compile time.
+                        unsafe { sse2::$name($($arg),+) }
+                    } else {
+                        fallback::$name($($arg),+)
+                    }
+                }
+            }
+        )+
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use super::{backend, fixed_backend, reset_backend, set_backend, Backend};
+
+    const ALL: [Backend; 6] = [
+        Backend::Fallback,
+        Backend::Sse2,
+        Backend::Avx2,
+        Backend::Neon,
+        Backend::Simd128,
+        Backend::Portable,
+    ];
+
+    // All tests that change the backend are in this one test, since tests run
+    // in parallel.
+    #[test]
+    fn set_and_reset() {
+        let haystack =
+            b"xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxabcxxxxxxxxxxxx";
+        for &b in &ALL {
+            let fixed = fixed_backend().unwrap_or(b) != b;
+            if !b.is_available() || fixed {
+                assert!(set_backend(b).is_err());
+                continue;
+            }
+            set_backend(b).unwrap();
+            assert_eq!(b, backend());
+            assert_eq!(Some(41), crate::memchr(b'a', haystack));
+            assert_eq!(Some(42), crate::memchr2(b'b', b'c', haystack));
+            assert_eq!(Some(43), crate::memrchr3(b'c', b'b', b'a', haystack));
+        }
+        reset_backend();
+        assert!(backend().is_available());
+    }
+
+    #[test]
+    fn names() {
+        for &b in &ALL {
+            assert_eq!(Some(b), Backend::from_name(b.name()));
+            assert_eq!(b.name(), b.to_string());
+        }
+        assert!(Backend::Fallback.is_available());
+        assert!(Backend::best().is_available());
+    }
+
+    #[test]
+    fn kernels() {
+        type Kernels = [fn(u8, u8, u8, &[u8]) -> Option<usize>; 2];
+        // On targets without a vector implementation, nothing is pushed.
+        #[allow(unused_mut)]
+        let mut all: Vec<Kernels> = vec![[
+            |a, b, c, h| super::fallback::memchr3(a, b, c, h),
+            |a, b, c, h| super::fallback::memrchr3(a, b, c, h),
+        ]];
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            #[cfg(target_arch = "x86")]
+            use super::x86;
+            #[cfg(target_arch = "x86_64")]
+            use super::x86_64 as x86;
+
+            if x86::sse2::is_available() {
+                all.push([
+                    |a, b, c, h| x86::sse2::memchr3(a, b, c, h),
+                    |a, b, c, h| x86::sse2::memrchr3(a, b, c, h),
+                ]);
+            }
+            if x86::avx2::is_available() {
+                all.push([
+                    |a, b, c, h| x86::avx2::memchr3(a, b, c, h),
+                    |a, b, c, h| x86::avx2::memrchr3(a, b, c, h),
+                ]);
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if super::aarch64::neon::is_available() {
+                all.push([
+                    |a, b, c, h| super::aarch64::neon::memchr3(a, b, c, h),
+                    |a, b, c, h| super::aarch64::neon::memrchr3(a, b, c, h),
+                ]);
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            if super::wasm32::simd128::is_available() {
+                all.push([
+                    |a, b, c, h| super::wasm32::simd128::memchr3(a, b, c, h),
+                    |a, b, c, h| super::wasm32::simd128::memrchr3(a, b, c, h),
+                ]);
+            }
+        }
+        #[cfg(feature = "portable-simd")]
+        {
+            if super::portable::is_available() {
+                all.push([
+                    |a, b, c, h| super::portable::memchr3(a, b, c, h),
+                    |a, b, c, h| super::portable::memrchr3(a, b, c, h),
+                ]);
+            }
+        }
+        let haystack: Vec<u8> =
+            (0..300u32).map(|i| (i * 7 % 251) as u8).collect();
+        for [forward, reverse] in all {
+            for start in 0..70 {
+                let h = &haystack[start..];
+                for &(a, b, c) in &[(0, 1, 2), (250, 7, 100), (255, 255, 255)]
+                {
+                    let naive = |&x: &u8| x == a || x == b || x == c;
+                    assert_eq!(h.iter().position(naive), forward(a, b, c, h));
+                    assert_eq!(h.iter().rposition(naive), reverse(a, b, c, h));
+                }
+            }
+        }
+    }
+}
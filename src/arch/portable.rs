@@ -0,0 +1,41 @@
+/*!
+The implementation of the `memchr` family of routines using the portable
+vectors of `core::simd`, which work on 16 bytes at a time.
+
+This module only exists when the `portable-simd` feature is enabled, which
+requires a nightly compiler.
+*/
+
+use crate::arch::Backend;
+
+/// Returns whether this implementation can be used.
+///
+/// This is only `true` on targets for which this crate has no other vector
+/// implementation, or when that implementation has been disabled when
+/// building this crate. It is `false` under Miri.
+pub fn is_available() -> bool {
+    Backend::Portable.is_available()
+}
+
+macro_rules! call {
+    ($name:ident, $haystack:ident, $($needle:ident),+) => {{
+        assert!(
+            is_available(),
+            "the portable memchr backend is not available"
+        );
+        if $haystack.is_empty() {
+            return None;
+        }
+        #[cfg(all(not(miri), memchr_portable_simd))]
+        {
+            crate::memchr::portable::$name($($needle),+, $haystack)
+        }
+        #[cfg(not(all(not(miri), memchr_portable_simd)))]
+        {
+            let _ = ($($needle),+);
+            unreachable!()
+        }
+    }};
+}
+
+define_kernels!(call);
@@ -0,0 +1,9 @@
+/*!
+The implementations of the `memchr` family of routines that use `wasm32`
+vector instructions.
+
+Each sub-module has an `is_available` function. Calling a routine from a
+sub-module whose `is_available` returns `false` panics.
+*/
+
+pub mod simd128;
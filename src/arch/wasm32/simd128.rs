@@ -0,0 +1,40 @@
+/*!
+The implementation of the `memchr` family of routines using the WebAssembly
+`simd128` vector instructions, which work on 16 bytes at a time.
+*/
+
+use crate::arch::Backend;
+
+/// Returns whether this implementation can be used.
+///
+/// This is `false` unless the `simd128` target feature is enabled when
+/// building this crate, e.g., with `RUSTFLAGS="-C target-feature=+simd128"`.
+/// It is also `false` when SIMD has been disabled when building this crate,
+/// when the compiler is older than Rust 1.54, and under Miri.
+pub fn is_available() -> bool {
+    Backend::Simd128.is_available()
+}
+
+macro_rules! call {
+    ($name:ident, $haystack:ident, $($needle:ident),+) => {{
+        assert!(is_available(), "the simd128 memchr backend is not available");
+        if $haystack.is_empty() {
+            return None;
+        }
+        #[cfg(all(not(miri), memchr_runtime_simd128))]
+        {
+            // SAFETY: We checked that this crate was built with simd128
+            // enabled.
+            unsafe {
+                crate::memchr::wasm32::simd128::$name($($needle),+, $haystack)
+            }
+        }
+        #[cfg(not(all(not(miri), memchr_runtime_simd128)))]
+        {
+            let _ = ($($needle),+);
+            unreachable!()
+        }
+    }};
+}
+
+define_kernels!(call);
@@ -0,0 +1,44 @@
+/*!
+The implementation of the `memchr` family of routines using AVX2 vector
+instructions, which work on 32 bytes at a time.
+*/
+
+use crate::arch::Backend;
+
+/// Returns whether this implementation can be used.
+///
+/// Besides requiring a CPU that supports AVX2, this is `false` when neither
+/// the `std` nor the `cpuid` feature is enabled to detect it, unless this
+/// crate was compiled with AVX2 enabled.
+pub fn is_available() -> bool {
+    Backend::Avx2.is_available()
+}
+
+macro_rules! call {
+    ($name:ident, $haystack:ident, $($needle:ident),+) => {{
+        assert!(is_available(), "the AVX2 memchr backend is not available");
+        if $haystack.is_empty() {
+            return None;
+        }
+        #[cfg(all(
+            not(miri),
+            memchr_runtime_simd,
+            memchr_avx2,
+        ))]
+        {
+            // SAFETY: We checked that the CPU supports AVX2 above.
+            unsafe { crate::memchr::x86::avx::$name($($needle),+, $haystack) }
+        }
+        #[cfg(not(all(
+            not(miri),
+            memchr_runtime_simd,
+            memchr_avx2,
+        )))]
+        {
+            let _ = ($($needle),+);
+            unreachable!()
+        }
+    }};
+}
+
+define_kernels!(call);
@@ -0,0 +1,10 @@
+/*!
+The implementations of the `memchr` family of routines that use `x86_64` (or
+`x86`) vector instructions.
+
+Each sub-module has an `is_available` function. Calling a routine from a
+sub-module whose `is_available` returns `false` panics.
+*/
+
+pub mod avx2;
+pub mod sse2;
@@ -0,0 +1,37 @@
+/*!
+The implementation of the `memchr` family of routines using SSE2 vector
+instructions, which work on 16 bytes at a time.
+*/
+
+use crate::arch::Backend;
+
+/// Returns whether this implementation can be used.
+///
+/// This is `false` when SIMD has been disabled when building this crate, and
+/// under Miri.
+pub fn is_available() -> bool {
+    Backend::Sse2.is_available()
+}
+
+macro_rules! call {
+    ($name:ident, $haystack:ident, $($needle:ident),+) => {{
+        assert!(is_available(), "the SSE2 memchr backend is not available");
+        if $haystack.is_empty() {
+            return None;
+        }
+        #[cfg(all(not(miri), memchr_runtime_simd))]
+        {
+            // SAFETY: This crate is only built to use SSE2 on targets that
+            // enable it, which includes all x86_64 targets, and we checked
+            // that it was built to use it.
+            unsafe { crate::memchr::x86::sse2::$name($($needle),+, $haystack) }
+        }
+        #[cfg(not(all(not(miri), memchr_runtime_simd)))]
+        {
+            let _ = ($($needle),+);
+            unreachable!()
+        }
+    }};
+}
+
+define_kernels!(call);
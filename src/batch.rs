@@ -0,0 +1,436 @@
+/*!
+This module provides routines for extracting every match in a haystack at
+once, instead of driving an iterator one match at a time.
+
+Every routine comes in two forms:
+
+* A `_fill` form, which writes match offsets into a fixed caller provided
+  slice. It returns the number of offsets written along with a resume point:
+  when the slice fills up before the search is complete, the resume point is
+  the offset of the next match, which can be passed back in as the starting
+  position of the next call.
+* An `_all_into` form, which appends every match offset to a `Vec`. (Only
+  when the `std` feature is enabled.)
+
+Offsets may be written as `usize`, `u64` or `u32`, via the [`Offset`] trait.
+This makes it possible to fill columnar buffers of 32-bit offsets directly.
+
+The routines for single bytes compute a match bitmask for each 64 byte block
+of the haystack using the same vectorized kernels as the [`bitmask`] module,
+and then expand the set bits of each mask into offsets in a tight loop. The
+equivalent routines for substrings live on
+[`memmem::Finder`](crate::memmem::Finder).
+
+[`bitmask`]: crate::bitmask
+
+# Example: collecting line offsets
+
+```
+use memchr::batch;
+
+# #[cfg(feature = "std")] {
+let haystack = b"foo\nbar\nbaz\n";
+let mut offsets: Vec<u32> = vec![];
+batch::memchr_all_into(b'\n', haystack, &mut offsets);
+assert_eq!(offsets, vec![3, 7, 11]);
+# }
+```
+
+# Example: filling a fixed buffer
+
+```
+use memchr::batch;
+
+let haystack = b"a,b,c,d,e";
+let mut buf = [0usize; 3];
+let (n, resume) = batch::memchr_fill(b',', haystack, 0, &mut buf);
+assert_eq!((3, Some(7)), (n, resume));
+assert_eq!([1, 3, 5], buf);
+
+let (n, resume) = batch::memchr_fill(b',', haystack, 7, &mut buf);
+assert_eq!((1, None), (n, resume));
+assert_eq!(7, buf[0]);
+```
+*/
+
+use crate::bitmask;
+
+/// The number of 64 byte blocks whose masks are computed at a time.
+const WINDOW_BLOCKS: usize = 64;
+
+/// A type that match offsets can be written as.
+///
+/// This is implemented for `usize`, `u64` and `u32`. It is sealed, so it
+/// cannot be implemented outside of this crate.
+///
+/// This is also the type of the offsets array accepted by the columnar
+/// routines on [`memmem::Finder`](crate::memmem::Finder), such as
+/// [`rows_containing`](crate::memmem::Finder::rows_containing).
+pub trait Offset: Copy + private::Sealed {}
+
+impl Offset for usize {}
+impl Offset for u64 {}
+impl Offset for u32 {}
+
+mod private {
+    use core::convert::TryFrom;
+
+    pub trait Sealed {
+        /// Convert a match offset to this type.
+        ///
+        /// # Panics
+        ///
+        /// When the offset does not fit in this type.
+        fn from_offset(offset: usize) -> Self;
+
+        /// Convert this offset to a `usize`.
+        ///
+        /// # Panics
+        ///
+        /// When this offset does not fit in a `usize`.
+        fn to_offset(self) -> usize;
+    }
+
+    impl Sealed for usize {
+        #[inline(always)]
+        fn from_offset(offset: usize) -> usize {
+            offset
+        }
+
+        #[inline(always)]
+        fn to_offset(self) -> usize {
+            self
+        }
+    }
+
+    impl Sealed for u64 {
+        #[inline(always)]
+        fn from_offset(offset: usize) -> u64 {
+            offset as u64
+        }
+
+        #[inline(always)]
+        fn to_offset(self) -> usize {
+            usize::try_from(self).expect("offset does not fit in usize")
+        }
+    }
+
+    impl Sealed for u32 {
+        #[inline(always)]
+        fn from_offset(offset: usize) -> u32 {
+            u32::try_from(offset).expect("match offset does not fit in u32")
+        }
+
+        #[inline(always)]
+        fn to_offset(self) -> usize {
+            usize::try_from(self).expect("offset does not fit in usize")
+        }
+    }
+}
+
+/// Write the offsets of `n1` in `haystack`, starting the search at `start`,
+/// to `out`.
+///
+/// This returns the number of offsets written, followed by `None` if every
+/// match was written, or `Some(i)` if `out` filled up, where `i` is the
+/// offset of the next match. Passing `i` back as `start` continues the
+/// search.
+///
+/// # Panics
+///
+/// When `start > haystack.len()`, or when a match offset does not fit in `T`.
+#[inline]
+pub fn memchr_fill<T: Offset>(
+    n1: u8,
+    haystack: &[u8],
+    start: usize,
+    out: &mut [T],
+) -> (usize, Option<usize>) {
+    fill(&[n1], haystack, start, out)
+}
+
+/// Like [`memchr_fill`], but for offsets of either `n1` or `n2`.
+///
+/// # Panics
+///
+/// When `start > haystack.len()`, or when a match offset does not fit in `T`.
+#[inline]
+pub fn memchr2_fill<T: Offset>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+    start: usize,
+    out: &mut [T],
+) -> (usize, Option<usize>) {
+    fill(&[n1, n2], haystack, start, out)
+}
+
+/// Like [`memchr_fill`], but for offsets of any of `n1`, `n2` or `n3`.
+///
+/// # Panics
+///
+/// When `start > haystack.len()`, or when a match offset does not fit in `T`.
+#[inline]
+pub fn memchr3_fill<T: Offset>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+    start: usize,
+    out: &mut [T],
+) -> (usize, Option<usize>) {
+    fill(&[n1, n2, n3], haystack, start, out)
+}
+
+/// Append the offsets of every occurrence of `n1` in `haystack` to `out`.
+///
+/// This returns the number of offsets appended.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Panics
+///
+/// When a match offset does not fit in `T`.
+#[cfg(feature = "std")]
+#[inline]
+pub fn memchr_all_into<T: Offset>(
+    n1: u8,
+    haystack: &[u8],
+    out: &mut Vec<T>,
+) -> usize {
+    all_into(&[n1], haystack, out)
+}
+
+/// Like [`memchr_all_into`], but for offsets of either `n1` or `n2`.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Panics
+///
+/// When a match offset does not fit in `T`.
+#[cfg(feature = "std")]
+#[inline]
+pub fn memchr2_all_into<T: Offset>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+    out: &mut Vec<T>,
+) -> usize {
+    all_into(&[n1, n2], haystack, out)
+}
+
+/// Like [`memchr_all_into`], but for offsets of any of `n1`, `n2` or `n3`.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Panics
+///
+/// When a match offset does not fit in `T`.
+#[cfg(feature = "std")]
+#[inline]
+pub fn memchr3_all_into<T: Offset>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+    out: &mut Vec<T>,
+) -> usize {
+    all_into(&[n1, n2, n3], haystack, out)
+}
+
+/// Convert a match offset to the given offset type.
+#[inline(always)]
+pub(crate) fn offset<T: Offset>(offset: usize) -> T {
+    <T as private::Sealed>::from_offset(offset)
+}
+
+/// Convert an offset of the given type to a `usize`.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) fn to_usize<T: Offset>(offset: T) -> usize {
+    <T as private::Sealed>::to_offset(offset)
+}
+
+fn fill<T: Offset>(
+    bytes: &[u8],
+    haystack: &[u8],
+    start: usize,
+    out: &mut [T],
+) -> (usize, Option<usize>) {
+    assert!(start <= haystack.len(), "start must be in bounds of haystack");
+    let mut masks = [0u64; WINDOW_BLOCKS];
+    let mut written = 0;
+    let mut at = start;
+    while at < haystack.len() {
+        let end = core::cmp::min(haystack.len(), at + 64 * WINDOW_BLOCKS);
+        let count = bitmask::fill_bytes(bytes, &haystack[at..end], &mut masks);
+        for (k, &mask) in masks[..count].iter().enumerate() {
+            let base = at + 64 * k;
+            let mut mask = mask;
+            while mask != 0 {
+                let i = base + mask.trailing_zeros() as usize;
+                if written == out.len() {
+                    return (written, Some(i));
+                }
+                out[written] = offset(i);
+                written += 1;
+                mask &= mask - 1;
+            }
+        }
+        at = end;
+    }
+    (written, None)
+}
+
+#[cfg(feature = "std")]
+fn all_into<T: Offset>(
+    bytes: &[u8],
+    haystack: &[u8],
+    out: &mut Vec<T>,
+) -> usize {
+    let old_len = out.len();
+    let mut masks = [0u64; WINDOW_BLOCKS];
+    let mut at = 0;
+    while at < haystack.len() {
+        let end = core::cmp::min(haystack.len(), at + 64 * WINDOW_BLOCKS);
+        let count = bitmask::fill_bytes(bytes, &haystack[at..end], &mut masks);
+        for (k, &mask) in masks[..count].iter().enumerate() {
+            let base = at + 64 * k;
+            let mut mask = mask;
+            out.reserve(mask.count_ones() as usize);
+            while mask != 0 {
+                out.push(offset(base + mask.trailing_zeros() as usize));
+                mask &= mask - 1;
+            }
+        }
+        at = end;
+    }
+    out.len() - old_len
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{
+        memchr2_all_into, memchr2_fill, memchr3_all_into, memchr3_fill,
+        memchr_all_into, memchr_fill,
+    };
+    use crate::memmem::Finder;
+
+    /// Collect every offset by repeatedly calling the given fill routine
+    /// with a buffer of the given size.
+    fn drain(
+        size: usize,
+        fill: impl Fn(usize, &mut [u32]) -> (usize, Option<usize>),
+    ) -> Vec<usize> {
+        let mut buf = vec![0u32; size];
+        let mut all = vec![];
+        let mut start = 0;
+        loop {
+            let (n, resume) = fill(start, &mut buf);
+            assert!(n <= size);
+            all.extend(buf[..n].iter().map(|&i| i as usize));
+            match resume {
+                None => return all,
+                Some(i) => {
+                    assert_eq!(n, size);
+                    start = i;
+                }
+            }
+            if size == 0 {
+                return all;
+            }
+        }
+    }
+
+    #[test]
+    fn long_haystack() {
+        let haystack: Vec<u8> = (0..20_000).map(|i| (i % 251) as u8).collect();
+        let expected: Vec<usize> =
+            crate::memchr3_iter(3, 250, 99, &haystack).collect();
+        let mut got: Vec<usize> = vec![];
+        memchr3_all_into(3, 250, 99, &haystack, &mut got);
+        assert_eq!(expected, got);
+        for &size in &[1, 7, 64, 1000] {
+            let got = drain(size, |start, buf| {
+                memchr3_fill(3, 250, 99, &haystack, start, buf)
+            });
+            assert_eq!(expected, got);
+        }
+    }
+
+    #[test]
+    fn empty_buffer() {
+        assert_eq!((0, Some(1)), memchr_fill::<u32>(b'a', b"ba", 0, &mut []));
+        assert_eq!((0, None), memchr_fill::<u32>(b'a', b"bb", 0, &mut []));
+        assert_eq!((0, None), memchr_fill::<u32>(b'a', b"ba", 2, &mut []));
+    }
+
+    #[test]
+    fn appends() {
+        let mut out = vec![99usize];
+        assert_eq!(2, memchr_all_into(b'a', b"abca", &mut out));
+        assert_eq!(vec![99, 0, 3], out);
+    }
+
+    #[test]
+    fn finder() {
+        let haystack = b"aaaaa";
+        let finder = Finder::new("aa");
+        let mut out: Vec<u64> = vec![];
+        assert_eq!(2, finder.find_all_into(haystack, &mut out));
+        assert_eq!(vec![0, 2], out);
+
+        let mut buf = [0u32; 1];
+        assert_eq!((1, Some(2)), finder.find_fill(haystack, 0, &mut buf));
+        assert_eq!((1, None), finder.find_fill(haystack, 2, &mut buf));
+        assert_eq!(2, buf[0]);
+
+        let mut out: Vec<usize> = vec![];
+        Finder::new("").find_all_into(b"ab", &mut out);
+        assert_eq!(vec![0, 1, 2], out);
+    }
+
+    quickcheck! {
+        fn qc_memchr(haystack: Vec<u8>, n1: u8, n2: u8, size: usize) -> bool {
+            let size = size % 10 + 1;
+            let expected1: Vec<usize> = crate::memchr_iter(n1, &haystack).collect();
+            let expected2: Vec<usize> =
+                crate::memchr2_iter(n1, n2, &haystack).collect();
+
+            let mut got1: Vec<usize> = vec![];
+            memchr_all_into(n1, &haystack, &mut got1);
+            let mut got2: Vec<u32> = vec![];
+            memchr2_all_into(n1, n2, &haystack, &mut got2);
+            let got2: Vec<usize> = got2.iter().map(|&i| i as usize).collect();
+
+            let drained1 = drain(size, |start, buf| {
+                memchr_fill(n1, &haystack, start, buf)
+            });
+            let drained2 = drain(size, |start, buf| {
+                memchr2_fill(n1, n2, &haystack, start, buf)
+            });
+            expected1 == got1
+                && expected2 == got2
+                && expected1 == drained1
+                && expected2 == drained2
+        }
+
+        fn qc_finder(haystack: Vec<u8>, needle: Vec<u8>, size: usize) -> bool {
+            // Use a small alphabet so that matches are likely.
+            let haystack: Vec<u8> = haystack.iter().map(|&b| b % 2).collect();
+            let needle: Vec<u8> =
+                needle.iter().take(3).map(|&b| b % 2).collect();
+            let size = size % 10 + 1;
+            let finder = Finder::new(&needle);
+            let expected: Vec<usize> = finder.find_iter(&haystack).collect();
+            let mut got: Vec<usize> = vec![];
+            finder.find_all_into(&haystack, &mut got);
+            let drained = drain(size, |start, buf| {
+                finder.find_fill(&haystack, start, buf)
+            });
+            expected == got && expected == drained
+        }
+    }
+}
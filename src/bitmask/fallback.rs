@@ -0,0 +1,22 @@
+// This module defines a pure Rust platform independent implementation of the
+// bitmask routines. It looks up each byte of the haystack in a byte set and
+// sets the corresponding bit of the mask one byte at a time.
+
+use crate::ByteSet;
+
+/// Fill `masks` with a bit for each byte of `haystack` that is equal to one
+/// of `bytes`. `masks` must have room for every block of the haystack.
+pub fn fill(bytes: &[u8], haystack: &[u8], masks: &mut [u64]) {
+    fill_set(&ByteSet::from_bytes(bytes), haystack, masks)
+}
+
+/// Fill `masks` with a bit for each byte of `haystack` that is in `set`.
+/// `masks` must have room for every block of the haystack.
+pub fn fill_set(set: &ByteSet, haystack: &[u8], masks: &mut [u64]) {
+    for (block, mask) in haystack.chunks(64).zip(masks.iter_mut()) {
+        *mask = 0;
+        for (i, &b) in block.iter().enumerate() {
+            *mask |= (set.contains(b) as u64) << i;
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use core::mem::size_of;
+
+use crate::{bitmask::MAX_VECTOR_BYTES, memmem::vector::Vector};
+
+/// Fill `masks` with a bit for each byte of `haystack` that is equal to one
+/// of `bytes`, which must contain between 1 and `MAX_VECTOR_BYTES` bytes.
+/// `masks` must have room for every block of the haystack.
+///
+/// Each 64 byte block of the haystack is compared with a vector of every
+/// byte in `bytes`, and the movemask of each vector is shifted into place in
+/// the block's mask. The final block, if partial, is copied into a zero
+/// padded buffer and the bits past the end of the haystack are cleared.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute.
+/// Therefore, callers must ensure that whatever target feature is being used
+/// supports the vector functions that this function is specialized for. (For
+/// the specific vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn fill<V: Vector>(
+    bytes: &[u8],
+    haystack: &[u8],
+    masks: &mut [u64],
+) {
+    debug_assert!(!bytes.is_empty() && bytes.len() <= MAX_VECTOR_BYTES);
+    let mut needles = [V::splat(0); MAX_VECTOR_BYTES];
+    for (n, &b) in needles.iter_mut().zip(bytes) {
+        *n = V::splat(b);
+    }
+    let needles = &needles[..bytes.len()];
+
+    let blocks = haystack.chunks_exact(64);
+    let rest = blocks.remainder();
+    let mut masks = masks.iter_mut();
+    for (block, mask) in blocks.zip(&mut masks) {
+        *mask = block_mask(needles, block.as_ptr());
+    }
+    if !rest.is_empty() {
+        let mut buf = [0u8; 64];
+        buf[..rest.len()].copy_from_slice(rest);
+        let all = (1u64 << rest.len()) - 1;
+        *masks.next().unwrap() = block_mask(needles, buf.as_ptr()) & all;
+    }
+}
+
+/// Returns the mask of the bytes in the 64 byte block at `ptr` that are equal
+/// to one of the given needle vectors.
+#[inline(always)]
+unsafe fn block_mask<V: Vector>(needles: &[V], ptr: *const u8) -> u64 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < 64 {
+        let chunk = V::load_unaligned(ptr.add(i));
+        let mut eq = chunk.cmpeq(needles[0]);
+        for &n in &needles[1..] {
+            eq = eq.or(chunk.cmpeq(n));
+        }
+        mask |= (eq.movemask() as u64) << i;
+        i += size_of::<V>();
+    }
+    mask
+}
@@ -0,0 +1,355 @@
+/*!
+This module provides routines for computing match bitmasks over a haystack.
+
+Instead of reporting the positions of matching bytes one at a time, the
+routines in this module produce a `u64` for every 64 byte block of the
+haystack, where bit `i` of the mask for block `k` is set if and only if the
+byte at position `64 * k + i` matches. This is the "structural index"
+representation used by simdjson-style parsers: a tokenizer can combine masks
+with cheap bitwise operations, and then walk the set bits of the result.
+
+The masks are written to a caller provided `&mut [u64]`, which must have
+room for at least [`mask_count(haystack.len())`](mask_count) masks. Bits
+corresponding to positions past the end of the haystack are always zero.
+
+# Example
+
+This shows how to find the structural characters of a JSON document:
+
+```
+use memchr::{bitmask, ByteSet};
+
+let haystack = br#"{"a": [1, 2], "b": {}}"#;
+let mut masks = vec![0; bitmask::mask_count(haystack.len())];
+bitmask::fill_set(&ByteSet::from_bytes(b"{}[]:,\"\\"), haystack, &mut masks);
+
+let positions: Vec<usize> =
+    (0..64).filter(|&i| masks[0] & (1 << i) != 0).collect();
+assert_eq!(
+    positions,
+    vec![0, 1, 3, 4, 6, 8, 11, 12, 14, 16, 17, 19, 20, 21],
+);
+```
+*/
+
+use crate::ByteSet;
+
+#[cfg_attr(all(target_feature = "avx2", memchr_runtime_avx), allow(dead_code))]
+mod fallback;
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+mod genericsimd;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+pub(crate) mod x86;
+
+#[cfg(not(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+)))]
+use self::fallback as imp;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+use self::x86 as imp;
+
+/// The maximum number of distinct bytes that are compared with vectors. Sets
+/// with more bytes than this (whose complements are also too big) are looked
+/// up one byte at a time.
+pub(crate) const MAX_VECTOR_BYTES: usize = 16;
+
+/// Returns the number of masks needed to cover a haystack of the given
+/// length, i.e., the number of 64 byte blocks, rounding up.
+///
+/// # Example
+///
+/// ```
+/// use memchr::bitmask;
+///
+/// assert_eq!(0, bitmask::mask_count(0));
+/// assert_eq!(1, bitmask::mask_count(64));
+/// assert_eq!(2, bitmask::mask_count(65));
+/// ```
+#[inline]
+pub fn mask_count(haystack_len: usize) -> usize {
+    haystack_len / 64 + ((haystack_len & 63) != 0) as usize
+}
+
+/// Fill `masks` with the positions of `n1` in `haystack`.
+///
+/// Bit `i` of `masks[k]` is set if and only if `haystack[64 * k + i] == n1`.
+/// This returns the number of masks written, which is always
+/// `mask_count(haystack.len())`. Any masks past that are left untouched.
+///
+/// # Panics
+///
+/// When `masks.len()` is less than `mask_count(haystack.len())`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::bitmask;
+///
+/// let mut masks = [0; 1];
+/// assert_eq!(1, bitmask::fill(b'\n', b"a\nb\n", &mut masks));
+/// assert_eq!(0b1010, masks[0]);
+/// ```
+#[inline]
+pub fn fill(n1: u8, haystack: &[u8], masks: &mut [u64]) -> usize {
+    fill_bytes(&[n1], haystack, masks)
+}
+
+/// Like [`fill`], but sets the bit for positions equal to either `n1` or
+/// `n2`.
+///
+/// # Panics
+///
+/// When `masks.len()` is less than `mask_count(haystack.len())`.
+#[inline]
+pub fn fill2(n1: u8, n2: u8, haystack: &[u8], masks: &mut [u64]) -> usize {
+    fill_bytes(&[n1, n2], haystack, masks)
+}
+
+/// Like [`fill`], but sets the bit for positions equal to any of `n1`, `n2`
+/// or `n3`.
+///
+/// # Panics
+///
+/// When `masks.len()` is less than `mask_count(haystack.len())`.
+#[inline]
+pub fn fill3(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+    masks: &mut [u64],
+) -> usize {
+    fill_bytes(&[n1, n2, n3], haystack, masks)
+}
+
+/// Like [`fill`], but sets the bit for positions whose byte is in `set`.
+///
+/// Sets with a small number of bytes, or whose complement has a small number
+/// of bytes, are searched with vector comparisons. Other sets are looked up
+/// one byte at a time.
+///
+/// # Panics
+///
+/// When `masks.len()` is less than `mask_count(haystack.len())`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{bitmask, ByteSet};
+///
+/// let mut masks = [0; 1];
+/// bitmask::fill_set(&ByteSet::range(b'0', b'9'), b"a1b22", &mut masks);
+/// assert_eq!(0b11010, masks[0]);
+/// ```
+#[inline]
+pub fn fill_set(set: &ByteSet, haystack: &[u8], masks: &mut [u64]) -> usize {
+    let count = check_len(haystack, masks);
+    let masks = &mut masks[..count];
+    if set.is_empty() {
+        for mask in masks.iter_mut() {
+            *mask = 0;
+        }
+    } else if set.len() <= MAX_VECTOR_BYTES {
+        let (bytes, len) = set_bytes(set);
+        imp::fill(&bytes[..len], haystack, masks);
+    } else if set.complement().len() <= MAX_VECTOR_BYTES {
+        let complement = set.complement();
+        if complement.is_empty() {
+            for mask in masks.iter_mut() {
+                *mask = !0;
+            }
+        } else {
+            let (bytes, len) = set_bytes(&complement);
+            imp::fill(&bytes[..len], haystack, masks);
+            for mask in masks.iter_mut() {
+                *mask = !*mask;
+            }
+        }
+        let rest = haystack.len() & 63;
+        if rest != 0 {
+            masks[count - 1] &= (1 << rest) - 1;
+        }
+    } else {
+        fallback::fill_set(set, haystack, masks);
+    }
+    count
+}
+
+/// Fill `masks` for a list of between 1 and `MAX_VECTOR_BYTES` bytes.
+#[inline(always)]
+pub(crate) fn fill_bytes(
+    bytes: &[u8],
+    haystack: &[u8],
+    masks: &mut [u64],
+) -> usize {
+    let count = check_len(haystack, masks);
+    imp::fill(bytes, haystack, &mut masks[..count]);
+    count
+}
+
+/// Returns the number of occurrences in `haystack` of any of a list of
+/// between 1 and `MAX_VECTOR_BYTES` bytes.
+pub(crate) fn count_bytes(bytes: &[u8], haystack: &[u8]) -> usize {
+    let mut masks = [0u64; 64];
+    haystack
+        .chunks(64 * masks.len())
+        .map(|window| {
+            let n = fill_bytes(bytes, window, &mut masks);
+            masks[..n].iter().map(|m| m.count_ones() as usize).sum::<usize>()
+        })
+        .sum()
+}
+
+/// Returns the number of masks needed for the haystack, and panics if there
+/// isn't enough room for them.
+#[inline(always)]
+fn check_len(haystack: &[u8], masks: &[u64]) -> usize {
+    let count = mask_count(haystack.len());
+    assert!(
+        masks.len() >= count,
+        "need {} masks for a haystack of length {}, but only {} were given",
+        count,
+        haystack.len(),
+        masks.len(),
+    );
+    count
+}
+
+/// Returns the bytes in the given set, which must have at most
+/// `MAX_VECTOR_BYTES` bytes, along with how many there are.
+#[inline(always)]
+fn set_bytes(set: &ByteSet) -> ([u8; MAX_VECTOR_BYTES], usize) {
+    let mut bytes = [0; MAX_VECTOR_BYTES];
+    let mut len = 0;
+    for b in set.iter() {
+        bytes[len] = b;
+        len += 1;
+    }
+    (bytes, len)
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{fallback, fill, fill2, fill3, fill_set, mask_count};
+    use crate::ByteSet;
+
+    fn naive(set: &ByteSet, haystack: &[u8]) -> Vec<u64> {
+        let mut masks = vec![0; mask_count(haystack.len())];
+        for (i, &b) in haystack.iter().enumerate() {
+            if set.contains(b) {
+                masks[i / 64] |= 1 << (i % 64);
+            }
+        }
+        masks
+    }
+
+    /// Run the given routine on haystacks of every length up to a few blocks,
+    /// for needle lists of every supported size, and compare it with a naive
+    /// implementation.
+    fn run_tests(fill: impl Fn(&[u8], &[u8], &mut [u64])) {
+        let needles: Vec<u8> = (0..16).map(|i| i * 3).collect();
+        for len in 0..200 {
+            let haystack: Vec<u8> =
+                (0..len).map(|i| (i * 7 % 50) as u8).collect();
+            for n in 1..=needles.len() {
+                let bytes = &needles[..n];
+                let mut masks = vec![!0; mask_count(len) + 1];
+                fill(bytes, &haystack, &mut masks[..mask_count(len)]);
+                let expected = naive(&ByteSet::from_bytes(bytes), &haystack);
+                assert_eq!(&expected[..], &masks[..mask_count(len)]);
+                assert_eq!(!0, masks[mask_count(len)]);
+            }
+        }
+    }
+
+    #[test]
+    fn fill_auto() {
+        run_tests(super::imp::fill);
+    }
+
+    #[test]
+    fn fill_fallback() {
+        run_tests(fallback::fill);
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
+    #[test]
+    fn fill_sse2() {
+        run_tests(|b, h, m| unsafe { super::x86::sse2::fill(b, h, m) });
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_avx
+    ))]
+    #[test]
+    fn fill_avx2() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        run_tests(|b, h, m| unsafe { super::x86::avx::fill(b, h, m) });
+    }
+
+    #[test]
+    fn fill_set_sizes() {
+        let haystack: Vec<u8> = (0..=255u8).chain(0..=255u8).collect();
+        let sets = [
+            ByteSet::empty(),
+            ByteSet::full(),
+            ByteSet::range(b'a', b'z'),
+            ByteSet::range(1, 254),
+            ByteSet::range(0, 200),
+        ];
+        for set in &sets {
+            for len in &[0, 1, 63, 64, 65, 300, haystack.len()] {
+                let haystack = &haystack[..*len];
+                let mut masks = vec![0; mask_count(haystack.len())];
+                fill_set(set, haystack, &mut masks);
+                assert_eq!(naive(set, haystack), masks, "{:?} {}", set, len);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_few_masks() {
+        fill(b'a', &[b'a'; 65], &mut [0; 1]);
+    }
+
+    quickcheck! {
+        fn qc_fill(haystack: Vec<u8>, n1: u8, n2: u8, n3: u8) -> bool {
+            let count = mask_count(haystack.len());
+            let mut masks = vec![0; count];
+            let set1 = ByteSet::singleton(n1);
+            let mut set3 = ByteSet::from_bytes(&[n1, n2, n3]);
+            fill(n1, &haystack, &mut masks);
+            let ok1 = masks == naive(&set1, &haystack);
+            fill2(n1, n2, &haystack, &mut masks);
+            let ok2 = masks == naive(&ByteSet::from_bytes(&[n1, n2]), &haystack);
+            fill3(n1, n2, n3, &haystack, &mut masks);
+            let ok3 = masks == naive(&set3, &haystack);
+            set3 = set3.complement();
+            fill_set(&set3, &haystack, &mut masks);
+            let ok4 = masks == naive(&set3, &haystack);
+            ok1 && ok2 && ok3 && ok4
+        }
+    }
+}
@@ -0,0 +1,17 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m256i;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::__m256i;
+
+use crate::bitmask::genericsimd;
+
+/// An AVX2 accelerated routine for filling match masks.
+///
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn fill(bytes: &[u8], haystack: &[u8], masks: &mut [u64]) {
+    genericsimd::fill::<__m256i>(bytes, haystack, masks)
+}
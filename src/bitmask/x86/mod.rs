@@ -0,0 +1,14 @@
+#[cfg(not(all(target_feature = "avx2", memchr_runtime_avx)))]
+use crate::bitmask::fallback;
+
+// We only use AVX when we can detect at runtime whether it's available, which
+// requires std or the cpuid feature, or when it's enabled at compile time.
+#[cfg(memchr_avx2)]
+pub(crate) mod avx;
+#[cfg_attr(all(target_feature = "avx2", memchr_runtime_avx), allow(dead_code))]
+pub(crate) mod sse2;
+
+unsafe_ifunc! {
+    FILL, fill_detect,
+    fn fill(bytes: &[u8], haystack: &[u8], masks: &mut [u64]);
+}
@@ -0,0 +1,246 @@
+use core::fmt;
+
+/// A set of bytes, represented as a 256-bit bitset.
+///
+/// Byte sets are used to describe the positions of class needles in
+/// [`memmem::ClassFinder`](crate::memmem::ClassFinder), where each position
+/// in the needle may match one of several bytes.
+///
+/// # Example
+///
+/// ```
+/// use memchr::ByteSet;
+///
+/// let mut set = ByteSet::range(b'0', b'9');
+/// set.insert(b'_');
+/// assert!(set.contains(b'5'));
+/// assert!(set.contains(b'_'));
+/// assert!(!set.contains(b'a'));
+/// assert_eq!(11, set.len());
+/// ```
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct ByteSet {
+    bits: [u64; 4],
+}
+
+impl ByteSet {
+    /// Create a set that contains no bytes.
+    #[inline]
+    pub const fn empty() -> ByteSet {
+        ByteSet { bits: [0; 4] }
+    }
+
+    /// Create a set that contains every byte.
+    #[inline]
+    pub const fn full() -> ByteSet {
+        ByteSet { bits: [!0; 4] }
+    }
+
+    /// Create a set that contains only the given byte.
+    #[inline]
+    pub fn singleton(byte: u8) -> ByteSet {
+        let mut set = ByteSet::empty();
+        set.insert(byte);
+        set
+    }
+
+    /// Create a set containing each of the given bytes.
+    #[inline]
+    pub fn from_bytes<B: ?Sized + AsRef<[u8]>>(bytes: &B) -> ByteSet {
+        let mut set = ByteSet::empty();
+        for &b in bytes.as_ref() {
+            set.insert(b);
+        }
+        set
+    }
+
+    /// Create a set containing every byte in the inclusive range
+    /// `start..=end`. If `start > end`, then the set is empty.
+    #[inline]
+    pub fn range(start: u8, end: u8) -> ByteSet {
+        let mut set = ByteSet::empty();
+        if start <= end {
+            for b in start..=end {
+                set.insert(b);
+            }
+        }
+        set
+    }
+
+    /// Create a set containing every byte `b` such that
+    /// `b & mask == value & mask`.
+    ///
+    /// This is useful for describing bytes where only some bits are known,
+    /// such as a nibble wildcard like `4?` in a hex signature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::ByteSet;
+    ///
+    /// let set = ByteSet::masked(0x40, 0xF0);
+    /// assert_eq!(ByteSet::range(0x40, 0x4F), set);
+    /// ```
+    #[inline]
+    pub fn masked(value: u8, mask: u8) -> ByteSet {
+        let mut set = ByteSet::empty();
+        for b in 0..=255u8 {
+            if b & mask == value & mask {
+                set.insert(b);
+            }
+        }
+        set
+    }
+
+    /// Add the given byte to this set.
+    #[inline]
+    pub fn insert(&mut self, byte: u8) {
+        self.bits[(byte / 64) as usize] |= 1 << (byte % 64);
+    }
+
+    /// Remove the given byte from this set.
+    #[inline]
+    pub fn remove(&mut self, byte: u8) {
+        self.bits[(byte / 64) as usize] &= !(1 << (byte % 64));
+    }
+
+    /// Returns true if and only if the given byte is in this set.
+    #[inline]
+    pub fn contains(&self, byte: u8) -> bool {
+        self.bits[(byte / 64) as usize] & (1 << (byte % 64)) != 0
+    }
+
+    /// Returns the number of bytes in this set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns true if and only if this set contains no bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bits == [0; 4]
+    }
+
+    /// Returns true if and only if this set contains every byte.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.bits == [!0; 4]
+    }
+
+    /// If this set contains exactly one byte, then return it.
+    #[inline]
+    pub fn single(&self) -> Option<u8> {
+        if self.len() != 1 {
+            return None;
+        }
+        self.iter().next()
+    }
+
+    /// Returns the union of this set and `other`.
+    #[inline]
+    pub fn union(&self, other: &ByteSet) -> ByteSet {
+        let mut set = *self;
+        for (a, b) in set.bits.iter_mut().zip(other.bits.iter()) {
+            *a |= *b;
+        }
+        set
+    }
+
+    /// Returns the set of all bytes not in this set.
+    #[inline]
+    pub fn complement(&self) -> ByteSet {
+        let mut set = *self;
+        for a in set.bits.iter_mut() {
+            *a = !*a;
+        }
+        set
+    }
+
+    /// Returns an iterator over the bytes in this set, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> ByteSetIter<'_> {
+        ByteSetIter { set: self, next: 0 }
+    }
+}
+
+impl fmt::Debug for ByteSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut set = f.debug_set();
+        for b in self.iter() {
+            set.entry(&DebugByte(b));
+        }
+        set.finish()
+    }
+}
+
+/// An iterator over the bytes in a [`ByteSet`], in ascending order.
+#[derive(Clone, Debug)]
+pub struct ByteSetIter<'a> {
+    set: &'a ByteSet,
+    // A u16 so that we can represent the end of iteration.
+    next: u16,
+}
+
+impl<'a> Iterator for ByteSetIter<'a> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        while self.next <= 0xFF {
+            let b = self.next as u8;
+            self.next += 1;
+            if self.set.contains(b) {
+                return Some(b);
+            }
+        }
+        None
+    }
+}
+
+/// A byte that is formatted as an escaped character.
+struct DebugByte(u8);
+
+impl fmt::Debug for DebugByte {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'")?;
+        for c in core::ascii::escape_default(self.0) {
+            write!(f, "{}", c as char)?;
+        }
+        write!(f, "'")
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::ByteSet;
+
+    #[test]
+    fn basics() {
+        let mut set = ByteSet::empty();
+        assert!(set.is_empty());
+        assert_eq!(None, set.single());
+        set.insert(0);
+        set.insert(255);
+        set.insert(64);
+        assert_eq!(vec![0, 64, 255], set.iter().collect::<Vec<u8>>());
+        set.remove(64);
+        assert_eq!(2, set.len());
+        set.remove(0);
+        assert_eq!(Some(255), set.single());
+        assert_eq!(255, set.complement().len());
+        assert!(ByteSet::full().is_full());
+        assert_eq!(256, ByteSet::full().iter().count());
+        assert!(ByteSet::range(2, 1).is_empty());
+        assert_eq!(ByteSet::full(), ByteSet::masked(0, 0));
+        let nibble: Vec<u8> = ByteSet::masked(0x0D, 0x0F).iter().collect();
+        assert_eq!(16, nibble.len());
+        assert!(nibble.iter().all(|&b| b & 0x0F == 0x0D));
+    }
+
+    #[test]
+    fn debug() {
+        let set = ByteSet::from_bytes(b"a\n");
+        assert_eq!(r"{'\n', 'a'}", format!("{:?}", set));
+    }
+}
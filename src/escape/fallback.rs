@@ -0,0 +1,67 @@
+// This module defines a pure Rust platform independent implementation of
+// escape byte search. It uses the same word-at-a-time approach as the memchr
+// fallback routines to skip over chunks of the haystack that contain no bytes
+// from the escape set.
+
+use crate::{
+    escape::EscapeSet,
+    memchr::fallback::{contains_zero_byte, repeat_byte, USIZE_BYTES},
+};
+
+/// The high bit of every byte in a word.
+const HI_USIZE: usize = (0x8080808080808080u64) as usize;
+
+pub fn find(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+    let start_ptr = haystack.as_ptr();
+    let mut ptr = start_ptr;
+
+    unsafe {
+        let end_ptr = start_ptr.add(haystack.len());
+        // The "has less than" trick below only works when the threshold is
+        // at most 128. Beyond that, a word test would report false positives
+        // and negatives, so we just do a byte-at-a-time search. In practice,
+        // escape sets use small thresholds, like 0x20 for JSON.
+        if set.below() <= 0x80 && haystack.len() >= USIZE_BYTES {
+            let vbelow = repeat_byte(set.below());
+            let mut vbytes = [0usize; crate::escape::MAX_ESCAPE_BYTES];
+            for (v, &b) in vbytes.iter_mut().zip(set.bytes()) {
+                *v = repeat_byte(b);
+            }
+            let vbytes = &vbytes[..set.bytes().len()];
+
+            while ptr <= end_ptr.sub(USIZE_BYTES) {
+                let chunk = (ptr as *const usize).read_unaligned();
+                if has_less(chunk, vbelow)
+                    || vbytes.iter().any(|&v| contains_zero_byte(chunk ^ v))
+                {
+                    break;
+                }
+                ptr = ptr.add(USIZE_BYTES);
+            }
+        }
+        while ptr < end_ptr {
+            if set.contains(*ptr) {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.add(1);
+        }
+        None
+    }
+}
+
+/// Returns true if any byte in `x` is less than the byte repeated in `vn`.
+///
+/// This is the same borrow propagation trick used by `contains_zero_byte`,
+/// which is in fact the special case of `vn` repeating the byte `1`. It is
+/// only correct when the repeated byte is at most `0x80`.
+#[inline(always)]
+fn has_less(x: usize, vn: usize) -> bool {
+    x.wrapping_sub(vn) & !x & HI_USIZE != 0
+}
+
+/// Subtract `b` from `a` and return the difference. `a` should be greater than
+/// or equal to `b`.
+fn sub(a: *const u8, b: *const u8) -> usize {
+    debug_assert!(a >= b);
+    (a as usize) - (b as usize)
+}
@@ -0,0 +1,111 @@
+use core::mem::size_of;
+
+use crate::{
+    escape::{EscapeSet, MAX_ESCAPE_BYTES},
+    memmem::vector::Vector,
+};
+
+/// An escape set with each of its bytes broadcast into a vector.
+#[derive(Clone, Copy, Debug)]
+struct VectorSet<V> {
+    /// When the threshold is non-zero, this contains `below - 1` in every
+    /// lane. A lane `x` is below the threshold when `min(x, below - 1) == x`.
+    below: Option<V>,
+    /// The specific bytes of the escape set. Only the first `len` are valid.
+    bytes: [V; MAX_ESCAPE_BYTES],
+    len: usize,
+}
+
+impl<V: Vector> VectorSet<V> {
+    #[inline(always)]
+    unsafe fn new(set: &EscapeSet) -> VectorSet<V> {
+        let below = match set.below() {
+            0 => None,
+            below => Some(V::splat(below - 1)),
+        };
+        let mut bytes = [V::splat(0); MAX_ESCAPE_BYTES];
+        for (v, &b) in bytes.iter_mut().zip(set.bytes()) {
+            *v = V::splat(b);
+        }
+        VectorSet { below, bytes, len: set.bytes().len() }
+    }
+
+    /// Returns a mask with a bit set for every lane in `chunk` that is a
+    /// member of this set.
+    #[inline(always)]
+    unsafe fn matches(&self, chunk: V) -> u32 {
+        let mut eq = match self.below {
+            None => V::splat(0),
+            Some(below) => chunk.min_u8(below).cmpeq(chunk),
+        };
+        for &v in &self.bytes[..self.len] {
+            eq = eq.or(chunk.cmpeq(v));
+        }
+        eq.movemask()
+    }
+}
+
+/// Search for the first byte in `haystack` that is in the given escape set.
+///
+/// This is a simpler version of the memchr3 kernels, generalized to an
+/// arbitrary number of bytes and to a "less than" threshold. The haystack is
+/// scanned one vector at a time with unaligned loads, and the final vector
+/// overlaps with the previous one when the haystack length isn't a multiple
+/// of the vector size. Since every byte in the overlap was already rejected,
+/// the first match in the final vector is always the correct answer.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute.
+/// Therefore, callers must ensure that whatever target feature is being used
+/// supports the vector functions that this function is specialized for. (For
+/// the specific vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn find<V: Vector>(
+    set: &EscapeSet,
+    haystack: &[u8],
+) -> Option<usize> {
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < size_of::<V>() {
+        while ptr < end_ptr {
+            if set.contains(*ptr) {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.add(1);
+        }
+        return None;
+    }
+
+    let vset = VectorSet::<V>::new(set);
+    while ptr <= end_ptr.sub(size_of::<V>()) {
+        let mask = vset.matches(V::load_unaligned(ptr));
+        if mask != 0 {
+            return Some(sub(ptr, start_ptr) + forward_pos(mask));
+        }
+        ptr = ptr.add(size_of::<V>());
+    }
+    if ptr < end_ptr {
+        ptr = end_ptr.sub(size_of::<V>());
+        let mask = vset.matches(V::load_unaligned(ptr));
+        if mask != 0 {
+            return Some(sub(ptr, start_ptr) + forward_pos(mask));
+        }
+    }
+    None
+}
+
+/// Compute the position of the first matching byte from the given mask.
+fn forward_pos(mask: u32) -> usize {
+    mask.trailing_zeros() as usize
+}
+
+/// Subtract `b` from `a` and return the difference. `a` should be greater than
+/// or equal to `b`.
+fn sub(a: *const u8, b: *const u8) -> usize {
+    debug_assert!(a >= b);
+    (a as usize) - (b as usize)
+}
@@ -0,0 +1,513 @@
+/*!
+This module provides routines for finding bytes that need escaping and for
+writing escaped strings.
+
+Serializing a string into formats like JSON or HTML is usually dominated by
+one question: where is the next byte that must be escaped? The bytes between
+two such positions can be copied verbatim, which is much cheaper than
+inspecting every byte one at a time. The routines in this module answer that
+question using the same vectorized techniques that power `memchr3`, and then
+build escaping writers on top of them.
+
+The set of bytes that need escaping is described by an [`EscapeSet`]. This
+crate provides sets for JSON, HTML and POSIX shell quoting, but any set that
+fits the model (all bytes below some threshold, plus a handful of specific
+bytes) can be searched with [`find_next_escape`]. This makes it possible to
+plug in other formats without reimplementing the search itself.
+
+# Example: finding the next byte to escape
+
+```
+use memchr::escape::{self, EscapeSet};
+
+let haystack = b"foo \"bar\" baz";
+assert_eq!(Some(4), escape::find_next_escape(&EscapeSet::JSON, haystack));
+assert_eq!(Some(4), escape::find_next_escape_json(haystack));
+assert_eq!(None, escape::find_next_escape_html(b"no markup here"));
+```
+
+# Example: escaping a string
+
+This requires the `std` feature.
+
+```
+use memchr::escape;
+
+# #[cfg(feature = "std")] {
+let mut out = vec![];
+escape::escape_json(b"say \"hi\"\n", &mut out).unwrap();
+assert_eq!(&out[..], &b"say \\\"hi\\\"\\n"[..]);
+
+let mut out = vec![];
+escape::escape_html(b"<a href='x'>&</a>", &mut out).unwrap();
+assert_eq!(&out[..], &b"&lt;a href=&#39;x&#39;&gt;&amp;&lt;/a&gt;"[..]);
+# }
+```
+*/
+
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg_attr(all(target_feature = "avx2", memchr_runtime_avx), allow(dead_code))]
+mod fallback;
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+mod genericsimd;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+pub(crate) mod x86;
+
+/// The maximum number of specific bytes that an [`EscapeSet`] may contain.
+///
+/// This limit exists so that an escape set can be stored inline and so that
+/// the vectorized search does a bounded amount of work per chunk of haystack.
+pub const MAX_ESCAPE_BYTES: usize = 8;
+
+/// A set of bytes that need escaping.
+///
+/// An escape set is made up of two parts: a threshold, where every byte
+/// strictly less than the threshold is considered part of the set, and a small
+/// list of specific bytes. At most [`MAX_ESCAPE_BYTES`] specific bytes may be
+/// given.
+///
+/// For example, JSON requires escaping all bytes less than `0x20` along with
+/// `"` and `\`, which corresponds to `EscapeSet::new(0x20, b"\"\\")`.
+///
+/// Escape sets are cheap to copy. The built-in sets [`JSON`](Self::JSON),
+/// [`HTML`](Self::HTML) and [`SHELL`](Self::SHELL) are associated constants.
+#[derive(Clone, Copy, Debug)]
+pub struct EscapeSet {
+    /// Every byte less than this is a member of the set. When this is zero,
+    /// no byte satisfies this part of the set.
+    below: u8,
+    /// The specific bytes in this set. Only the first `len` are valid.
+    bytes: [u8; MAX_ESCAPE_BYTES],
+    /// The number of valid bytes in `bytes`.
+    len: u8,
+}
+
+impl EscapeSet {
+    /// The bytes that must be escaped inside a JSON string: everything below
+    /// `0x20`, `"` and `\`.
+    pub const JSON: EscapeSet = EscapeSet {
+        below: 0x20,
+        bytes: [b'"', b'\\', 0, 0, 0, 0, 0, 0],
+        len: 2,
+    };
+
+    /// The bytes that must be escaped in HTML text and quoted attribute
+    /// values: `<`, `>`, `&`, `'` and `"`.
+    pub const HTML: EscapeSet = EscapeSet {
+        below: 0,
+        bytes: [b'<', b'>', b'&', b'\'', b'"', 0, 0, 0],
+        len: 5,
+    };
+
+    /// The bytes that must be escaped inside a single quoted POSIX shell
+    /// string. Only the single quote itself is special there.
+    pub const SHELL: EscapeSet =
+        EscapeSet { below: 0, bytes: [b'\'', 0, 0, 0, 0, 0, 0, 0], len: 1 };
+
+    /// Create a new escape set containing every byte less than `below`, and
+    /// every byte in `bytes`.
+    ///
+    /// If `below` is `0`, then only the bytes given are in the set.
+    ///
+    /// # Panics
+    ///
+    /// When more than [`MAX_ESCAPE_BYTES`] bytes are given.
+    pub fn new(below: u8, bytes: &[u8]) -> EscapeSet {
+        assert!(
+            bytes.len() <= MAX_ESCAPE_BYTES,
+            "an escape set can contain at most {} bytes, but {} were given",
+            MAX_ESCAPE_BYTES,
+            bytes.len(),
+        );
+        let mut set =
+            EscapeSet { below, bytes: [0; MAX_ESCAPE_BYTES], len: 0 };
+        set.bytes[..bytes.len()].copy_from_slice(bytes);
+        set.len = bytes.len() as u8;
+        set
+    }
+
+    /// Returns true if and only if the given byte is in this set.
+    #[inline]
+    pub fn contains(&self, byte: u8) -> bool {
+        byte < self.below || self.bytes().contains(&byte)
+    }
+
+    /// Returns the threshold of this set. Every byte less than the value
+    /// returned is in this set.
+    #[inline]
+    pub fn below(&self) -> u8 {
+        self.below
+    }
+
+    /// Returns the specific bytes in this set, not including the bytes
+    /// covered by the threshold.
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+/// Returns the index of the first byte in `haystack` that is in the given
+/// escape set, or `None` if no such byte exists.
+///
+/// This is operationally the same as
+/// `haystack.iter().position(|&b| set.contains(b))`, but uses vectorized
+/// routines when available.
+///
+/// # Example
+///
+/// This shows how to describe a custom format, in this case CSV fields, and
+/// search for the first byte that requires quoting.
+///
+/// ```
+/// use memchr::escape::{find_next_escape, EscapeSet};
+///
+/// let csv = EscapeSet::new(0, b"\",\r\n");
+/// assert_eq!(Some(5), find_next_escape(&csv, b"hello, world"));
+/// assert_eq!(None, find_next_escape(&csv, b"hello world"));
+/// ```
+#[inline]
+pub fn find_next_escape(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+        haystack.iter().position(|&b| set.contains(b))
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
+    #[inline(always)]
+    fn imp(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+        x86::find(set, haystack)
+    }
+
+    #[cfg(all(
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+        fallback::find(set, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(set, haystack)
+    }
+}
+
+/// Returns the index of the first byte in `haystack` that must be escaped
+/// inside a JSON string.
+///
+/// This is equivalent to `find_next_escape(&EscapeSet::JSON, haystack)`.
+#[inline]
+pub fn find_next_escape_json(haystack: &[u8]) -> Option<usize> {
+    find_next_escape(&EscapeSet::JSON, haystack)
+}
+
+/// Returns the index of the first byte in `haystack` that must be escaped
+/// in HTML.
+///
+/// This is equivalent to `find_next_escape(&EscapeSet::HTML, haystack)`.
+#[inline]
+pub fn find_next_escape_html(haystack: &[u8]) -> Option<usize> {
+    find_next_escape(&EscapeSet::HTML, haystack)
+}
+
+/// Write `input` to `wtr`, calling `replace` for every byte in `set`.
+///
+/// Runs of bytes that aren't in `set` are written to `wtr` verbatim with a
+/// single call. For every byte in `set`, `replace` is called with that byte
+/// and is responsible for writing its escaped form.
+///
+/// This is the building block used by [`escape_json`] and [`escape_html`],
+/// and can be used to implement escaping for other formats.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+///
+/// use memchr::escape::{escape_with, EscapeSet};
+///
+/// let mut out = vec![];
+/// escape_with(&EscapeSet::new(0, b"%"), b"100% sure", &mut out, |_, w| {
+///     w.write_all(b"%%")
+/// }).unwrap();
+/// assert_eq!(&out[..], &b"100%% sure"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn escape_with<W, F>(
+    set: &EscapeSet,
+    input: &[u8],
+    mut wtr: W,
+    mut replace: F,
+) -> io::Result<()>
+where
+    W: io::Write,
+    F: FnMut(u8, &mut W) -> io::Result<()>,
+{
+    let mut input = input;
+    while let Some(i) = find_next_escape(set, input) {
+        if i > 0 {
+            wtr.write_all(&input[..i])?;
+        }
+        replace(input[i], &mut wtr)?;
+        input = &input[i + 1..];
+    }
+    if !input.is_empty() {
+        wtr.write_all(input)?;
+    }
+    Ok(())
+}
+
+/// Write `input` to `wtr` escaped for use inside a JSON string.
+///
+/// The surrounding quotes are not written. `"` and `\` are escaped with a
+/// backslash, the control characters `\b`, `\f`, `\n`, `\r` and `\t` use
+/// their short forms and all other bytes below `0x20` are written as `\u00XX`.
+/// All other bytes, including bytes that are not valid UTF-8, are copied
+/// verbatim. Callers that need to guarantee valid JSON should ensure `input`
+/// is valid UTF-8.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn escape_json<W: io::Write>(input: &[u8], wtr: W) -> io::Result<()> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    escape_with(&EscapeSet::JSON, input, wtr, |b, wtr| match b {
+        b'"' => wtr.write_all(b"\\\""),
+        b'\\' => wtr.write_all(b"\\\\"),
+        b'\x08' => wtr.write_all(b"\\b"),
+        b'\x0C' => wtr.write_all(b"\\f"),
+        b'\n' => wtr.write_all(b"\\n"),
+        b'\r' => wtr.write_all(b"\\r"),
+        b'\t' => wtr.write_all(b"\\t"),
+        b => {
+            let hi = HEX[(b >> 4) as usize];
+            let lo = HEX[(b & 0xF) as usize];
+            wtr.write_all(&[b'\\', b'u', b'0', b'0', hi, lo])
+        }
+    })
+}
+
+/// Write `input` to `wtr` escaped for use in HTML text or in a quoted
+/// attribute value.
+///
+/// `<`, `>`, `&`, `'` and `"` are replaced by `&lt;`, `&gt;`, `&amp;`,
+/// `&#39;` and `&quot;`, respectively. All other bytes are copied verbatim.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn escape_html<W: io::Write>(input: &[u8], wtr: W) -> io::Result<()> {
+    escape_with(&EscapeSet::HTML, input, wtr, |b, wtr| match b {
+        b'<' => wtr.write_all(b"&lt;"),
+        b'>' => wtr.write_all(b"&gt;"),
+        b'&' => wtr.write_all(b"&amp;"),
+        b'\'' => wtr.write_all(b"&#39;"),
+        b'"' => wtr.write_all(b"&quot;"),
+        _ => unreachable!("byte {:?} is not in the HTML escape set", b),
+    })
+}
+
+/// Write `input` to `wtr` as a single quoted POSIX shell word.
+///
+/// The output is always wrapped in single quotes, even when `input` is empty
+/// or contains no special bytes. Every `'` in `input` is written as `'\''`,
+/// which closes the quoted string, writes an escaped quote and reopens it.
+/// Since no other byte is special inside single quotes, everything else is
+/// copied verbatim.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn escape_shell<W: io::Write>(input: &[u8], mut wtr: W) -> io::Result<()> {
+    wtr.write_all(b"'")?;
+    escape_with(&EscapeSet::SHELL, input, &mut wtr, |_, wtr| {
+        wtr.write_all(b"'\\''")
+    })?;
+    wtr.write_all(b"'")
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{
+        escape_html, escape_json, escape_shell, fallback, find_next_escape,
+        EscapeSet,
+    };
+
+    fn naive(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+        haystack.iter().position(|&b| set.contains(b))
+    }
+
+    /// Run the given search routine on every suffix of every haystack in a
+    /// small set of generated inputs, and compare it with a naive search.
+    fn run_tests(mut find: impl FnMut(&EscapeSet, &[u8]) -> Option<usize>) {
+        let sets = [
+            EscapeSet::JSON,
+            EscapeSet::HTML,
+            EscapeSet::SHELL,
+            EscapeSet::new(0, b""),
+            EscapeSet::new(0x80, b""),
+            EscapeSet::new(0xFF, b"\xFF"),
+            EscapeSet::new(3, b"abcdefgh"),
+        ];
+        for set in sets.iter() {
+            for len in 0..130 {
+                let mut haystack = vec![b'z'; len];
+                assert_eq!(
+                    naive(set, &haystack),
+                    find(set, &haystack),
+                    "set: {:?}",
+                    set,
+                );
+                for i in 0..len {
+                    for &b in &[b'"', b'\\', b'<', b'\'', 0, 0x1F, b'h', 0xFF]
+                    {
+                        haystack[i] = b;
+                        for start in 0..core::cmp::min(len, 34) {
+                            let h = &haystack[start..];
+                            assert_eq!(
+                                naive(set, h),
+                                find(set, h),
+                                "set: {:?}, haystack: {:?}",
+                                set,
+                                h,
+                            );
+                        }
+                        haystack[i] = b'z';
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn find_auto() {
+        run_tests(find_next_escape);
+    }
+
+    #[test]
+    fn find_fallback() {
+        run_tests(fallback::find);
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
+    #[test]
+    fn find_sse2() {
+        run_tests(|set, haystack| unsafe {
+            super::x86::sse2::find(set, haystack)
+        });
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_avx
+    ))]
+    #[test]
+    fn find_avx2() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        run_tests(|set, haystack| unsafe {
+            super::x86::avx::find(set, haystack)
+        });
+    }
+
+    quickcheck! {
+        fn qc_find_matches_naive(
+            below: u8,
+            bytes: Vec<u8>,
+            haystack: Vec<u8>
+        ) -> bool {
+            let set = EscapeSet::new(below, &bytes[..bytes.len().min(8)]);
+            naive(&set, &haystack) == find_next_escape(&set, &haystack)
+        }
+
+        fn qc_fallback_matches_naive(
+            below: u8,
+            bytes: Vec<u8>,
+            haystack: Vec<u8>
+        ) -> bool {
+            let set = EscapeSet::new(below, &bytes[..bytes.len().min(8)]);
+            naive(&set, &haystack) == fallback::find(&set, &haystack)
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_bytes() {
+        EscapeSet::new(0, b"123456789");
+    }
+
+    fn json(input: &[u8]) -> String {
+        let mut out = vec![];
+        escape_json(input, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn html(input: &[u8]) -> String {
+        let mut out = vec![];
+        escape_html(input, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn shell(input: &[u8]) -> String {
+        let mut out = vec![];
+        escape_shell(input, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn escape_json_simple() {
+        assert_eq!("", json(b""));
+        assert_eq!("abc", json(b"abc"));
+        assert_eq!("\\\"", json(b"\""));
+        assert_eq!("a\\\\b", json(b"a\\b"));
+        assert_eq!("\\b\\f\\n\\r\\t", json(b"\x08\x0C\n\r\t"));
+        assert_eq!("\\u0000\\u001f\\u000b", json(b"\x00\x1F\x0B"));
+        assert_eq!("\u{2603} \\\"\u{2603}\\\"", json("☃ \"☃\"".as_bytes()));
+        let long = "x".repeat(100) + "\n" + &"y".repeat(100);
+        let expected = "x".repeat(100) + "\\n" + &"y".repeat(100);
+        assert_eq!(expected, json(long.as_bytes()));
+    }
+
+    #[test]
+    fn escape_html_simple() {
+        assert_eq!("", html(b""));
+        assert_eq!("abc", html(b"abc"));
+        assert_eq!("&lt;&gt;&amp;&#39;&quot;", html(b"<>&'\""));
+        assert_eq!("a &lt;b&gt; c", html(b"a <b> c"));
+    }
+
+    #[test]
+    fn escape_shell_simple() {
+        assert_eq!("''", shell(b""));
+        assert_eq!("'abc'", shell(b"abc"));
+        assert_eq!("'a b$c'", shell(b"a b$c"));
+        assert_eq!("''\\'''", shell(b"'"));
+        assert_eq!("'it'\\''s'", shell(b"it's"));
+    }
+}
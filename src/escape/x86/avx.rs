@@ -0,0 +1,21 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{__m128i, __m256i};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::escape::{genericsimd, EscapeSet};
+
+/// An AVX2 accelerated search for the first byte in an escape set.
+///
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn find(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+    if haystack.len() < 32 {
+        genericsimd::find::<__m128i>(set, haystack)
+    } else {
+        genericsimd::find::<__m256i>(set, haystack)
+    }
+}
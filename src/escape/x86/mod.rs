@@ -0,0 +1,15 @@
+#[cfg(not(all(target_feature = "avx2", memchr_runtime_avx)))]
+use crate::escape::fallback;
+use crate::escape::EscapeSet;
+
+// We only use AVX when we can detect at runtime whether it's available, which
+// requires std or the cpuid feature, or when it's enabled at compile time.
+#[cfg(memchr_avx2)]
+pub(crate) mod avx;
+#[cfg_attr(all(target_feature = "avx2", memchr_runtime_avx), allow(dead_code))]
+pub(crate) mod sse2;
+
+unsafe_ifunc! {
+    FIND, find_detect,
+    fn find(set: &EscapeSet, haystack: &[u8]) -> Option<usize>;
+}
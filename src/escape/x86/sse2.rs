@@ -0,0 +1,18 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m128i;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::__m128i;
+
+use crate::escape::{genericsimd, EscapeSet};
+
+/// An SSE2 accelerated search for the first byte in an escape set.
+///
+/// # Safety
+///
+/// Callers must ensure that the sse2 CPU feature is enabled in the current
+/// environment. This feature should be enabled in all x86_64 targets, and
+/// this module is only compiled for x86 targets that enable it.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn find(set: &EscapeSet, haystack: &[u8]) -> Option<usize> {
+    genericsimd::find::<__m128i>(set, haystack)
+}
@@ -9,8 +9,36 @@ This section gives a brief high level overview of what this crate offers.
   in the forward or reverse direction. When searching for more than one byte,
   positions are considered a match if the byte at that position matches any
   of the bytes.
+* The top-level module also provides [`mismatch`] and [`rmismatch`] for
+  finding the first or last position at which two byte strings differ, along
+  with helpers for computing the length of their common prefix or suffix.
+* The top-level module also provides [`One`], [`Two`] and [`Three`], which
+  are prebuilt searchers for one, two or three bytes. They are faster than
+  the routines above when searching many small haystacks for the same bytes.
+* The top-level module also provides [`memchr_pair`] and [`memrchr_pair`]
+  for finding a pair of bytes separated by a fixed distance, such as `\r\n`.
+* The top-level module also provides [`find_run`] and [`rfind_run`] for
+  finding runs of a repeated byte, such as zero-filled padding, along with
+  [`find_any_run`] and [`rfind_any_run`] for runs of any byte.
 * The [`memmem`] sub-module provides forward and reverse substring search
   routines.
+* The [`batch`] sub-module provides routines for extracting every match
+  offset at once into a caller provided buffer, instead of driving an
+  iterator.
+* The [`bitmask`] sub-module provides routines for computing a `u64` match
+  mask for every 64 byte block of a haystack, which is useful for building
+  simdjson-style tokenizers.
+* The `par` sub-module provides parallel searches over large haystacks using
+  Rayon. It is only available when the `rayon` feature is enabled.
+* The [`escape`] sub-module provides routines for finding bytes that need
+  escaping, such as in JSON or HTML strings, along with writers that escape
+  strings using them.
+* The [`arch`] sub-module reports which implementation the `memchr` family of
+  routines selected for the current CPU, allows overriding that choice, and
+  provides direct access to each implementation.
+* The [`transform`] sub-module provides `tr`-style routines for replacing,
+  deleting and squeezing bytes, and for converting `\r\n` line endings, both
+  in place and into a new buffer.
 
 In all such cases, routines operate on `&[u8]` without regard to encoding. This
 is exactly what you want when searching either UTF-8 or arbitrary bytes.
@@ -131,11 +159,15 @@ implementation in the standard library, even if only for searching on UTF-8?
 The reason is that the implementation details for using SIMD in the standard
 library haven't quite been worked out yet.
 
-**NOTE:** Currently, only `x86_64` targets have highly accelerated
-implementations of substring search. For `memchr`, all targets have
-somewhat-accelerated implementations, while only `x86_64` targets have highly
-accelerated implementations. This limitation is expected to be lifted once the
-standard library exposes a platform independent SIMD API.
+**NOTE:** Currently, only `x86_64`, `x86`, `aarch64` and `wasm32` targets
+have highly accelerated implementations of substring search. For `memchr`,
+all targets have somewhat-accelerated implementations, while only `x86_64`,
+`x86`, `aarch64` and `wasm32` targets have highly accelerated
+implementations. (On `x86`, this requires a target with SSE2 enabled, such as
+`i686-unknown-linux-gnu`. On `aarch64`, this requires Rust 1.59 or newer. On
+`wasm32`, this requires Rust 1.54 or newer and enabling the `simd128` target
+feature.) This limitation is expected to be lifted once the standard library
+exposes a platform independent SIMD API.
 
 # Crate features
 
@@ -144,17 +176,27 @@ standard library exposes a platform independent SIMD API.
   from the standard library is runtime SIMD CPU feature detection. This means
   that this feature must be enabled to get AVX accelerated routines. When
   `std` is not enabled, this crate will still attempt to use SSE2 accelerated
-  routines on `x86_64`.
+  routines on `x86_64`, and on `x86` targets with SSE2 enabled.
 * **libc** - When enabled (**not** the default), this library will use your
   platform's libc implementation of `memchr` (and `memrchr` on Linux). This
-  can be useful on non-`x86_64` targets where the fallback implementation in
+  can be useful on non-x86 targets where the fallback implementation in
   this crate is not as good as the one found in your libc. All other routines
   (e.g., `memchr[23]` and substring search) unconditionally use the
   implementation in this crate.
+* **portable-simd** - When enabled (**not** the default), this library will
+  compile implementations built on the unstable `core::simd` API. These are
+  used on targets that have no architecture specific SIMD routines in this
+  crate, in place of the fallback routines. This requires a nightly compiler.
+* **rayon** - When enabled (**not** the default), this provides the `par`
+  module for searching large haystacks in parallel. This also enables `std`.
+* **memmap2** - When enabled (**not** the default),
+  `memmem::Finder::find_iter_file` memory maps regular files instead of
+  reading them into a buffer. This also enables `std`.
 */
 
 #![deny(missing_docs)]
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(all(not(miri), feature = "portable-simd"), feature(portable_simd))]
 // It's not worth trying to gate all code on just miri, so turn off relevant
 // dead code warnings.
 #![cfg_attr(miri, allow(dead_code, unused_macros))]
@@ -168,14 +210,43 @@ standard library exposes a platform independent SIMD API.
 )))]
 compile_error!("memchr currently not supported on non-{16,32,64}");
 
+// These dependencies are renamed in Cargo.toml so that the features of the
+// same name can also enable 'std'. See the comment there.
+#[cfg(feature = "memmap2")]
+extern crate memmap2_dep as memmap2;
+#[cfg(feature = "rayon")]
+extern crate rayon_dep as rayon;
+
+pub use crate::byteset::{ByteSet, ByteSetIter};
 pub use crate::memchr::{
     memchr, memchr2, memchr2_iter, memchr3, memchr3_iter, memchr_iter,
     memrchr, memrchr2, memrchr2_iter, memrchr3, memrchr3_iter, memrchr_iter,
-    Memchr, Memchr2, Memchr3,
+    Memchr, Memchr2, Memchr3, One, OneIter, Three, ThreeIter, Two, TwoIter,
+};
+pub use crate::mempair::{
+    memchr_pair, memchr_pair_iter, memrchr_pair, memrchr_pair_iter, MemchrPair,
+};
+pub use crate::memrun::{
+    find_any_run, find_run, rfind_any_run, rfind_run, run_len,
+};
+pub use crate::mismatch::{
+    common_prefix_len, common_suffix_len, mismatch, rmismatch,
 };
 
+#[macro_use]
+pub mod arch;
+pub mod batch;
+pub mod bitmask;
+mod byteset;
 mod cow;
+pub mod escape;
 mod memchr;
 pub mod memmem;
+mod mempair;
+mod memrun;
+mod mismatch;
+#[cfg(feature = "rayon")]
+pub mod par;
 #[cfg(test)]
 mod tests;
+pub mod transform;
@@ -0,0 +1,42 @@
+// NEON is part of the baseline of all common aarch64 targets, and this module
+// is only compiled when it is enabled at compile time. So unlike on x86_64,
+// there is no runtime detection or dispatch here.
+
+pub mod neon;
+
+macro_rules! unsafe_neon {
+    ($fn:ident, $($arg:ident),+) => {{
+        // SAFETY: NEON is enabled at compile time.
+        unsafe { neon::$fn($($arg),+) }
+    }};
+}
+
+#[inline(always)]
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_neon!(memchr, n1, haystack)
+}
+
+#[inline(always)]
+pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_neon!(memchr2, n1, n2, haystack)
+}
+
+#[inline(always)]
+pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_neon!(memchr3, n1, n2, n3, haystack)
+}
+
+#[inline(always)]
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_neon!(memrchr, n1, haystack)
+}
+
+#[inline(always)]
+pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_neon!(memrchr2, n1, n2, haystack)
+}
+
+#[inline(always)]
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_neon!(memrchr3, n1, n2, n3, haystack)
+}
@@ -5,13 +5,13 @@
 use core::{cmp, usize};
 
 #[cfg(target_pointer_width = "16")]
-const USIZE_BYTES: usize = 2;
+pub(crate) const USIZE_BYTES: usize = 2;
 
 #[cfg(target_pointer_width = "32")]
-const USIZE_BYTES: usize = 4;
+pub(crate) const USIZE_BYTES: usize = 4;
 
 #[cfg(target_pointer_width = "64")]
-const USIZE_BYTES: usize = 8;
+pub(crate) const USIZE_BYTES: usize = 8;
 
 // The number of bytes to loop at in one iteration of memchr/memrchr.
 const LOOP_SIZE: usize = 2 * USIZE_BYTES;
@@ -24,7 +24,7 @@ const LOOP_SIZE: usize = 2 * USIZE_BYTES;
 /// bytes where the borrow propagated all the way to the most significant
 /// bit."
 #[inline(always)]
-fn contains_zero_byte(x: usize) -> bool {
+pub(crate) fn contains_zero_byte(x: usize) -> bool {
     const LO_U64: u64 = 0x0101010101010101;
     const HI_U64: u64 = 0x8080808080808080;
 
@@ -39,7 +39,7 @@ fn contains_zero_byte(x: usize) -> bool {
 /// `01001110` in binary, then the returned value on a 32-bit system would be:
 /// `01001110_01001110_01001110_01001110`.
 #[inline(always)]
-fn repeat_byte(b: u8) -> usize {
+pub(crate) fn repeat_byte(b: u8) -> usize {
     (b as usize) * (usize::MAX / 255)
 }
 
@@ -0,0 +1,201 @@
+/*!
+Vectorized implementations of the `memchr` family of routines that are
+generic over the vector type.
+
+These are used on aarch64 and wasm32, where NEON and simd128 provide 16 byte
+vectors, and by the core::simd routines of the `portable-simd` feature. The
+x86 routines predate these and use the vendor intrinsics
+directly, which lets them take advantage of aligned loads and AVX2.
+*/
+
+use core::mem::size_of;
+
+use crate::memmem::vector::Vector;
+
+/// The number of vectors checked in each iteration of the main loop. On
+/// NEON and simd128, computing a movemask is comparatively expensive, so the
+/// vectors are combined and only the combined vector is checked for a match.
+const UNROLL: usize = 4;
+
+/// Returns the offset of the first position in `haystack` at which `eq`
+/// reports a match.
+///
+/// `eq` is given a vector loaded from the haystack, and returns a vector in
+/// which the lanes at matching positions have their high bit set and all
+/// other lanes are zero. For example, a single byte search would compare
+/// the given vector with a vector of the needle.
+///
+/// # Panics
+///
+/// When the haystack is shorter than a single vector. Callers should handle
+/// such haystacks with a fallback routine.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute,
+/// or with the target feature enabled at compile time. Therefore, callers
+/// must ensure that whatever target feature is being used supports the
+/// vector functions that this function is specialized for. (For the specific
+/// vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn fwd<V: Vector>(
+    haystack: &[u8],
+    eq: impl Fn(V) -> V,
+) -> Option<usize> {
+    let size = size_of::<V>();
+    assert!(haystack.len() >= size, "haystack too small");
+
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+    while diff(end_ptr, ptr) >= UNROLL * size {
+        let a = eq(V::load_unaligned(ptr));
+        let b = eq(V::load_unaligned(ptr.add(size)));
+        let c = eq(V::load_unaligned(ptr.add(2 * size)));
+        let d = eq(V::load_unaligned(ptr.add(3 * size)));
+        if a.or(b).or(c.or(d)).movemask() != 0 {
+            let at = diff(ptr, start_ptr);
+            for (i, v) in [a, b, c, d].iter().enumerate() {
+                let mask = v.movemask();
+                if mask != 0 {
+                    return Some(at + i * size + first_set(mask));
+                }
+            }
+        }
+        ptr = ptr.add(UNROLL * size);
+    }
+    while diff(end_ptr, ptr) >= size {
+        let mask = eq(V::load_unaligned(ptr)).movemask();
+        if mask != 0 {
+            return Some(diff(ptr, start_ptr) + first_set(mask));
+        }
+        ptr = ptr.add(size);
+    }
+    if ptr < end_ptr {
+        // The last vector overlaps with the one before it. This is fine,
+        // since we know there are no matches in the overlap.
+        ptr = end_ptr.sub(size);
+        let mask = eq(V::load_unaligned(ptr)).movemask();
+        if mask != 0 {
+            return Some(diff(ptr, start_ptr) + first_set(mask));
+        }
+    }
+    None
+}
+
+/// Returns the offset of the last position in `haystack` at which `eq`
+/// reports a match.
+///
+/// This is the reverse of `fwd`, where the last vector overlaps with the
+/// start of the haystack.
+///
+/// # Panics
+///
+/// When the haystack is shorter than a single vector.
+///
+/// # Safety
+///
+/// Same as `fwd`.
+#[inline(always)]
+pub(crate) unsafe fn rev<V: Vector>(
+    haystack: &[u8],
+    eq: impl Fn(V) -> V,
+) -> Option<usize> {
+    let size = size_of::<V>();
+    assert!(haystack.len() >= size, "haystack too small");
+
+    let start_ptr = haystack.as_ptr();
+    let mut ptr = start_ptr.add(haystack.len());
+    while diff(ptr, start_ptr) >= UNROLL * size {
+        ptr = ptr.sub(UNROLL * size);
+        let a = eq(V::load_unaligned(ptr));
+        let b = eq(V::load_unaligned(ptr.add(size)));
+        let c = eq(V::load_unaligned(ptr.add(2 * size)));
+        let d = eq(V::load_unaligned(ptr.add(3 * size)));
+        if a.or(b).or(c.or(d)).movemask() != 0 {
+            let at = diff(ptr, start_ptr);
+            for (i, v) in [a, b, c, d].iter().enumerate().rev() {
+                let mask = v.movemask();
+                if mask != 0 {
+                    return Some(at + i * size + last_set(mask));
+                }
+            }
+        }
+    }
+    while diff(ptr, start_ptr) >= size {
+        ptr = ptr.sub(size);
+        let mask = eq(V::load_unaligned(ptr)).movemask();
+        if mask != 0 {
+            return Some(diff(ptr, start_ptr) + last_set(mask));
+        }
+    }
+    if ptr > start_ptr {
+        let mask = eq(V::load_unaligned(start_ptr)).movemask();
+        if mask != 0 {
+            return Some(last_set(mask));
+        }
+    }
+    None
+}
+
+/// Returns the position of the lowest set bit in a non-zero mask.
+#[inline(always)]
+fn first_set(mask: u32) -> usize {
+    mask.trailing_zeros() as usize
+}
+
+/// Returns the position of the highest set bit in a non-zero mask.
+#[inline(always)]
+fn last_set(mask: u32) -> usize {
+    31 - mask.leading_zeros() as usize
+}
+
+/// Subtract `b` from `a` and return the difference. `a` should be greater
+/// than or equal to `b`.
+#[inline(always)]
+fn diff(a: *const u8, b: *const u8) -> usize {
+    debug_assert!(a >= b);
+    (a as usize) - (b as usize)
+}
+
+// The kernels are only used on aarch64 and wasm32, but they are generic, so
+// we test them with SSE2 on x86_64 too.
+#[cfg(all(test, feature = "std", target_arch = "x86_64"))]
+mod tests {
+    use core::arch::x86_64::__m128i;
+
+    use crate::memmem::vector::Vector;
+
+    fn find(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe {
+            let (v1, v2) = (__m128i::splat(n1), __m128i::splat(n2));
+            super::fwd(haystack, |c: __m128i| c.cmpeq(v1).or(c.cmpeq(v2)))
+        }
+    }
+
+    fn rfind(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe {
+            let (v1, v2) = (__m128i::splat(n1), __m128i::splat(n2));
+            super::rev(haystack, |c: __m128i| c.cmpeq(v1).or(c.cmpeq(v2)))
+        }
+    }
+
+    #[test]
+    fn matches_naive() {
+        let haystack: Vec<u8> =
+            (0..300u32).map(|i| (i * 11 % 199) as u8).collect();
+        for start in 0..40 {
+            for end in (start + 16)..(start + 150) {
+                let h = &haystack[start..end];
+                for &(a, b) in &[(0, 1), (198, 11), (5, 250), (255, 255)] {
+                    let naive = |&x: &u8| x == a || x == b;
+                    assert_eq!(h.iter().position(naive), find(a, b, h));
+                    assert_eq!(h.iter().rposition(naive), rfind(a, b, h));
+                }
+            }
+        }
+    }
+}
@@ -1,16 +1,38 @@
 use core::iter::Rev;
 
 pub use self::iter::{Memchr, Memchr2, Memchr3};
+pub use self::searcher::{One, OneIter, Three, ThreeIter, Two, TwoIter};
 
 // N.B. If you're looking for the cfg knobs for libc, see build.rs.
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+pub(crate) mod aarch64;
 #[cfg(memchr_libc)]
 mod c;
 #[allow(dead_code)]
 pub mod fallback;
+#[cfg(any(
+    all(not(miri), target_arch = "aarch64", memchr_runtime_neon),
+    all(not(miri), target_arch = "wasm32", memchr_runtime_simd128),
+    all(not(miri), feature = "portable-simd"),
+    all(test, not(miri), target_arch = "x86_64", memchr_runtime_simd),
+))]
+mod genericsimd;
 mod iter;
 pub mod naive;
-#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
-mod x86;
+// The portable kernels are compiled whenever the feature is enabled, so that
+// they can be tested on all targets, but only used when build.rs selects them.
+#[cfg(all(not(miri), feature = "portable-simd"))]
+#[cfg_attr(not(memchr_portable_simd), allow(dead_code))]
+pub(crate) mod portable;
+mod searcher;
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+pub(crate) mod wasm32;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+pub(crate) mod x86;
 
 /// An iterator over all occurrences of the needle in a haystack.
 #[inline]
@@ -91,15 +113,43 @@ pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
         naive::memchr(n1, haystack)
     }
 
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
     #[inline(always)]
     fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
         x86::memchr(n1, haystack)
     }
 
+    #[cfg(all(target_arch = "aarch64", memchr_runtime_neon, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        aarch64::memchr(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "wasm32", memchr_runtime_simd128, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        wasm32::memchr(n1, haystack)
+    }
+
+    #[cfg(all(memchr_portable_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr(n1, haystack)
+    }
+
     #[cfg(all(
         memchr_libc,
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -109,7 +159,13 @@ pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
 
     #[cfg(all(
         not(memchr_libc),
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -155,14 +211,42 @@ pub fn memchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
         naive::memchr2(n1, n2, haystack)
     }
 
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
     #[inline(always)]
     fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
         x86::memchr2(n1, n2, haystack)
     }
 
+    #[cfg(all(target_arch = "aarch64", memchr_runtime_neon, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        aarch64::memchr2(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "wasm32", memchr_runtime_simd128, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        wasm32::memchr2(n1, n2, haystack)
+    }
+
+    #[cfg(all(memchr_portable_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr2(n1, n2, haystack)
+    }
+
     #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -213,14 +297,42 @@ pub fn memchr3(
         naive::memchr3(n1, n2, n3, haystack)
     }
 
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
     #[inline(always)]
     fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
         x86::memchr3(n1, n2, n3, haystack)
     }
 
+    #[cfg(all(target_arch = "aarch64", memchr_runtime_neon, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        aarch64::memchr3(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "wasm32", memchr_runtime_simd128, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        wasm32::memchr3(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(memchr_portable_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr3(n1, n2, n3, haystack)
+    }
+
     #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -264,16 +376,44 @@ pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
         naive::memrchr(n1, haystack)
     }
 
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
     #[inline(always)]
     fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
         x86::memrchr(n1, haystack)
     }
 
+    #[cfg(all(target_arch = "aarch64", memchr_runtime_neon, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        aarch64::memrchr(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "wasm32", memchr_runtime_simd128, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        wasm32::memrchr(n1, haystack)
+    }
+
+    #[cfg(all(memchr_portable_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr(n1, haystack)
+    }
+
     #[cfg(all(
         memchr_libc,
         target_os = "linux",
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri)
     ))]
     #[inline(always)]
@@ -283,7 +423,13 @@ pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
 
     #[cfg(all(
         not(all(memchr_libc, target_os = "linux")),
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -329,14 +475,42 @@ pub fn memrchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
         naive::memrchr2(n1, n2, haystack)
     }
 
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
     #[inline(always)]
     fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
         x86::memrchr2(n1, n2, haystack)
     }
 
+    #[cfg(all(target_arch = "aarch64", memchr_runtime_neon, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        aarch64::memrchr2(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "wasm32", memchr_runtime_simd128, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        wasm32::memrchr2(n1, n2, haystack)
+    }
+
+    #[cfg(all(memchr_portable_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr2(n1, n2, haystack)
+    }
+
     #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -387,14 +561,42 @@ pub fn memrchr3(
         naive::memrchr3(n1, n2, n3, haystack)
     }
 
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
     #[inline(always)]
     fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
         x86::memrchr3(n1, n2, n3, haystack)
     }
 
+    #[cfg(all(target_arch = "aarch64", memchr_runtime_neon, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        aarch64::memrchr3(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "wasm32", memchr_runtime_simd128, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        wasm32::memrchr3(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(memchr_portable_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr3(n1, n2, n3, haystack)
+    }
+
     #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(all(target_arch = "aarch64", memchr_runtime_neon)),
+        not(all(target_arch = "wasm32", memchr_runtime_simd128)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -0,0 +1,141 @@
+// The routines in this module are implemented on top of core::simd, which
+// doesn't require any target features. So unlike the routines for specific
+// architectures, they are safe to call, and there is no runtime detection or
+// dispatch here.
+//
+// SAFETY: Every unsafe block below only calls the generic kernels, which
+// require the vector operations to be supported by the current CPU. This is
+// always the case for u8x16.
+
+use core::{mem::size_of, simd::u8x16};
+
+use crate::memchr::{fallback, genericsimd};
+use crate::memmem::vector::Vector;
+
+const VECTOR_SIZE: usize = size_of::<u8x16>();
+
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    memchr_vec(n1, u8x16::splat(n1), haystack)
+}
+
+/// Like `memchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[inline]
+pub fn memchr_vec(n1: u8, vn1: u8x16, haystack: &[u8]) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memchr(n1, haystack);
+    }
+    unsafe { genericsimd::fwd(haystack, |c: u8x16| c.cmpeq(vn1)) }
+}
+
+pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    memchr2_vec(n1, n2, u8x16::splat(n1), u8x16::splat(n2), haystack)
+}
+
+/// Like `memchr2`, but with the needles already broadcast to every lane of
+/// `vn1` and `vn2`.
+#[inline]
+pub fn memchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: u8x16,
+    vn2: u8x16,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memchr2(n1, n2, haystack);
+    }
+    unsafe {
+        genericsimd::fwd(haystack, |c: u8x16| c.cmpeq(vn1).or(c.cmpeq(vn2)))
+    }
+}
+
+pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    let (vn1, vn2, vn3) =
+        (u8x16::splat(n1), u8x16::splat(n2), u8x16::splat(n3));
+    memchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memchr3`, but with the needles already broadcast to every lane of
+/// `vn1`, `vn2` and `vn3`.
+#[inline]
+pub fn memchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: u8x16,
+    vn2: u8x16,
+    vn3: u8x16,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memchr3(n1, n2, n3, haystack);
+    }
+    unsafe {
+        genericsimd::fwd(haystack, |c: u8x16| {
+            c.cmpeq(vn1).or(c.cmpeq(vn2)).or(c.cmpeq(vn3))
+        })
+    }
+}
+
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    memrchr_vec(n1, u8x16::splat(n1), haystack)
+}
+
+/// Like `memrchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[inline]
+pub fn memrchr_vec(n1: u8, vn1: u8x16, haystack: &[u8]) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memrchr(n1, haystack);
+    }
+    unsafe { genericsimd::rev(haystack, |c: u8x16| c.cmpeq(vn1)) }
+}
+
+pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    memrchr2_vec(n1, n2, u8x16::splat(n1), u8x16::splat(n2), haystack)
+}
+
+/// Like `memrchr2`, but with the needles already broadcast to every lane of
+/// `vn1` and `vn2`.
+#[inline]
+pub fn memrchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: u8x16,
+    vn2: u8x16,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memrchr2(n1, n2, haystack);
+    }
+    unsafe {
+        genericsimd::rev(haystack, |c: u8x16| c.cmpeq(vn1).or(c.cmpeq(vn2)))
+    }
+}
+
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    let (vn1, vn2, vn3) =
+        (u8x16::splat(n1), u8x16::splat(n2), u8x16::splat(n3));
+    memrchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memrchr3`, but with the needles already broadcast to every lane of
+/// `vn1`, `vn2` and `vn3`.
+#[inline]
+pub fn memrchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: u8x16,
+    vn2: u8x16,
+    vn3: u8x16,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memrchr3(n1, n2, n3, haystack);
+    }
+    unsafe {
+        genericsimd::rev(haystack, |c: u8x16| {
+            c.cmpeq(vn1).or(c.cmpeq(vn2)).or(c.cmpeq(vn3))
+        })
+    }
+}
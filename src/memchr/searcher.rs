@@ -0,0 +1,637 @@
+/*!
+Prebuilt searchers for one, two or three bytes.
+
+Each call to `memchr` (and friends) broadcasts its needles into vectors and
+goes through the runtime dispatch of the selected backend. Both are cheap,
+but when searching many tiny haystacks for the same bytes, the overhead is
+measurable. The searchers in this module do both once, when they are built.
+*/
+
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+use core::arch::aarch64::uint8x16_t;
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+use core::arch::wasm32::v128;
+#[cfg(all(not(miri), target_arch = "x86", memchr_runtime_simd))]
+use core::arch::x86::{__m128i, __m256i};
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+use core::arch::x86_64::{__m128i, __m256i};
+use core::fmt;
+#[cfg(all(not(miri), memchr_portable_simd))]
+use core::simd::u8x16;
+
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+use super::aarch64::neon;
+#[cfg(all(not(miri), memchr_portable_simd))]
+use super::portable;
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+use super::wasm32::simd128;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd,
+    memchr_avx2
+))]
+use super::x86::avx;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+use super::x86::sse2;
+use super::{fallback, naive};
+use crate::{
+    arch::{self, Backend},
+    bitmask,
+};
+
+/// A needle broadcast to every lane of an x86 vector. The AVX2 routines use
+/// all of it, while the SSE2 routines use its low half.
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+type Splat = __m256i;
+
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+fn splat(b: u8) -> Splat {
+    // SAFETY: Every bit pattern is a valid `__m256i`. Building it from an
+    // array avoids requiring AVX to be available.
+    unsafe { core::mem::transmute::<[u8; 32], Splat>([b; 32]) }
+}
+
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+fn low(v: Splat) -> __m128i {
+    // SAFETY: Every bit pattern is a valid `__m128i`.
+    unsafe { core::mem::transmute::<Splat, [__m128i; 2]>(v)[0] }
+}
+
+/// A needle broadcast to every lane of a NEON vector.
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+type Splat = uint8x16_t;
+
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+fn splat(b: u8) -> Splat {
+    // SAFETY: Every bit pattern is a valid `uint8x16_t`.
+    unsafe { core::mem::transmute::<[u8; 16], Splat>([b; 16]) }
+}
+
+/// A needle broadcast to every lane of a simd128 vector.
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+type Splat = v128;
+
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+fn splat(b: u8) -> Splat {
+    // SAFETY: Every bit pattern is a valid `v128`.
+    unsafe { core::mem::transmute::<[u8; 16], Splat>([b; 16]) }
+}
+
+/// A needle broadcast to every lane of a core::simd vector.
+#[cfg(all(not(miri), memchr_portable_simd))]
+type Splat = u8x16;
+
+#[cfg(all(not(miri), memchr_portable_simd))]
+fn splat(b: u8) -> Splat {
+    u8x16::splat(b)
+}
+
+/// Returns the offset of `p` from `start`.
+fn offset(start: *const u8, p: *const u8) -> usize {
+    debug_assert!(start <= p);
+    p as usize - start as usize
+}
+
+macro_rules! searcher {
+    (
+        $(#[$doc:meta])*
+        struct $name:ident;
+        $(#[$iter_doc:meta])*
+        struct $iter:ident;
+        kernels: $memchr:ident, $memrchr:ident;
+        vec_kernels: $memchr_vec:ident, $memrchr_vec:ident;
+        needles: $($n:ident / $vn:ident),+;
+    ) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy)]
+        pub struct $name {
+            $($n: u8,)+
+            $(
+                #[cfg(any(
+                    all(
+                        not(miri),
+                        any(target_arch = "x86", target_arch = "x86_64"),
+                        memchr_runtime_simd,
+                    ),
+                    all(
+                        not(miri),
+                        target_arch = "aarch64",
+                        memchr_runtime_neon,
+                    ),
+                    all(
+                        not(miri),
+                        target_arch = "wasm32",
+                        memchr_runtime_simd128,
+                    ),
+                    all(not(miri), memchr_portable_simd),
+                ))]
+                $vn: Splat,
+            )+
+            backend: Backend,
+            find: unsafe fn(&$name, &[u8]) -> Option<usize>,
+            rfind: unsafe fn(&$name, &[u8]) -> Option<usize>,
+        }
+
+        impl $name {
+            /// Create a searcher for the given bytes.
+            ///
+            /// The searcher uses the backend selected for the `memchr`
+            /// family of routines when it is created. Changing the backend
+            /// later with [`arch::set_backend`](crate::arch) doesn't affect
+            /// existing searchers.
+            pub fn new($($n: u8),+) -> $name {
+                type Search = unsafe fn(&$name, &[u8]) -> Option<usize>;
+
+                let backend = arch::backend();
+                let (find, rfind): (Search, Search) = match backend {
+                    #[cfg(all(
+                        not(miri),
+                        any(target_arch = "x86", target_arch = "x86_64"),
+                        memchr_runtime_simd,
+                        memchr_avx2,
+                    ))]
+                    Backend::Avx2 => ($name::find_avx2, $name::rfind_avx2),
+                    #[cfg(all(
+                        not(miri),
+                        any(target_arch = "x86", target_arch = "x86_64"),
+                        memchr_runtime_simd,
+                    ))]
+                    Backend::Sse2 => ($name::find_sse2, $name::rfind_sse2),
+                    #[cfg(all(
+                        not(miri),
+                        target_arch = "aarch64",
+                        memchr_runtime_neon,
+                    ))]
+                    Backend::Neon => ($name::find_neon, $name::rfind_neon),
+                    #[cfg(all(
+                        not(miri),
+                        target_arch = "wasm32",
+                        memchr_runtime_simd128,
+                    ))]
+                    Backend::Simd128 => {
+                        ($name::find_simd128, $name::rfind_simd128)
+                    }
+                    #[cfg(all(not(miri), memchr_portable_simd))]
+                    Backend::Portable => {
+                        ($name::find_portable, $name::rfind_portable)
+                    }
+                    _ => ($name::find_fallback, $name::rfind_fallback),
+                };
+                $name {
+                    $($n,)+
+                    $(
+                        #[cfg(any(
+                            all(
+                                not(miri),
+                                any(
+                                    target_arch = "x86",
+                                    target_arch = "x86_64",
+                                ),
+                                memchr_runtime_simd,
+                            ),
+                            all(
+                                not(miri),
+                                target_arch = "aarch64",
+                                memchr_runtime_neon,
+                            ),
+                            all(
+                                not(miri),
+                                target_arch = "wasm32",
+                                memchr_runtime_simd128,
+                            ),
+                            all(not(miri), memchr_portable_simd),
+                        ))]
+                        $vn: splat($n),
+                    )+
+                    backend,
+                    find,
+                    rfind,
+                }
+            }
+
+            /// Returns the backend used by this searcher.
+            pub fn backend(&self) -> Backend {
+                self.backend
+            }
+
+            /// Returns the offset of the first occurrence of any of this
+            /// searcher's bytes in `haystack`, or `None` if there isn't one.
+            #[inline]
+            pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+                if haystack.is_empty() {
+                    return None;
+                }
+                // SAFETY: The routine was chosen for a backend that is
+                // available, and the haystack isn't empty.
+                unsafe { (self.find)(self, haystack) }
+            }
+
+            /// Returns the offset of the last occurrence of any of this
+            /// searcher's bytes in `haystack`, or `None` if there isn't one.
+            #[inline]
+            pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+                if haystack.is_empty() {
+                    return None;
+                }
+                // SAFETY: The routine was chosen for a backend that is
+                // available, and the haystack isn't empty.
+                unsafe { (self.rfind)(self, haystack) }
+            }
+
+            /// Returns the number of occurrences of any of this searcher's
+            /// bytes in `haystack`.
+            pub fn count(&self, haystack: &[u8]) -> usize {
+                bitmask::count_bytes(&[$(self.$n),+], haystack)
+            }
+
+            /// Returns an iterator over the offsets of all occurrences of
+            /// any of this searcher's bytes in `haystack`.
+            ///
+            /// The iterator is double ended, so it can also report the
+            /// occurrences in reverse order.
+            pub fn iter<'a, 'h>(&'a self, haystack: &'h [u8]) -> $iter<'a, 'h> {
+                $iter {
+                    searcher: self,
+                    haystack,
+                    start: 0,
+                    end: haystack.len(),
+                }
+            }
+
+            /// Like [`find`](Self::find), but for the bytes from `start` up
+            /// to (but not including) `end`. It returns a pointer to the
+            /// first occurrence.
+            ///
+            /// # Safety
+            ///
+            /// `start` and `end` must be derived from a pointer to the same
+            /// object, with `start <= end`, and all the bytes between them
+            /// must be readable for the duration of the call.
+            #[inline]
+            pub unsafe fn find_raw(
+                &self,
+                start: *const u8,
+                end: *const u8,
+            ) -> Option<*const u8> {
+                let len = offset(start, end);
+                if len == 0 {
+                    return None;
+                }
+                let haystack = core::slice::from_raw_parts(start, len);
+                (self.find)(self, haystack).map(|i| start.add(i))
+            }
+
+            /// Like [`rfind`](Self::rfind), but for the bytes from `start`
+            /// up to (but not including) `end`. It returns a pointer to the
+            /// last occurrence.
+            ///
+            /// # Safety
+            ///
+            /// `start` and `end` must be derived from a pointer to the same
+            /// object, with `start <= end`, and all the bytes between them
+            /// must be readable for the duration of the call.
+            #[inline]
+            pub unsafe fn rfind_raw(
+                &self,
+                start: *const u8,
+                end: *const u8,
+            ) -> Option<*const u8> {
+                let len = offset(start, end);
+                if len == 0 {
+                    return None;
+                }
+                let haystack = core::slice::from_raw_parts(start, len);
+                (self.rfind)(self, haystack).map(|i| start.add(i))
+            }
+
+            #[cfg(all(
+                not(miri),
+                any(target_arch = "x86", target_arch = "x86_64"),
+                memchr_runtime_simd,
+                memchr_avx2,
+            ))]
+            #[target_feature(enable = "avx2")]
+            unsafe fn find_avx2(&self, haystack: &[u8]) -> Option<usize> {
+                avx::$memchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            #[cfg(all(
+                not(miri),
+                any(target_arch = "x86", target_arch = "x86_64"),
+                memchr_runtime_simd,
+                memchr_avx2,
+            ))]
+            #[target_feature(enable = "avx2")]
+            unsafe fn rfind_avx2(&self, haystack: &[u8]) -> Option<usize> {
+                avx::$memrchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            #[cfg(all(
+                not(miri),
+                any(target_arch = "x86", target_arch = "x86_64"),
+                memchr_runtime_simd,
+            ))]
+            #[target_feature(enable = "sse2")]
+            unsafe fn find_sse2(&self, haystack: &[u8]) -> Option<usize> {
+                sse2::$memchr_vec($(self.$n,)+ $(low(self.$vn),)+ haystack)
+            }
+
+            #[cfg(all(
+                not(miri),
+                any(target_arch = "x86", target_arch = "x86_64"),
+                memchr_runtime_simd,
+            ))]
+            #[target_feature(enable = "sse2")]
+            unsafe fn rfind_sse2(&self, haystack: &[u8]) -> Option<usize> {
+                sse2::$memrchr_vec($(self.$n,)+ $(low(self.$vn),)+ haystack)
+            }
+
+            #[cfg(all(
+                not(miri),
+                target_arch = "aarch64",
+                memchr_runtime_neon,
+            ))]
+            unsafe fn find_neon(&self, haystack: &[u8]) -> Option<usize> {
+                neon::$memchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            #[cfg(all(
+                not(miri),
+                target_arch = "aarch64",
+                memchr_runtime_neon,
+            ))]
+            unsafe fn rfind_neon(&self, haystack: &[u8]) -> Option<usize> {
+                neon::$memrchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            #[cfg(all(
+                not(miri),
+                target_arch = "wasm32",
+                memchr_runtime_simd128,
+            ))]
+            unsafe fn find_simd128(&self, haystack: &[u8]) -> Option<usize> {
+                simd128::$memchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            #[cfg(all(
+                not(miri),
+                target_arch = "wasm32",
+                memchr_runtime_simd128,
+            ))]
+            unsafe fn rfind_simd128(
+                &self,
+                haystack: &[u8],
+            ) -> Option<usize> {
+                simd128::$memrchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            #[cfg(all(not(miri), memchr_portable_simd))]
+            unsafe fn find_portable(&self, haystack: &[u8]) -> Option<usize> {
+                portable::$memchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            #[cfg(all(not(miri), memchr_portable_simd))]
+            unsafe fn rfind_portable(
+                &self,
+                haystack: &[u8],
+            ) -> Option<usize> {
+                portable::$memrchr_vec($(self.$n,)+ $(self.$vn,)+ haystack)
+            }
+
+            unsafe fn find_fallback(&self, haystack: &[u8]) -> Option<usize> {
+                if cfg!(miri) {
+                    naive::$memchr($(self.$n,)+ haystack)
+                } else {
+                    fallback::$memchr($(self.$n,)+ haystack)
+                }
+            }
+
+            unsafe fn rfind_fallback(
+                &self,
+                haystack: &[u8],
+            ) -> Option<usize> {
+                if cfg!(miri) {
+                    naive::$memrchr($(self.$n,)+ haystack)
+                } else {
+                    fallback::$memrchr($(self.$n,)+ haystack)
+                }
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name))
+                    $(.field(stringify!($n), &self.$n))+
+                    .field("backend", &self.backend)
+                    .finish()
+            }
+        }
+
+        $(#[$iter_doc])*
+        #[derive(Clone, Debug)]
+        pub struct $iter<'a, 'h> {
+            searcher: &'a $name,
+            haystack: &'h [u8],
+            // The range of the haystack that hasn't been searched yet.
+            start: usize,
+            end: usize,
+        }
+
+        impl<'a, 'h> Iterator for $iter<'a, 'h> {
+            type Item = usize;
+
+            #[inline]
+            fn next(&mut self) -> Option<usize> {
+                let rest = &self.haystack[self.start..self.end];
+                let found = self.start + self.searcher.find(rest)?;
+                self.start = found + 1;
+                Some(found)
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (0, Some(self.end - self.start))
+            }
+        }
+
+        impl<'a, 'h> DoubleEndedIterator for $iter<'a, 'h> {
+            #[inline]
+            fn next_back(&mut self) -> Option<usize> {
+                let rest = &self.haystack[self.start..self.end];
+                let found = self.start + self.searcher.rfind(rest)?;
+                self.end = found;
+                Some(found)
+            }
+        }
+    };
+}
+
+searcher! {
+    /// A prebuilt searcher for a single byte.
+    ///
+    /// This finds the same occurrences as [`memchr`](crate::memchr) and
+    /// [`memrchr`](crate::memrchr), but broadcasts the needle and selects
+    /// the implementation once, when it is built, instead of on every call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::One;
+    ///
+    /// let newline = One::new(b'\n');
+    /// let haystack = b"foo\nbar\nbaz";
+    /// assert_eq!(Some(3), newline.find(haystack));
+    /// assert_eq!(Some(7), newline.rfind(haystack));
+    /// assert_eq!(2, newline.count(haystack));
+    /// assert_eq!(vec![3, 7], newline.iter(haystack).collect::<Vec<_>>());
+    /// ```
+    struct One;
+    /// An iterator over the occurrences of a byte, created by
+    /// [`One::iter`].
+    struct OneIter;
+    kernels: memchr, memrchr;
+    vec_kernels: memchr_vec, memrchr_vec;
+    needles: n1 / vn1;
+}
+
+searcher! {
+    /// A prebuilt searcher for either of two bytes.
+    ///
+    /// This finds the same occurrences as [`memchr2`](crate::memchr2) and
+    /// [`memrchr2`](crate::memrchr2), but broadcasts the needles and selects
+    /// the implementation once, when it is built, instead of on every call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::Two;
+    ///
+    /// let eol = Two::new(b'\r', b'\n');
+    /// assert_eq!(Some(3), eol.find(b"foo\r\nbar"));
+    /// assert_eq!(2, eol.count(b"foo\r\nbar"));
+    /// ```
+    struct Two;
+    /// An iterator over the occurrences of either of two bytes, created by
+    /// [`Two::iter`].
+    struct TwoIter;
+    kernels: memchr2, memrchr2;
+    vec_kernels: memchr2_vec, memrchr2_vec;
+    needles: n1 / vn1, n2 / vn2;
+}
+
+searcher! {
+    /// A prebuilt searcher for any of three bytes.
+    ///
+    /// This finds the same occurrences as [`memchr3`](crate::memchr3) and
+    /// [`memrchr3`](crate::memrchr3), but broadcasts the needles and selects
+    /// the implementation once, when it is built, instead of on every call.
+    ///
+    /// # Example
+    ///
+    /// This shows how a parser might use the raw pointer API to find the end
+    /// of a string literal.
+    ///
+    /// ```
+    /// use memchr::Three;
+    ///
+    /// let special = Three::new(b'"', b'\\', b'\n');
+    /// let input = br#"abc\"def" rest"#;
+    /// let found = unsafe {
+    ///     let start = input.as_ptr();
+    ///     special.find_raw(start, start.add(input.len()))
+    /// };
+    /// assert_eq!(Some(input[3..].as_ptr()), found);
+    /// ```
+    struct Three;
+    /// An iterator over the occurrences of any of three bytes, created by
+    /// [`Three::iter`].
+    struct ThreeIter;
+    kernels: memchr3, memrchr3;
+    vec_kernels: memchr3_vec, memrchr3_vec;
+    needles: n1 / vn1, n2 / vn2, n3 / vn3;
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{One, Three, Two};
+
+    #[test]
+    fn matches_naive() {
+        let haystack: Vec<u8> =
+            (0..500u32).map(|i| (i * 13 % 127) as u8).collect();
+        for start in 0..70 {
+            let h = &haystack[start..];
+            for &(a, b, c) in &[(0, 1, 2), (126, 13, 50), (200, 201, 202)] {
+                let is1 = |&x: &u8| x == a;
+                let is2 = |&x: &u8| x == a || x == b;
+                let is3 = |&x: &u8| x == a || x == b || x == c;
+                let (one, two, three) =
+                    (One::new(a), Two::new(a, b), Three::new(a, b, c));
+
+                assert_eq!(h.iter().position(is1), one.find(h));
+                assert_eq!(h.iter().rposition(is1), one.rfind(h));
+                assert_eq!(h.iter().filter(|x| is1(x)).count(), one.count(h));
+                assert_eq!(h.iter().position(is2), two.find(h));
+                assert_eq!(h.iter().rposition(is2), two.rfind(h));
+                assert_eq!(h.iter().filter(|x| is2(x)).count(), two.count(h));
+                assert_eq!(h.iter().position(is3), three.find(h));
+                assert_eq!(h.iter().rposition(is3), three.rfind(h));
+                assert_eq!(
+                    h.iter().filter(|x| is3(x)).count(),
+                    three.count(h),
+                );
+
+                let expected: Vec<usize> =
+                    (0..h.len()).filter(|&i| is3(&h[i])).collect();
+                let forward: Vec<usize> = three.iter(h).collect();
+                let mut reverse: Vec<usize> = three.iter(h).rev().collect();
+                reverse.reverse();
+                assert_eq!(expected, forward);
+                assert_eq!(expected, reverse);
+            }
+        }
+    }
+
+    #[test]
+    fn iter_from_both_ends() {
+        let one = One::new(b'a');
+        let mut it = one.iter(b"a_a_a_a");
+        assert_eq!(Some(0), it.next());
+        assert_eq!(Some(6), it.next_back());
+        assert_eq!(Some(2), it.next());
+        assert_eq!(Some(4), it.next_back());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+    }
+
+    #[test]
+    fn raw() {
+        let three = Three::new(b'x', b'y', b'z');
+        let haystack = b"..x..y..z..";
+        let start = haystack.as_ptr();
+        unsafe {
+            let end = start.add(haystack.len());
+            assert_eq!(Some(start.add(2)), three.find_raw(start, end));
+            assert_eq!(Some(start.add(8)), three.rfind_raw(start, end));
+            assert_eq!(None, three.find_raw(start, start));
+            assert_eq!(None, three.find_raw(start, start.add(2)));
+        }
+    }
+}
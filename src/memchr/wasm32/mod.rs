@@ -0,0 +1,42 @@
+// WebAssembly has no runtime feature detection, so this module is only
+// compiled when simd128 is enabled at compile time. So unlike on x86_64, there
+// is no runtime detection or dispatch here.
+
+pub mod simd128;
+
+macro_rules! unsafe_simd128 {
+    ($fn:ident, $($arg:ident),+) => {{
+        // SAFETY: simd128 is enabled at compile time.
+        unsafe { simd128::$fn($($arg),+) }
+    }};
+}
+
+#[inline(always)]
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_simd128!(memchr, n1, haystack)
+}
+
+#[inline(always)]
+pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_simd128!(memchr2, n1, n2, haystack)
+}
+
+#[inline(always)]
+pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_simd128!(memchr3, n1, n2, n3, haystack)
+}
+
+#[inline(always)]
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_simd128!(memrchr, n1, haystack)
+}
+
+#[inline(always)]
+pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_simd128!(memrchr2, n1, n2, haystack)
+}
+
+#[inline(always)]
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_simd128!(memrchr3, n1, n2, n3, haystack)
+}
@@ -0,0 +1,136 @@
+use core::{arch::wasm32::*, mem::size_of};
+
+use crate::memchr::{fallback, genericsimd};
+use crate::memmem::vector::Vector;
+
+const VECTOR_SIZE: usize = size_of::<v128>();
+
+pub unsafe fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    memchr_vec(n1, u8x16_splat(n1), haystack)
+}
+
+/// Like `memchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[inline]
+pub unsafe fn memchr_vec(n1: u8, vn1: v128, haystack: &[u8]) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memchr(n1, haystack);
+    }
+    genericsimd::fwd(haystack, |c: v128| c.cmpeq(vn1))
+}
+
+pub unsafe fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    memchr2_vec(n1, n2, u8x16_splat(n1), u8x16_splat(n2), haystack)
+}
+
+/// Like `memchr2`, but with the needles already broadcast to every lane of
+/// `vn1` and `vn2`.
+#[inline]
+pub unsafe fn memchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: v128,
+    vn2: v128,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memchr2(n1, n2, haystack);
+    }
+    genericsimd::fwd(haystack, |c: v128| c.cmpeq(vn1).or(c.cmpeq(vn2)))
+}
+
+pub unsafe fn memchr3(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let (vn1, vn2, vn3) = (u8x16_splat(n1), u8x16_splat(n2), u8x16_splat(n3));
+    memchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memchr3`, but with the needles already broadcast to every lane of
+/// `vn1`, `vn2` and `vn3`.
+#[inline]
+pub unsafe fn memchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: v128,
+    vn2: v128,
+    vn3: v128,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memchr3(n1, n2, n3, haystack);
+    }
+    genericsimd::fwd(haystack, |c: v128| {
+        c.cmpeq(vn1).or(c.cmpeq(vn2)).or(c.cmpeq(vn3))
+    })
+}
+
+pub unsafe fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    memrchr_vec(n1, u8x16_splat(n1), haystack)
+}
+
+/// Like `memrchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[inline]
+pub unsafe fn memrchr_vec(
+    n1: u8,
+    vn1: v128,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memrchr(n1, haystack);
+    }
+    genericsimd::rev(haystack, |c: v128| c.cmpeq(vn1))
+}
+
+pub unsafe fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    memrchr2_vec(n1, n2, u8x16_splat(n1), u8x16_splat(n2), haystack)
+}
+
+/// Like `memrchr2`, but with the needles already broadcast to every lane of
+/// `vn1` and `vn2`.
+#[inline]
+pub unsafe fn memrchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: v128,
+    vn2: v128,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memrchr2(n1, n2, haystack);
+    }
+    genericsimd::rev(haystack, |c: v128| c.cmpeq(vn1).or(c.cmpeq(vn2)))
+}
+
+pub unsafe fn memrchr3(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let (vn1, vn2, vn3) = (u8x16_splat(n1), u8x16_splat(n2), u8x16_splat(n3));
+    memrchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memrchr3`, but with the needles already broadcast to every lane of
+/// `vn1`, `vn2` and `vn3`.
+#[inline]
+pub unsafe fn memrchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: v128,
+    vn2: v128,
+    vn3: v128,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        return fallback::memrchr3(n1, n2, n3, haystack);
+    }
+    genericsimd::rev(haystack, |c: v128| {
+        c.cmpeq(vn1).or(c.cmpeq(vn2)).or(c.cmpeq(vn3))
+    })
+}
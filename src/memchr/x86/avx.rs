@@ -1,4 +1,8 @@
-use core::{arch::x86_64::*, cmp, mem::size_of};
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+use core::{cmp, mem::size_of};
 
 use super::sse2;
 
@@ -16,6 +20,23 @@ const LOOP_SIZE2: usize = 2 * VECTOR_SIZE;
 
 #[target_feature(enable = "avx2")]
 pub unsafe fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.len() < VECTOR_SIZE {
+        // For small haystacks, defer to the SSE2 implementation. Codegen
+        // suggests this completely avoids touching the AVX vectors.
+        return sse2::memchr(n1, haystack);
+    }
+    let vn1 = _mm256_set1_epi8(n1 as i8);
+    memchr_vec(n1, vn1, haystack)
+}
+
+/// Like `memchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[target_feature(enable = "avx2")]
+#[inline]
+pub unsafe fn memchr_vec(
+    n1: u8,
+    vn1: __m256i,
+    haystack: &[u8],
+) -> Option<usize> {
     // For a high level explanation for how this algorithm works, see the
     // sse2 implementation. The avx implementation here is the same, but with
     // 256-bit vectors instead of 128-bit vectors.
@@ -67,12 +88,11 @@ pub unsafe fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     let mut ptr = start_ptr;
 
     if haystack.len() < VECTOR_SIZE {
-        // For small haystacks, defer to the SSE2 implementation. Codegen
-        // suggests this completely avoids touching the AVX vectors.
-        return sse2::memchr(n1, haystack);
+        // For small haystacks, defer to the SSE2 implementation, using the
+        // low half of the needle vector.
+        return sse2::memchr_vec(n1, _mm256_castsi256_si128(vn1), haystack);
     }
 
-    let vn1 = _mm256_set1_epi8(n1 as i8);
     let loop_size = cmp::min(LOOP_SIZE, haystack.len());
     if let Some(i) = forward_search1(start_ptr, end_ptr, ptr, vn1) {
         return Some(i);
@@ -120,6 +140,22 @@ pub unsafe fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
 
 #[target_feature(enable = "avx2")]
 pub unsafe fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    let vn1 = _mm256_set1_epi8(n1 as i8);
+    let vn2 = _mm256_set1_epi8(n2 as i8);
+    memchr2_vec(n1, n2, vn1, vn2, haystack)
+}
+
+/// Like `memchr2`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "avx2")]
+#[inline]
+pub unsafe fn memchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: __m256i,
+    vn2: __m256i,
+    haystack: &[u8],
+) -> Option<usize> {
     #[cold]
     #[inline(never)]
     #[target_feature(enable = "avx2")]
@@ -144,8 +180,6 @@ pub unsafe fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
         at + forward_pos2(mask1, mask2)
     }
 
-    let vn1 = _mm256_set1_epi8(n1 as i8);
-    let vn2 = _mm256_set1_epi8(n2 as i8);
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
@@ -207,6 +241,25 @@ pub unsafe fn memchr3(
     n2: u8,
     n3: u8,
     haystack: &[u8],
+) -> Option<usize> {
+    let vn1 = _mm256_set1_epi8(n1 as i8);
+    let vn2 = _mm256_set1_epi8(n2 as i8);
+    let vn3 = _mm256_set1_epi8(n3 as i8);
+    memchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memchr3`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "avx2")]
+#[inline]
+pub unsafe fn memchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: __m256i,
+    vn2: __m256i,
+    vn3: __m256i,
+    haystack: &[u8],
 ) -> Option<usize> {
     #[cold]
     #[inline(never)]
@@ -236,9 +289,6 @@ pub unsafe fn memchr3(
         at + forward_pos3(mask1, mask2, mask3)
     }
 
-    let vn1 = _mm256_set1_epi8(n1 as i8);
-    let vn2 = _mm256_set1_epi8(n2 as i8);
-    let vn3 = _mm256_set1_epi8(n3 as i8);
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
@@ -305,6 +355,17 @@ pub unsafe fn memchr3(
 #[target_feature(enable = "avx2")]
 pub unsafe fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = _mm256_set1_epi8(n1 as i8);
+    memrchr_vec(n1, vn1, haystack)
+}
+
+/// Like `memrchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[target_feature(enable = "avx2")]
+#[inline]
+pub unsafe fn memrchr_vec(
+    n1: u8,
+    vn1: __m256i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE, len);
     let start_ptr = haystack.as_ptr();
@@ -385,6 +446,20 @@ pub unsafe fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
 pub unsafe fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = _mm256_set1_epi8(n1 as i8);
     let vn2 = _mm256_set1_epi8(n2 as i8);
+    memrchr2_vec(n1, n2, vn1, vn2, haystack)
+}
+
+/// Like `memrchr2`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "avx2")]
+#[inline]
+pub unsafe fn memrchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: __m256i,
+    vn2: __m256i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
@@ -458,6 +533,22 @@ pub unsafe fn memrchr3(
     let vn1 = _mm256_set1_epi8(n1 as i8);
     let vn2 = _mm256_set1_epi8(n2 as i8);
     let vn3 = _mm256_set1_epi8(n3 as i8);
+    memrchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memrchr3`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "avx2")]
+#[inline]
+pub unsafe fn memrchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: __m256i,
+    vn2: __m256i,
+    vn3: __m256i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
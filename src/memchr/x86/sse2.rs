@@ -1,4 +1,8 @@
-use core::{arch::x86_64::*, cmp, mem::size_of};
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+use core::{cmp, mem::size_of};
 
 const VECTOR_SIZE: usize = size_of::<__m128i>();
 const VECTOR_ALIGN: usize = VECTOR_SIZE - 1;
@@ -14,6 +18,18 @@ const LOOP_SIZE2: usize = 2 * VECTOR_SIZE;
 
 #[target_feature(enable = "sse2")]
 pub unsafe fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    memchr_vec(n1, vn1, haystack)
+}
+
+/// Like `memchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[target_feature(enable = "sse2")]
+#[inline]
+pub unsafe fn memchr_vec(
+    n1: u8,
+    vn1: __m128i,
+    haystack: &[u8],
+) -> Option<usize> {
     // What follows is a fast SSE2-only algorithm to detect the position of
     // `n1` in `haystack` if it exists. From what I know, this is the "classic"
     // algorithm. I believe it can be found in places like glibc and Go's
@@ -105,7 +121,6 @@ pub unsafe fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     // structure to what you see below, so this comment applies fairly well to
     // all of them.
 
-    let vn1 = _mm_set1_epi8(n1 as i8);
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE, len);
     let start_ptr = haystack.as_ptr();
@@ -190,6 +205,20 @@ pub unsafe fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
 pub unsafe fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = _mm_set1_epi8(n1 as i8);
     let vn2 = _mm_set1_epi8(n2 as i8);
+    memchr2_vec(n1, n2, vn1, vn2, haystack)
+}
+
+/// Like `memchr2`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "sse2")]
+#[inline]
+pub unsafe fn memchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: __m128i,
+    vn2: __m128i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
@@ -265,6 +294,22 @@ pub unsafe fn memchr3(
     let vn1 = _mm_set1_epi8(n1 as i8);
     let vn2 = _mm_set1_epi8(n2 as i8);
     let vn3 = _mm_set1_epi8(n3 as i8);
+    memchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memchr3`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "sse2")]
+#[inline]
+pub unsafe fn memchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: __m128i,
+    vn2: __m128i,
+    vn3: __m128i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
@@ -341,6 +386,17 @@ pub unsafe fn memchr3(
 #[target_feature(enable = "sse2")]
 pub unsafe fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = _mm_set1_epi8(n1 as i8);
+    memrchr_vec(n1, vn1, haystack)
+}
+
+/// Like `memrchr`, but with `n1` already broadcast to every lane of `vn1`.
+#[target_feature(enable = "sse2")]
+#[inline]
+pub unsafe fn memrchr_vec(
+    n1: u8,
+    vn1: __m128i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE, len);
     let start_ptr = haystack.as_ptr();
@@ -421,6 +477,20 @@ pub unsafe fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
 pub unsafe fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = _mm_set1_epi8(n1 as i8);
     let vn2 = _mm_set1_epi8(n2 as i8);
+    memrchr2_vec(n1, n2, vn1, vn2, haystack)
+}
+
+/// Like `memrchr2`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "sse2")]
+#[inline]
+pub unsafe fn memrchr2_vec(
+    n1: u8,
+    n2: u8,
+    vn1: __m128i,
+    vn2: __m128i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
@@ -494,6 +564,22 @@ pub unsafe fn memrchr3(
     let vn1 = _mm_set1_epi8(n1 as i8);
     let vn2 = _mm_set1_epi8(n2 as i8);
     let vn3 = _mm_set1_epi8(n3 as i8);
+    memrchr3_vec(n1, n2, n3, vn1, vn2, vn3, haystack)
+}
+
+/// Like `memrchr3`, but with each needle already broadcast to every lane of
+/// the corresponding vector.
+#[target_feature(enable = "sse2")]
+#[inline]
+pub unsafe fn memrchr3_vec(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    vn1: __m128i,
+    vn2: __m128i,
+    vn3: __m128i,
+    haystack: &[u8],
+) -> Option<usize> {
     let len = haystack.len();
     let loop_size = cmp::min(LOOP_SIZE2, len);
     let start_ptr = haystack.as_ptr();
@@ -9,7 +9,11 @@
 // I don't see a way of effectively using PCMPISTRI unless there's some fast
 // way to replace zero bytes with a byte that is not not a needle byte.
 
-use core::{arch::x86_64::*, mem::size_of};
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+use core::mem::size_of;
 
 use x86::sse2;
 
@@ -0,0 +1,603 @@
+use core::fmt;
+
+use crate::{
+    memchr_iter, memmem::byte_frequencies::BYTE_FREQUENCIES, ByteSet,
+};
+
+/// A single substring searcher for a class needle.
+///
+/// A class needle is a sequence of [`ByteSet`]s, where the byte at each
+/// position in a match must be in the corresponding set. This is useful for
+/// binary signatures where some positions are wildcards or may take one of a
+/// few values, such as `48 8B ?? ?? 89 5C 24`. Use
+/// [`ClassFinder::from_signature`] to build a class needle from such a
+/// string.
+///
+/// The scan is driven by the two rarest positions in the needle that are
+/// fixed to a single byte, using the same vectorized approach as the
+/// substring searcher in [`Finder`](crate::memmem::Finder) when possible. A
+/// needle with fewer fixed positions is searched with `memchr` on its only
+/// fixed byte, or by checking every position if it has none.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Complexity
+///
+/// The worst case time complexity of a search is `O(n * m)`, where `n` is the
+/// length of the haystack and `m` is the length of the needle, since every
+/// candidate found by the scan must be verified position by position.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::ClassFinder;
+///
+/// let finder = ClassFinder::from_signature("48 8B ?? ?? 89 5C 24").unwrap();
+/// let haystack = b"\x90\x90\x48\x8B\x01\x02\x89\x5C\x24\xC3";
+/// assert_eq!(Some(2), finder.find(haystack));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClassFinder {
+    needle: Vec<ByteSet>,
+    scan: Scan,
+}
+
+/// The strategy used to find candidates for a class needle.
+#[derive(Clone, Copy, Debug)]
+enum Scan {
+    /// The needle has no fixed positions, so every position is a candidate.
+    Naive,
+    /// The needle has one fixed position, which is found with memchr.
+    One { index: usize, byte: u8 },
+    /// The needle has at least two fixed positions, so the two rarest are
+    /// used to find candidates.
+    Two(RarePair),
+}
+
+/// The two rarest fixed positions of a class needle, along with the bytes
+/// they're fixed to.
+///
+/// `rare1i` is the rarest of the two. Unlike for substring needles, the two
+/// bytes may be equal since the positions are always distinct.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RarePair {
+    pub(crate) rare1i: usize,
+    pub(crate) rare1: u8,
+    pub(crate) rare2i: usize,
+    pub(crate) rare2: u8,
+}
+
+impl ClassFinder {
+    /// Create a new finder for the given class needle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::{memmem::ClassFinder, ByteSet};
+    ///
+    /// let finder = ClassFinder::new(&[
+    ///     ByteSet::singleton(b'v'),
+    ///     ByteSet::range(b'0', b'9'),
+    ///     ByteSet::singleton(b'.'),
+    ///     ByteSet::range(b'0', b'9'),
+    /// ]);
+    /// assert_eq!(Some(8), finder.find(b"release v2.1"));
+    /// ```
+    pub fn new(needle: &[ByteSet]) -> ClassFinder {
+        let needle = needle.to_vec();
+        let scan = Scan::new(&needle);
+        ClassFinder { needle, scan }
+    }
+
+    /// Create a new finder from a hex signature.
+    ///
+    /// A signature is a sequence of the following items, optionally
+    /// separated by whitespace:
+    ///
+    /// * Two hex digits, such as `4D`, match that byte exactly.
+    /// * `??` matches any byte.
+    /// * A hex digit paired with `?`, such as `4?` or `?D`, matches any byte
+    ///   with the given high or low nibble.
+    /// * A set in brackets, such as `[30-39 41 61]`, matches any of the bytes
+    ///   or inclusive ranges of bytes in it. A set starting with `^`, such as
+    ///   `[^00]`, matches any byte not in it.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the signature is empty or malformed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::ClassFinder;
+    ///
+    /// let finder = ClassFinder::from_signature("E8 ?? ?? [00 FF] [00 FF]")?;
+    /// assert_eq!(5, finder.needle().len());
+    /// assert_eq!(Some(1), finder.find(b"\x90\xE8\x10\x20\xFF\xFF"));
+    ///
+    /// assert!(ClassFinder::from_signature("E8 ?").is_err());
+    /// # Ok::<(), memchr::memmem::ParseSignatureError>(())
+    /// ```
+    pub fn from_signature(
+        signature: &str,
+    ) -> Result<ClassFinder, ParseSignatureError> {
+        Ok(ClassFinder::new(&parse_signature(signature)?))
+    }
+
+    /// Returns the needle that this finder searches for.
+    #[inline]
+    pub fn needle(&self) -> &[ByteSet] {
+        &self.needle
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack.
+    ///
+    /// An empty needle matches at position `0`.
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let n = self.needle.len();
+        if haystack.len() < n {
+            return None;
+        }
+        match self.scan {
+            Scan::Naive => (0..=haystack.len() - n)
+                .find(|&i| is_match(&self.needle, &haystack[i..i + n])),
+            Scan::One { index, byte } => {
+                let window = &haystack[index..haystack.len() - n + index + 1];
+                memchr_iter(byte, window)
+                    .find(|&i| is_match(&self.needle, &haystack[i..i + n]))
+            }
+            Scan::Two(ref pair) => imp(pair, haystack, &self.needle),
+        }
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in the given haystack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::ClassFinder;
+    ///
+    /// let finder = ClassFinder::from_signature("0F [80-8F]").unwrap();
+    /// let haystack = b"\x0F\x84\x00\x0F\x0F\x8F\x0F\x90";
+    /// let matches: Vec<usize> = finder.find_iter(haystack).collect();
+    /// assert_eq!(vec![0, 4], matches);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> ClassFindIter<'h, 'a> {
+        ClassFindIter { haystack, finder: self, pos: 0 }
+    }
+}
+
+/// An iterator over non-overlapping occurrences of a class needle in a
+/// haystack.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// finder.
+#[derive(Debug)]
+pub struct ClassFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: &'n ClassFinder,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for ClassFindIter<'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        let result = self.finder.find(&self.haystack[self.pos..]);
+        match result {
+            None => None,
+            Some(i) => {
+                let pos = self.pos + i;
+                self.pos = pos + core::cmp::max(1, self.finder.needle.len());
+                Some(pos)
+            }
+        }
+    }
+}
+
+impl Scan {
+    /// Pick the strategy for the given needle by finding its two rarest fixed
+    /// positions, according to the pre-computed byte frequencies used for
+    /// substring search.
+    fn new(needle: &[ByteSet]) -> Scan {
+        let rank = |b: u8| BYTE_FREQUENCIES[b as usize];
+        let mut rare1: Option<(usize, u8)> = None;
+        let mut rare2: Option<(usize, u8)> = None;
+        for (i, set) in needle.iter().enumerate() {
+            let b = match set.single() {
+                None => continue,
+                Some(b) => b,
+            };
+            match rare1 {
+                Some((_, r1)) if rank(r1) <= rank(b) => match rare2 {
+                    Some((_, r2)) if rank(r2) <= rank(b) => {}
+                    _ => rare2 = Some((i, b)),
+                },
+                _ => {
+                    rare2 = rare1;
+                    rare1 = Some((i, b));
+                }
+            }
+        }
+        match (rare1, rare2) {
+            (None, _) => Scan::Naive,
+            (Some((index, byte)), None) => Scan::One { index, byte },
+            (Some((rare1i, rare1)), Some((rare2i, rare2))) => {
+                Scan::Two(RarePair { rare1i, rare1, rare2i, rare2 })
+            }
+        }
+    }
+}
+
+/// Returns true if and only if every byte in `chunk` is in the set at the
+/// corresponding position of `needle`. `chunk` must be at least as long as
+/// `needle`.
+#[inline(always)]
+pub(crate) fn is_match(needle: &[ByteSet], chunk: &[u8]) -> bool {
+    needle.iter().zip(chunk).all(|(set, &b)| set.contains(b))
+}
+
+/// Find the first occurrence of `needle` in `haystack`, using its two rarest
+/// fixed positions to find candidates.
+///
+/// On x86, this uses a vectorized pair-compare routine. Otherwise, this
+/// uses memchr on the rarest byte.
+fn imp(pair: &RarePair, haystack: &[u8], needle: &[ByteSet]) -> Option<usize> {
+    #[cfg(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
+    {
+        crate::memmem::x86::class::find(pair, haystack, needle)
+    }
+    #[cfg(not(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    )))]
+    {
+        find_fallback(pair, haystack, needle)
+    }
+}
+
+/// Find the first occurrence of `needle` in `haystack` by searching for its
+/// rarest fixed byte with memchr, and then checking its second rarest fixed
+/// byte before verifying the whole candidate.
+///
+/// The haystack must be at least as long as the needle.
+pub(crate) fn find_fallback(
+    pair: &RarePair,
+    haystack: &[u8],
+    needle: &[ByteSet],
+) -> Option<usize> {
+    let n = needle.len();
+    debug_assert!(haystack.len() >= n);
+    let window = &haystack[pair.rare1i..haystack.len() - n + pair.rare1i + 1];
+    memchr_iter(pair.rare1, window).find(|&i| {
+        haystack[i + pair.rare2i] == pair.rare2
+            && is_match(needle, &haystack[i..i + n])
+    })
+}
+
+/// An error that occurs when parsing a hex signature.
+///
+/// This is returned by [`ClassFinder::from_signature`].
+///
+/// This is only available when the `std` feature is enabled.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseSignatureError {
+    offset: usize,
+    kind: ParseSignatureErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum ParseSignatureErrorKind {
+    Empty,
+    UnexpectedByte(u8),
+    UnexpectedEnd,
+    InvalidRange(u8, u8),
+    EmptySet,
+}
+
+impl ParseSignatureError {
+    /// Returns the byte offset in the signature at which the error occurred.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseSignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::ParseSignatureErrorKind::*;
+
+        write!(f, "invalid signature at offset {}: ", self.offset)?;
+        match self.kind {
+            Empty => write!(f, "signature is empty"),
+            UnexpectedByte(b) => write!(
+                f,
+                "unexpected byte '{}'",
+                core::ascii::escape_default(b)
+            ),
+            UnexpectedEnd => write!(f, "unexpected end of signature"),
+            InvalidRange(start, end) => write!(
+                f,
+                "range start {:02X} is greater than range end {:02X}",
+                start, end
+            ),
+            EmptySet => write!(f, "byte set matches no bytes"),
+        }
+    }
+}
+
+impl std::error::Error for ParseSignatureError {}
+
+/// Parse a hex signature into a class needle. See
+/// [`ClassFinder::from_signature`] for the syntax.
+fn parse_signature(
+    signature: &str,
+) -> Result<Vec<ByteSet>, ParseSignatureError> {
+    let mut p = Parser { sig: signature.as_bytes(), pos: 0 };
+    let mut needle = vec![];
+    while let Some(b) = p.skip_whitespace() {
+        if b == b'[' {
+            needle.push(p.parse_set()?);
+        } else {
+            needle.push(p.parse_masked()?);
+        }
+    }
+    if needle.is_empty() {
+        return Err(p.err(ParseSignatureErrorKind::Empty));
+    }
+    Ok(needle)
+}
+
+struct Parser<'s> {
+    sig: &'s [u8],
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn err(&self, kind: ParseSignatureErrorKind) -> ParseSignatureError {
+        ParseSignatureError { offset: self.pos, kind }
+    }
+
+    /// Skip whitespace and return the next byte, if one exists.
+    fn skip_whitespace(&mut self) -> Option<u8> {
+        while self.pos < self.sig.len() {
+            if !self.sig[self.pos].is_ascii_whitespace() {
+                return Some(self.sig[self.pos]);
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    /// Parse one nibble, returning `None` for a `?` wildcard.
+    fn parse_nibble(&mut self) -> Result<Option<u8>, ParseSignatureError> {
+        let b = match self.sig.get(self.pos) {
+            None => {
+                return Err(self.err(ParseSignatureErrorKind::UnexpectedEnd))
+            }
+            Some(&b) => b,
+        };
+        let nibble = match b {
+            b'?' => None,
+            b'0'..=b'9' => Some(b - b'0'),
+            b'a'..=b'f' => Some(b - b'a' + 10),
+            b'A'..=b'F' => Some(b - b'A' + 10),
+            _ => {
+                return Err(
+                    self.err(ParseSignatureErrorKind::UnexpectedByte(b))
+                )
+            }
+        };
+        self.pos += 1;
+        Ok(nibble)
+    }
+
+    /// Parse a byte made of two nibbles, either of which may be a wildcard.
+    fn parse_masked(&mut self) -> Result<ByteSet, ParseSignatureError> {
+        let (mut value, mut mask) = (0, 0);
+        if let Some(hi) = self.parse_nibble()? {
+            value |= hi << 4;
+            mask |= 0xF0;
+        }
+        if let Some(lo) = self.parse_nibble()? {
+            value |= lo;
+            mask |= 0x0F;
+        }
+        Ok(ByteSet::masked(value, mask))
+    }
+
+    /// Parse a byte made of two nibbles, neither of which may be a wildcard.
+    fn parse_byte(&mut self) -> Result<u8, ParseSignatureError> {
+        let start = self.pos;
+        match (self.parse_nibble()?, self.parse_nibble()?) {
+            (Some(hi), Some(lo)) => Ok((hi << 4) | lo),
+            _ => {
+                self.pos = start;
+                Err(self.err(ParseSignatureErrorKind::UnexpectedByte(b'?')))
+            }
+        }
+    }
+
+    /// Parse a bracketed set of bytes and byte ranges. The parser must be
+    /// positioned at the opening bracket.
+    fn parse_set(&mut self) -> Result<ByteSet, ParseSignatureError> {
+        debug_assert_eq!(Some(&b'['), self.sig.get(self.pos));
+        let open = self.pos;
+        self.pos += 1;
+        let negate = self.sig.get(self.pos) == Some(&b'^');
+        if negate {
+            self.pos += 1;
+        }
+        let mut set = ByteSet::empty();
+        loop {
+            match self.skip_whitespace() {
+                None => {
+                    return Err(
+                        self.err(ParseSignatureErrorKind::UnexpectedEnd)
+                    )
+                }
+                Some(b']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(_) => {}
+            }
+            let range_pos = self.pos;
+            let start = self.parse_byte()?;
+            let end = if self.sig.get(self.pos) == Some(&b'-') {
+                self.pos += 1;
+                self.parse_byte()?
+            } else {
+                start
+            };
+            if start > end {
+                self.pos = range_pos;
+                return Err(self
+                    .err(ParseSignatureErrorKind::InvalidRange(start, end)));
+            }
+            set = set.union(&ByteSet::range(start, end));
+        }
+        if negate {
+            set = set.complement();
+        }
+        if set.is_empty() {
+            self.pos = open;
+            return Err(self.err(ParseSignatureErrorKind::EmptySet));
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{is_match, ClassFinder, ParseSignatureErrorKind::*};
+    use crate::ByteSet;
+
+    fn naive(needle: &[ByteSet], haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < needle.len() {
+            return None;
+        }
+        (0..=haystack.len() - needle.len())
+            .find(|&i| is_match(needle, &haystack[i..]))
+    }
+
+    fn err(sig: &str) -> (usize, super::ParseSignatureErrorKind) {
+        let e = ClassFinder::from_signature(sig).unwrap_err();
+        (e.offset, e.kind)
+    }
+
+    #[test]
+    fn parse() {
+        let f = ClassFinder::from_signature("4D 5a ?? 4? ?F [00 10-12] [^00]")
+            .unwrap();
+        let n = f.needle();
+        assert_eq!(7, n.len());
+        assert_eq!(Some(0x4D), n[0].single());
+        assert_eq!(Some(0x5A), n[1].single());
+        assert!(n[2].is_full());
+        assert_eq!(ByteSet::range(0x40, 0x4F), n[3]);
+        assert_eq!(ByteSet::masked(0x0F, 0x0F), n[4]);
+        assert_eq!(ByteSet::from_bytes(&[0x00, 0x10, 0x11, 0x12]), n[5]);
+        assert_eq!(255, n[6].len());
+        assert!(!n[6].contains(0));
+
+        let f = ClassFinder::from_signature("4D5A??").unwrap();
+        assert_eq!(3, f.needle().len());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!((0, Empty), err(""));
+        assert_eq!((3, Empty), err("   "));
+        assert_eq!((4, UnexpectedEnd), err("4D 5"));
+        assert_eq!((3, UnexpectedByte(b'x')), err("4D x0"));
+        assert_eq!((4, UnexpectedEnd), err("[00 "));
+        assert_eq!((1, InvalidRange(0x20, 0x10)), err("[20-10]"));
+        assert_eq!((1, UnexpectedByte(b'?')), err("[?0]"));
+        assert_eq!((0, EmptySet), err("[^00-FF]"));
+        assert_eq!((0, EmptySet), err("[]"));
+        assert_eq!(
+            "invalid signature at offset 3: unexpected byte 'x'",
+            ClassFinder::from_signature("4D x0").unwrap_err().to_string(),
+        );
+    }
+
+    #[test]
+    fn scans() {
+        // No fixed positions.
+        let f = ClassFinder::from_signature("?? [61-62]").unwrap();
+        assert_eq!(Some(1), f.find(b"\x00\x00b\x00"));
+        assert_eq!(Some(0), f.find(b"\x00a"));
+        assert_eq!(None, f.find(b"\x00"));
+        // One fixed position.
+        let f = ClassFinder::from_signature("?? 7A ??").unwrap();
+        assert_eq!(None, f.find(b"z"));
+        assert_eq!(None, f.find(b"zz"));
+        assert_eq!(Some(0), f.find(b"zzz"));
+        assert_eq!(Some(1), f.find(b"xyzw"));
+        // Two or more fixed positions, with long haystacks so that the
+        // vectorized routines are used.
+        let f = ClassFinder::from_signature("48 8B ?? ?? 89 5C 24").unwrap();
+        let mut haystack = vec![0x48; 100];
+        haystack.extend_from_slice(b"\x48\x8B\x00\x00\x89\x5C");
+        assert_eq!(None, f.find(&haystack));
+        haystack.push(0x24);
+        assert_eq!(Some(100), f.find(&haystack));
+        haystack.extend_from_slice(&[0x8B; 100]);
+        assert_eq!(Some(100), f.find(&haystack));
+        assert_eq!(vec![100], f.find_iter(&haystack).collect::<Vec<_>>());
+    }
+
+    quickcheck! {
+        fn qc_matches_naive(
+            needle: Vec<(u8, u8)>,
+            haystack: Vec<u8>
+        ) -> bool {
+            // Map everything into a small alphabet so that matches are
+            // likely, and use a mix of fixed bytes, small sets and
+            // wildcards.
+            let haystack: Vec<u8> =
+                haystack.iter().map(|&b| b'a' + b % 4).collect();
+            let needle: Vec<ByteSet> = needle
+                .iter()
+                .take(8)
+                .map(|&(kind, b)| match kind % 4 {
+                    0 => ByteSet::full(),
+                    1 => ByteSet::from_bytes(&[b'a' + b % 4, b'a' + b / 4 % 4]),
+                    _ => ByteSet::singleton(b'a' + b % 4),
+                })
+                .collect();
+            let f = ClassFinder::new(&needle);
+            let expected: Vec<usize> = {
+                let mut all = vec![];
+                let mut pos = 0;
+                while let Some(i) = naive(&needle, &haystack[pos..]) {
+                    all.push(pos + i);
+                    pos += i + needle.len().max(1);
+                    if pos > haystack.len() {
+                        break;
+                    }
+                }
+                all
+            };
+            naive(&needle, &haystack) == f.find(&haystack)
+                && expected == f.find_iter(&haystack).collect::<Vec<_>>()
+        }
+    }
+}
@@ -0,0 +1,287 @@
+// This module provides searches over columnar string arrays, where many short
+// strings are stored Arrow-style in one contiguous values buffer along with
+// an offsets array. Row `i` is `values[offsets[i]..offsets[i + 1]]`.
+//
+// Searching each row with its own call to `find` spends more time setting up
+// the search than searching on short rows. Instead, `rows_containing` scans
+// the values buffer once and maps each match back to its row with a binary
+// search over the offsets. Since matches are found in increasing order, the
+// binary search only ever needs to consider the rows after the last one
+// found.
+
+use crate::{
+    batch::{to_usize, Offset},
+    memmem::Finder,
+};
+
+impl<'n> Finder<'n> {
+    /// Returns the indices of the rows of a columnar string array that
+    /// contain this needle.
+    ///
+    /// The array is given as a values buffer and an offsets array, as in
+    /// Apache Arrow: row `i` is `values[offsets[i]..offsets[i + 1]]`, so an
+    /// array with `n` rows has `n + 1` offsets. Offsets must be
+    /// non-decreasing. A match must lie entirely within a row to count;
+    /// matches spanning the boundary between two rows are ignored.
+    ///
+    /// The values buffer is scanned once, and each match is mapped back to
+    /// its row. This avoids the per row overhead of starting a new search,
+    /// which dominates when rows are short.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Panics
+    ///
+    /// When an offset is out of bounds for `values`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let values = b"foobarquuxbazfo";
+    /// let offsets: &[u32] = &[0, 3, 6, 10, 13, 15];
+    /// let finder = Finder::new("ba");
+    /// assert_eq!(vec![1, 3], finder.rows_containing(values, offsets));
+    ///
+    /// // "fo" at the end of one row and "o" at the start of the next don't
+    /// // count as a match of "foo".
+    /// let values = b"xfooy";
+    /// let offsets: &[u32] = &[0, 3, 5];
+    /// let finder = Finder::new("foo");
+    /// assert!(finder.rows_containing(values, offsets).is_empty());
+    /// ```
+    pub fn rows_containing<O: Offset>(
+        &self,
+        values: &[u8],
+        offsets: &[O],
+    ) -> Vec<usize> {
+        let rows = offsets.len().saturating_sub(1);
+        if self.needle().is_empty() {
+            return (0..rows).collect();
+        }
+        let mut found = vec![];
+        if rows == 0 {
+            return found;
+        }
+        let end = to_usize(offsets[rows]);
+        let values = &values[..end];
+        let mut prestate = self.searcher.prefilter_state();
+        let mut row = 0;
+        let mut pos = to_usize(offsets[0]);
+        while row < rows && pos <= end {
+            let m = match self.searcher.find(&mut prestate, &values[pos..]) {
+                None => break,
+                Some(i) => pos + i,
+            };
+            row = row_of(offsets, row, rows, m);
+            let row_end = to_usize(offsets[row + 1]);
+            if m + self.needle().len() <= row_end {
+                found.push(row);
+            }
+            // Either this row contains a match, or the first match starting
+            // in this row spans into the next one. In both cases, nothing
+            // else in this row is of interest.
+            row += 1;
+            pos = row_end;
+        }
+        found
+    }
+
+    /// Returns the indices of the rows of a columnar string array that start
+    /// with this needle.
+    ///
+    /// See [`Finder::rows_containing`] for how the array is represented.
+    /// Since only the start of each row needs to be checked, this compares
+    /// the needle with each row directly instead of scanning the values
+    /// buffer.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Panics
+    ///
+    /// When an offset is out of bounds for `values`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let values = b"foobarfoofo";
+    /// let offsets: &[u64] = &[0, 3, 6, 9, 11];
+    /// let finder = Finder::new("fo");
+    /// assert_eq!(vec![0, 2, 3], finder.rows_starting_with(values, offsets));
+    /// ```
+    pub fn rows_starting_with<O: Offset>(
+        &self,
+        values: &[u8],
+        offsets: &[O],
+    ) -> Vec<usize> {
+        let needle = self.needle();
+        self.filter_rows(values, offsets, |row| row.starts_with(needle))
+    }
+
+    /// Returns the indices of the rows of a columnar string array that end
+    /// with this needle.
+    ///
+    /// See [`Finder::rows_containing`] for how the array is represented.
+    /// Since only the end of each row needs to be checked, this compares the
+    /// needle with each row directly instead of scanning the values buffer.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Panics
+    ///
+    /// When an offset is out of bounds for `values`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let values = b"foo.rsbar.cquux.rs";
+    /// let offsets: &[u32] = &[0, 6, 11, 18];
+    /// let finder = Finder::new(".rs");
+    /// assert_eq!(vec![0, 2], finder.rows_ending_with(values, offsets));
+    /// ```
+    pub fn rows_ending_with<O: Offset>(
+        &self,
+        values: &[u8],
+        offsets: &[O],
+    ) -> Vec<usize> {
+        let needle = self.needle();
+        self.filter_rows(values, offsets, |row| row.ends_with(needle))
+    }
+
+    /// Returns the indices of the rows for which the given predicate returns
+    /// true.
+    fn filter_rows<O: Offset>(
+        &self,
+        values: &[u8],
+        offsets: &[O],
+        mut predicate: impl FnMut(&[u8]) -> bool,
+    ) -> Vec<usize> {
+        let mut found = vec![];
+        let mut start = match offsets.first() {
+            None => return found,
+            Some(&o) => to_usize(o),
+        };
+        for (row, &o) in offsets[1..].iter().enumerate() {
+            let end = to_usize(o);
+            if predicate(&values[start..end]) {
+                found.push(row);
+            }
+            start = end;
+        }
+        found
+    }
+}
+
+/// Returns the row in `lo..rows` containing the position `at`. That is, the
+/// last row `r` such that `offsets[r] <= at`. When there are empty rows, this
+/// returns the last of them that starts at `at`, which is the only one that
+/// can contain a non-empty match at `at`.
+///
+/// `offsets[lo]` must be less than or equal to `at`, and `at` must be less
+/// than `offsets[rows]`.
+fn row_of<O: Offset>(
+    offsets: &[O],
+    lo: usize,
+    rows: usize,
+    at: usize,
+) -> usize {
+    let (mut lo, mut hi) = (lo, rows);
+    // Invariant: offsets[lo] <= at < offsets[hi].
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if to_usize(offsets[mid]) <= at {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use crate::memmem::Finder;
+
+    /// Build a columnar array from the given rows.
+    fn columnar(rows: &[Vec<u8>]) -> (Vec<u8>, Vec<u32>) {
+        let mut values = vec![];
+        let mut offsets = vec![0];
+        for row in rows {
+            values.extend_from_slice(row);
+            offsets.push(values.len() as u32);
+        }
+        (values, offsets)
+    }
+
+    fn naive(
+        rows: &[Vec<u8>],
+        predicate: impl Fn(&[u8]) -> bool,
+    ) -> Vec<usize> {
+        (0..rows.len()).filter(|&i| predicate(&rows[i])).collect()
+    }
+
+    #[test]
+    fn empty() {
+        let finder = Finder::new("a");
+        let offsets: &[u32] = &[];
+        assert!(finder.rows_containing(b"", offsets).is_empty());
+        assert!(finder.rows_starting_with(b"", offsets).is_empty());
+        assert!(finder.rows_containing(b"aaa", &[3u32]).is_empty());
+        assert_eq!(
+            vec![0, 1, 2],
+            Finder::new("").rows_containing(b"ab", &[0u32, 1, 1, 2])
+        );
+    }
+
+    #[test]
+    fn values_outside_rows() {
+        // Matches before the first offset or after the last are ignored.
+        let values = b"xxabcxx";
+        let finder = Finder::new("x");
+        assert!(finder.rows_containing(values, &[2u64, 3, 5]).is_empty());
+        assert_eq!(vec![1], finder.rows_containing(values, &[2u64, 3, 6]));
+    }
+
+    #[test]
+    fn empty_rows_at_match() {
+        let rows = vec![b"a".to_vec(), vec![], vec![], b"bc".to_vec()];
+        let (values, offsets) = columnar(&rows);
+        assert_eq!(
+            vec![3],
+            Finder::new("bc").rows_containing(&values, &offsets)
+        );
+        assert_eq!(
+            vec![3],
+            Finder::new("b").rows_containing(&values, &offsets)
+        );
+    }
+
+    quickcheck! {
+        fn qc_rows(rows: Vec<Vec<u8>>, needle: Vec<u8>) -> bool {
+            // Use a small alphabet so that matches are likely.
+            let rows: Vec<Vec<u8>> = rows
+                .iter()
+                .map(|r| r.iter().take(6).map(|&b| b % 2).collect())
+                .collect();
+            let needle: Vec<u8> = needle.iter().take(3).map(|&b| b % 2).collect();
+            let (values, offsets) = columnar(&rows);
+            let finder = Finder::new(&needle);
+            let contains = naive(&rows, |r| {
+                needle.is_empty()
+                    || r.windows(needle.len()).any(|w| w == &needle[..])
+            });
+            finder.rows_containing(&values, &offsets) == contains
+                && finder.rows_starting_with(&values, &offsets)
+                    == naive(&rows, |r| r.starts_with(&needle))
+                && finder.rows_ending_with(&values, &offsets)
+                    == naive(&rows, |r| r.ends_with(&needle))
+        }
+    }
+}
@@ -0,0 +1,429 @@
+// This module provides substring searches over the contents of a file.
+//
+// Regular files are memory mapped when the `memmap2` feature is enabled, and
+// searched just like an in-memory haystack. Everything else (pipes, sockets,
+// character devices, files that can't be mapped, and all files when
+// `memmap2` is disabled) is read incrementally into a fixed size buffer.
+//
+// When streaming, a match may straddle the boundary between two reads. After
+// searching a buffer without finding a match, the last `needle.len() - 1`
+// bytes are moved to the front of the buffer before reading more, so that
+// such a match is found once the rest of it has been read. Bytes before the
+// end of the last match are never kept, which preserves non-overlapping
+// semantics. Since at most `needle.len() - 1` bytes are kept, a buffer of at
+// least twice the needle's length always has room for more data, so memory
+// use is bounded regardless of the size of the file.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::memmem::{prefilter::PrefilterState, Finder};
+
+/// The default capacity of the buffer used when a file can't be memory
+/// mapped.
+const DEFAULT_BUFFER_CAPACITY: usize = 64 * (1 << 10);
+
+impl<'n> Finder<'n> {
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in the file at the given path.
+    ///
+    /// See [`Finder::find_iter_file`] for details.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// When the file can't be opened.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("ERROR");
+    /// for result in finder.find_iter_path("/var/log/syslog")? {
+    ///     println!("match at offset {}", result?);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn find_iter_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<FileFindIter<'_, 'n>> {
+        Ok(self.find_iter_file(File::open(path)?))
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in the given file, starting from its current position.
+    ///
+    /// Matches are reported as absolute byte offsets from that position. They
+    /// are exactly the offsets that [`Finder::find_iter`] would report on the
+    /// file's contents.
+    ///
+    /// When the `memmap2` feature is enabled and `file` is a non-empty
+    /// regular file, it is memory mapped and searched in place. Otherwise, or
+    /// if mapping fails, it is read incrementally through a fixed size
+    /// buffer, so that pipes and other special files can be searched too. In
+    /// either case, files larger than the available memory can be searched.
+    ///
+    /// An I/O error while reading is yielded by the iterator, after which
+    /// the iterator is exhausted.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Memory maps
+    ///
+    /// A memory mapped file may be changed by other processes while it is
+    /// being searched, in which case the matches reported are unspecified.
+    /// On some platforms, truncating a file while it is mapped terminates the
+    /// process with a bus error. Disable the `memmap2` feature if that is not
+    /// acceptable.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("needle");
+    /// let file = File::open("haystack.txt")?;
+    /// let offsets = finder
+    ///     .find_iter_file(file)
+    ///     .collect::<std::io::Result<Vec<u64>>>()?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn find_iter_file(&self, file: File) -> FileFindIter<'_, 'n> {
+        #[cfg(feature = "memmap2")]
+        {
+            if let Some(map) = mmap(&file) {
+                return FileFindIter::new(self, Source::Mmap { map, pos: 0 });
+            }
+        }
+        self.find_iter_file_buffered(file, DEFAULT_BUFFER_CAPACITY)
+    }
+
+    /// Like `find_iter_file`, but never uses a memory map, and uses a buffer
+    /// with at least the given capacity.
+    fn find_iter_file_buffered(
+        &self,
+        file: File,
+        capacity: usize,
+    ) -> FileFindIter<'_, 'n> {
+        let capacity =
+            core::cmp::max(capacity, 2 * self.needle().len()).max(1);
+        let stream = Stream {
+            file,
+            buf: vec![0; capacity],
+            offset: 0,
+            pos: 0,
+            filled: 0,
+            eof: false,
+        };
+        FileFindIter::new(self, Source::Stream(stream))
+    }
+}
+
+/// An iterator over non-overlapping substring matches in a file.
+///
+/// Matches are reported by the absolute byte offset at which they begin. An
+/// I/O error is reported at most once, after which the iterator yields no
+/// more items.
+///
+/// `'f` is the lifetime of the finder while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FileFindIter<'f, 'n> {
+    finder: &'f Finder<'n>,
+    prestate: PrefilterState,
+    source: Source,
+    done: bool,
+}
+
+impl<'f, 'n> FileFindIter<'f, 'n> {
+    fn new(finder: &'f Finder<'n>, source: Source) -> FileFindIter<'f, 'n> {
+        let prestate = finder.searcher.prefilter_state();
+        FileFindIter { finder, prestate, source, done: false }
+    }
+}
+
+impl<'f, 'n> Iterator for FileFindIter<'f, 'n> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<io::Result<u64>> {
+        if self.done {
+            return None;
+        }
+        let result = match self.source {
+            #[cfg(feature = "memmap2")]
+            Source::Mmap { ref map, ref mut pos } => {
+                find_mapped(self.finder, &mut self.prestate, map, pos)
+            }
+            Source::Stream(ref mut stream) => {
+                stream.find(self.finder, &mut self.prestate)
+            }
+        };
+        match result {
+            Some(Ok(_)) => {}
+            None | Some(Err(_)) => self.done = true,
+        }
+        result
+    }
+}
+
+/// Where the contents of a file being searched come from.
+#[derive(Debug)]
+enum Source {
+    /// The entire file, memory mapped, along with the position at which the
+    /// next search starts.
+    #[cfg(feature = "memmap2")]
+    Mmap { map: memmap2::Mmap, pos: usize },
+    /// The file, read incrementally.
+    Stream(Stream),
+}
+
+/// Memory map the given file from its current position, if it is a regular
+/// file with some bytes left that fit in the address space.
+#[cfg(feature = "memmap2")]
+fn mmap(mut file: &File) -> Option<memmap2::Mmap> {
+    use core::convert::TryFrom;
+    use std::io::{Seek, SeekFrom};
+
+    let md = file.metadata().ok()?;
+    if !md.is_file() {
+        return None;
+    }
+    // `Seek::stream_position` requires Rust 1.51.
+    #[allow(clippy::seek_from_current)]
+    let start = file.seek(SeekFrom::Current(0)).ok()?;
+    let len = md.len().checked_sub(start)?;
+    if len == 0 || usize::try_from(len).is_err() {
+        return None;
+    }
+    let mut options = memmap2::MmapOptions::new();
+    options.offset(start);
+    // SAFETY: Mapping a file is only unsafe because the file may be changed
+    // while it is mapped. This is documented on `Finder::find_iter_file`.
+    unsafe { options.map(file).ok() }
+}
+
+/// Find the next match in a memory mapped file, starting at `pos`.
+#[cfg(feature = "memmap2")]
+fn find_mapped(
+    finder: &Finder<'_>,
+    prestate: &mut PrefilterState,
+    haystack: &[u8],
+    pos: &mut usize,
+) -> Option<io::Result<u64>> {
+    if *pos > haystack.len() {
+        return None;
+    }
+    let i = *pos + finder.searcher.find(prestate, &haystack[*pos..])?;
+    *pos = i + core::cmp::max(1, finder.needle().len());
+    Some(Ok(i as u64))
+}
+
+/// A file being read incrementally into a buffer.
+#[derive(Debug)]
+struct Stream {
+    file: File,
+    /// The buffer, whose length is its capacity.
+    buf: Vec<u8>,
+    /// The offset in the file corresponding to the start of `buf`.
+    offset: u64,
+    /// The position in `buf` at which the next search starts.
+    pos: usize,
+    /// The number of bytes at the start of `buf` that have been read.
+    filled: usize,
+    /// Whether the end of the file has been reached.
+    eof: bool,
+}
+
+impl Stream {
+    /// Find the next match, reading more of the file as needed.
+    fn find(
+        &mut self,
+        finder: &Finder<'_>,
+        prestate: &mut PrefilterState,
+    ) -> Option<io::Result<u64>> {
+        let nlen = finder.needle().len();
+        loop {
+            if self.pos <= self.filled {
+                let haystack = &self.buf[self.pos..self.filled];
+                if let Some(i) = finder.searcher.find(prestate, haystack) {
+                    let start = self.pos + i;
+                    // An empty match at the end of the buffer may also be
+                    // found at the start of the next one, so it is only
+                    // reported once there is nothing left to read.
+                    if self.eof || start < self.filled {
+                        self.pos = start + core::cmp::max(1, nlen);
+                        return Some(Ok(self.offset + start as u64));
+                    }
+                }
+            }
+            if self.eof {
+                return None;
+            }
+            // No match starts before `keep`, either because it's before the
+            // end of the previous match, or because there isn't enough room
+            // for the needle before the end of the buffer.
+            let keep = core::cmp::max(
+                self.pos,
+                self.filled.saturating_sub(nlen.saturating_sub(1)),
+            );
+            self.buf.copy_within(keep..self.filled, 0);
+            self.offset += keep as u64;
+            self.pos = 0;
+            self.filled -= keep;
+            if let Err(err) = self.fill() {
+                return Some(Err(err));
+            }
+        }
+    }
+
+    /// Read more bytes into the buffer, setting `eof` if there are none left.
+    fn fill(&mut self) -> io::Result<()> {
+        loop {
+            match self.file.read(&mut self.buf[self.filled..]) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.filled += n,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {
+                    continue
+                }
+                Err(err) => return Err(err),
+            }
+            return Ok(());
+        }
+    }
+}
+
+// WASI has no temporary directory to write the test files to.
+#[cfg(all(test, not(miri), not(target_os = "wasi")))]
+mod tests {
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::PathBuf,
+    };
+
+    use crate::memmem::Finder;
+
+    /// A file in the temporary directory that is removed when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> TempFile {
+            let path = std::env::temp_dir().join(format!(
+                "memchr-file-{}-{}",
+                std::process::id(),
+                name
+            ));
+            File::create(&path).unwrap().write_all(contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn haystack() -> Vec<u8> {
+        let mut haystack = vec![];
+        let mut i = 0u64;
+        while haystack.len() < 100_000 {
+            // A simple LCG to pick between a few short runs.
+            i = i.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let run = [&b"abab"[..], b"ba", b"aab", b"x"][(i >> 62) as usize];
+            haystack.extend_from_slice(run);
+        }
+        haystack
+    }
+
+    #[test]
+    fn buffered() {
+        let haystack = haystack();
+        let file = TempFile::new("buffered", &haystack);
+        for &needle in &["", "a", "ab", "aba", "abab", "xab", "zzz"] {
+            let finder = Finder::new(needle);
+            let expected: Vec<u64> =
+                finder.find_iter(&haystack).map(|i| i as u64).collect();
+            // Small buffers make matches straddle reads often.
+            for &capacity in &[1, 2, 3, 7, 64, 1 << 20] {
+                let got: Vec<u64> = finder
+                    .find_iter_file_buffered(
+                        File::open(&file.0).unwrap(),
+                        capacity,
+                    )
+                    .map(Result::unwrap)
+                    .collect();
+                assert_eq!(expected, got, "{:?} {}", needle, capacity);
+            }
+        }
+    }
+
+    #[test]
+    fn path() {
+        let haystack = haystack();
+        let file = TempFile::new("path", &haystack);
+        for &needle in &["", "ab", "xab", "zzz"] {
+            let finder = Finder::new(needle);
+            let expected: Vec<u64> =
+                finder.find_iter(&haystack).map(|i| i as u64).collect();
+            let got: Vec<u64> = finder
+                .find_iter_path(&file.0)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(expected, got, "{:?}", needle);
+        }
+    }
+
+    #[test]
+    fn current_position() {
+        use std::io::{Seek, SeekFrom};
+
+        let file = TempFile::new("position", b"abcabcabc");
+        let finder = Finder::new("abc");
+        let mut f = File::open(&file.0).unwrap();
+        f.seek(SeekFrom::Start(2)).unwrap();
+        let got: Vec<u64> =
+            finder.find_iter_file(f).map(Result::unwrap).collect();
+        assert_eq!(vec![1, 4], got);
+    }
+
+    #[test]
+    fn empty_file() {
+        let file = TempFile::new("empty", b"");
+        let finder = Finder::new("");
+        let got: Vec<u64> = finder
+            .find_iter_path(&file.0)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(vec![0], got);
+        assert_eq!(
+            0,
+            Finder::new("a").find_iter_path(&file.0).unwrap().count()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let finder = Finder::new("a");
+        let missing = std::env::temp_dir().join("memchr-file-does-not-exist");
+        assert!(finder.find_iter_path(&missing).is_err());
+
+        // Reading a directory fails on Unix, which is reported once.
+        #[cfg(unix)]
+        {
+            let mut it = finder.find_iter_path(std::env::temp_dir()).unwrap();
+            assert!(it.next().unwrap().is_err());
+            assert!(it.next().is_none());
+        }
+    }
+}
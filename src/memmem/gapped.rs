@@ -0,0 +1,524 @@
+use core::ops::Range;
+
+use crate::memmem::Finder;
+
+/// A searcher for a sequence of literal segments separated by bounded gaps.
+///
+/// A gapped finder is built from a first segment, followed by any number of
+/// additional segments, each with a minimum and maximum gap. The gap is the
+/// number of bytes between the end of the previous segment and the start of
+/// the next one. For example, "`password` followed by `=` within 16 bytes"
+/// corresponds to the segment `password`, followed by the segment `=` with a
+/// gap of `0..=15`.
+///
+/// Matches are reported as a range of byte offsets, starting at the first
+/// segment and ending after the last segment. When there is more than one way
+/// to match, the match with the leftmost starting position is reported, and
+/// among those, the one with the leftmost ending position. Iterating over
+/// matches yields non-overlapping matches.
+///
+/// Each segment is searched for with its own [`Finder`].
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Complexity
+///
+/// Each segment's occurrences are visited at most once per search, in order,
+/// regardless of how often they repeat or how large the gaps are. Notably,
+/// a frequently occurring first segment combined with a large maximum gap does
+/// not cause the bytes that follow each occurrence to be searched again.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::GappedFinder;
+///
+/// let finder = GappedFinder::new("password").then(0, 15, "=");
+/// let haystack = b"user=me password   =  hunter2";
+/// assert_eq!(Some(8..20), finder.find(haystack));
+/// assert_eq!(None, finder.find(b"password is a secret that is never = set"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GappedFinder<'n> {
+    /// The finders for each segment, in order.
+    segments: Vec<Finder<'n>>,
+    /// The gap preceding each segment, as an inclusive `(min, max)` pair. The
+    /// gap for the first segment is unused and always `(0, 0)`.
+    gaps: Vec<(usize, usize)>,
+}
+
+impl<'n> GappedFinder<'n> {
+    /// Create a new gapped finder whose first segment is `first`.
+    #[inline]
+    pub fn new<B: ?Sized + AsRef<[u8]>>(first: &'n B) -> GappedFinder<'n> {
+        GappedFinder { segments: vec![Finder::new(first)], gaps: vec![(0, 0)] }
+    }
+
+    /// Add a segment that must start at least `min_gap` and at most `max_gap`
+    /// bytes after the end of the previous segment.
+    ///
+    /// Use `usize::MAX` for `max_gap` to express an unbounded gap.
+    ///
+    /// # Panics
+    ///
+    /// When `min_gap > max_gap`.
+    #[inline]
+    pub fn then<B: ?Sized + AsRef<[u8]>>(
+        mut self,
+        min_gap: usize,
+        max_gap: usize,
+        segment: &'n B,
+    ) -> GappedFinder<'n> {
+        assert!(
+            min_gap <= max_gap,
+            "minimum gap ({}) must not exceed maximum gap ({})",
+            min_gap,
+            max_gap,
+        );
+        self.segments.push(Finder::new(segment));
+        self.gaps.push((min_gap, max_gap));
+        self
+    }
+
+    /// Returns the span of the first match in `haystack`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::GappedFinder;
+    ///
+    /// let finder = GappedFinder::new("BEGIN").then(0, 4096, "END");
+    /// assert_eq!(Some(4..17), finder.find(b"xx\n BEGIN foo END"));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        GappedSearch::new(self.segments.len()).find(self, haystack)
+    }
+
+    /// Returns an iterator over all non-overlapping matches in `haystack`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::GappedFinder;
+    ///
+    /// let finder = GappedFinder::new("a").then(1, 2, "b");
+    /// let matches: Vec<_> = finder.find_iter(b"ab a-b a--b a---b").collect();
+    /// assert_eq!(vec![3..6, 7..11], matches);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> GappedFindIter<'h, 'a> {
+        GappedFindIter {
+            haystack,
+            finder: self.as_ref(),
+            search: GappedSearch::new(self.segments.len()),
+            pos: 0,
+        }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows its segments.
+    #[inline]
+    pub fn into_owned(self) -> GappedFinder<'static> {
+        GappedFinder {
+            segments: self
+                .segments
+                .into_iter()
+                .map(Finder::into_owned)
+                .collect(),
+            gaps: self.gaps,
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    #[inline]
+    pub fn as_ref(&self) -> GappedFinder<'_> {
+        GappedFinder {
+            segments: self.segments.iter().map(Finder::as_ref).collect(),
+            gaps: self.gaps.clone(),
+        }
+    }
+}
+
+/// An iterator over non-overlapping matches of a [`GappedFinder`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// segments.
+#[derive(Debug)]
+pub struct GappedFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: GappedFinder<'n>,
+    search: GappedSearch,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for GappedFindIter<'h, 'n> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        self.search.restart(self.pos);
+        let m = self.search.find(&self.finder, self.haystack)?;
+        self.pos = if m.start == m.end { m.end + 1 } else { m.end };
+        Some(m)
+    }
+}
+
+/// The mutable state of a gapped search.
+///
+/// The search is a pipeline of stages, one per segment. Stage `i` produces
+/// the occurrences of segment `i` that can be reached from an occurrence of
+/// segment `i - 1` with a gap in range, in increasing order. Each occurrence
+/// is tagged with its origin: the leftmost start of a first segment occurrence
+/// from which it can be reached. Since the windows of valid predecessors only
+/// move forward as the occurrence moves forward, origins never decrease within
+/// a stage. So the first occurrence produced by the last stage is the match
+/// with the leftmost start, and for that start, the leftmost end.
+///
+/// Every stage only ever needs to look at one occurrence from the previous
+/// stage: the leftmost one that hasn't been ruled out yet. If it's too far
+/// left for the current occurrence, then it's too far left for every later
+/// occurrence too, and can be discarded. If it's too far right, then the
+/// current stage can skip directly to the first position that could follow
+/// it.
+#[derive(Clone, Debug)]
+struct GappedSearch {
+    stages: Vec<Stage>,
+}
+
+#[derive(Clone, Debug)]
+struct Stage {
+    /// The position at which to look for the next occurrence of this stage's
+    /// segment.
+    cursor: usize,
+    /// The leftmost occurrence (position, origin) produced by this stage that
+    /// hasn't been ruled out by the next stage yet.
+    peek: Option<(usize, usize)>,
+    /// Whether this stage has produced all of its occurrences.
+    done: bool,
+    /// The result of the last search for this stage's segment.
+    scanner: Scanner,
+}
+
+/// A cache of the last search for a segment.
+///
+/// If a search starting at `from` found the next occurrence at `next`, then
+/// any search starting in `from..=next` will find the same occurrence. Since a
+/// stage's cursor often jumps forward without passing an occurrence, this
+/// avoids searching the same bytes twice.
+#[derive(Clone, Copy, Debug)]
+struct Scanner {
+    from: usize,
+    next: Option<usize>,
+    valid: bool,
+}
+
+impl GappedSearch {
+    fn new(len: usize) -> GappedSearch {
+        let stage = Stage {
+            cursor: 0,
+            peek: None,
+            done: false,
+            scanner: Scanner { from: 0, next: None, valid: false },
+        };
+        GappedSearch { stages: vec![stage; len] }
+    }
+
+    /// Reset every stage to start searching at `at`, keeping the search
+    /// caches of each segment.
+    fn restart(&mut self, at: usize) {
+        for stage in self.stages.iter_mut() {
+            stage.cursor = at;
+            stage.peek = None;
+            stage.done = false;
+        }
+    }
+
+    fn find(
+        &mut self,
+        finder: &GappedFinder<'_>,
+        haystack: &[u8],
+    ) -> Option<Range<usize>> {
+        let last = self.stages.len() - 1;
+        let (pos, origin) = self.next(finder, haystack, last)?;
+        Some(origin..pos + finder.segments[last].needle().len())
+    }
+
+    /// Produce the next occurrence of stage `i` as a `(position, origin)`
+    /// pair.
+    fn next(
+        &mut self,
+        finder: &GappedFinder<'_>,
+        haystack: &[u8],
+        i: usize,
+    ) -> Option<(usize, usize)> {
+        if self.stages[i].done {
+            return None;
+        }
+        loop {
+            let q = match self.occurrence(finder, haystack, i) {
+                None => {
+                    self.stages[i].done = true;
+                    return None;
+                }
+                Some(q) => q,
+            };
+            if i == 0 {
+                self.stages[0].cursor = q + 1;
+                return Some((q, q));
+            }
+
+            let (min, max) = finder.gaps[i];
+            let plen = finder.segments[i - 1].needle().len();
+            // Find the leftmost occurrence from the previous stage that isn't
+            // too far to the left of `q`.
+            let (p, origin) = loop {
+                let peek = match self.stages[i - 1].peek {
+                    Some(peek) => peek,
+                    None => match self.next(finder, haystack, i - 1) {
+                        None => {
+                            self.stages[i].done = true;
+                            return None;
+                        }
+                        Some(peek) => {
+                            self.stages[i - 1].peek = Some(peek);
+                            peek
+                        }
+                    },
+                };
+                if peek.0.saturating_add(plen).saturating_add(max) < q {
+                    self.stages[i - 1].peek = None;
+                    continue;
+                }
+                break peek;
+            };
+            let earliest = p.saturating_add(plen).saturating_add(min);
+            if earliest <= q {
+                self.stages[i].cursor = q + 1;
+                return Some((q, origin));
+            }
+            // No occurrence before `earliest` can follow `p`, or anything
+            // after `p`, so skip ahead.
+            if earliest > haystack.len() {
+                self.stages[i].done = true;
+                return None;
+            }
+            self.stages[i].cursor = earliest;
+        }
+    }
+
+    /// Return the next occurrence of segment `i` at or after its stage's
+    /// cursor.
+    fn occurrence(
+        &mut self,
+        finder: &GappedFinder<'_>,
+        haystack: &[u8],
+        i: usize,
+    ) -> Option<usize> {
+        let stage = &mut self.stages[i];
+        let at = stage.cursor;
+        let scanner = &mut stage.scanner;
+        if scanner.valid && scanner.from <= at {
+            match scanner.next {
+                None => return None,
+                Some(next) if at <= next => return Some(next),
+                Some(_) => {}
+            }
+        }
+        let next = if at > haystack.len() {
+            None
+        } else {
+            finder.segments[i].find(&haystack[at..]).map(|j| at + j)
+        };
+        *scanner = Scanner { from: at, next, valid: true };
+        next
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use core::ops::Range;
+
+    use quickcheck::quickcheck;
+
+    use super::GappedFinder;
+
+    /// A naive gapped search that tries every combination of segment
+    /// occurrences.
+    fn naive(
+        segments: &[&[u8]],
+        gaps: &[(usize, usize)],
+        haystack: &[u8],
+        start: usize,
+    ) -> Option<Range<usize>> {
+        fn rest(
+            segments: &[&[u8]],
+            gaps: &[(usize, usize)],
+            haystack: &[u8],
+            at: usize,
+        ) -> Option<usize> {
+            if segments.is_empty() {
+                return Some(at);
+            }
+            let (min, max) = gaps[0];
+            let lo = at.saturating_add(min);
+            let hi = at.saturating_add(max);
+            let mut best = None;
+            let mut pos = lo;
+            while pos <= hi && pos + segments[0].len() <= haystack.len() {
+                if haystack[pos..].starts_with(segments[0]) {
+                    let end = pos + segments[0].len();
+                    if let Some(e) =
+                        rest(&segments[1..], &gaps[1..], haystack, end)
+                    {
+                        best = Some(best.map_or(e, |b: usize| b.min(e)));
+                    }
+                }
+                pos += 1;
+            }
+            best
+        }
+        for s in start..=haystack.len() {
+            if haystack[s..].starts_with(segments[0]) {
+                let end = s + segments[0].len();
+                if let Some(e) =
+                    rest(&segments[1..], &gaps[1..], haystack, end)
+                {
+                    return Some(s..e);
+                }
+            }
+        }
+        None
+    }
+
+    fn naive_all(
+        segments: &[&[u8]],
+        gaps: &[(usize, usize)],
+        haystack: &[u8],
+    ) -> Vec<Range<usize>> {
+        let mut matches = vec![];
+        let mut pos = 0;
+        while pos <= haystack.len() {
+            match naive(segments, gaps, haystack, pos) {
+                None => break,
+                Some(m) => {
+                    pos = if m.start == m.end { m.end + 1 } else { m.end };
+                    matches.push(m);
+                }
+            }
+        }
+        matches
+    }
+
+    fn build<'a>(
+        segments: &[&'a [u8]],
+        gaps: &[(usize, usize)],
+    ) -> GappedFinder<'a> {
+        let mut finder = GappedFinder::new(segments[0]);
+        for (seg, &(min, max)) in segments[1..].iter().zip(&gaps[1..]) {
+            finder = finder.then(min, max, *seg);
+        }
+        finder
+    }
+
+    fn check(segments: &[&[u8]], gaps: &[(usize, usize)], haystack: &[u8]) {
+        let finder = build(segments, gaps);
+        assert_eq!(
+            naive(segments, gaps, haystack, 0),
+            finder.find(haystack),
+            "segments: {:?}, gaps: {:?}, haystack: {:?}",
+            segments,
+            gaps,
+            haystack,
+        );
+        assert_eq!(
+            naive_all(segments, gaps, haystack),
+            finder.find_iter(haystack).collect::<Vec<_>>(),
+            "segments: {:?}, gaps: {:?}, haystack: {:?}",
+            segments,
+            gaps,
+            haystack,
+        );
+    }
+
+    #[test]
+    fn simple() {
+        check(&[b"a"], &[(0, 0)], b"xaxa");
+        check(&[b"a", b"b"], &[(0, 0), (0, 0)], b"abab");
+        check(&[b"a", b"b"], &[(0, 0), (1, 2)], b"ab a-b a--b a---b");
+        check(&[b"a", b"b"], &[(0, 0), (0, usize::MAX)], b"a------b");
+        check(&[b"aa", b"aa"], &[(0, 0), (0, 0)], b"aaaaaaa");
+        check(&[b"", b""], &[(0, 0), (0, 0)], b"abc");
+        check(&[b"a", b""], &[(0, 0), (2, 3)], b"aaaa");
+        check(&[b"a", b"b", b"c"], &[(0, 0), (0, 1), (0, 1)], b"aab-bc-c");
+        // The leftmost start doesn't come from the earliest placement of
+        // the middle segment.
+        check(&[b"a", b"b", b"c"], &[(0, 0), (0, 5), (3, 3)], b"abxbxxxc");
+    }
+
+    #[test]
+    fn repeated_anchor() {
+        // A frequently repeating anchor with a huge window must not cause
+        // quadratic behavior. This would take a very long time if every
+        // occurrence of 'a' rescanned the window after it.
+        let mut haystack = vec![b'a'; 1 << 20];
+        haystack.extend_from_slice(b"b");
+        let finder = GappedFinder::new("a").then(0, usize::MAX, "b");
+        assert_eq!(Some(0..haystack.len()), finder.find(&haystack));
+        let finder = GappedFinder::new("a").then(0, 1 << 19, "b");
+        assert_eq!(
+            Some((1 << 19) - 1..haystack.len()),
+            finder.find(&haystack)
+        );
+        assert_eq!(1, finder.find_iter(&haystack).count());
+    }
+
+    #[test]
+    #[should_panic]
+    fn bad_gap() {
+        GappedFinder::new("a").then(2, 1, "b");
+    }
+
+    #[test]
+    fn owned() {
+        let finder = {
+            let seg = String::from("b");
+            GappedFinder::new("a").then(0, 1, &seg).into_owned()
+        };
+        assert_eq!(Some(1..4), finder.find(b"xa-b"));
+    }
+
+    quickcheck! {
+        fn qc_matches_naive(
+            segments: Vec<Vec<u8>>,
+            gaps: Vec<(u8, u8)>,
+            haystack: Vec<u8>
+        ) -> bool {
+            // Use a small alphabet, short segments and small gaps so that
+            // matches are likely.
+            let small = |b: &u8| b'a' + b % 3;
+            let haystack: Vec<u8> = haystack.iter().map(small).collect();
+            let mut segments: Vec<Vec<u8>> = segments
+                .iter()
+                .take(4)
+                .map(|s| s.iter().take(3).map(small).collect())
+                .collect();
+            if segments.is_empty() {
+                segments.push(b"a".to_vec());
+            }
+            let mut ranges = vec![(0, 0)];
+            for i in 1..segments.len() {
+                let (a, b) = gaps.get(i).cloned().unwrap_or((0, 0));
+                let (a, b) = ((a % 8) as usize, (b % 8) as usize);
+                ranges.push((a.min(b), a.max(b)));
+            }
+            let segs: Vec<&[u8]> = segments.iter().map(|s| &s[..]).collect();
+            check(&segs, &ranges, &haystack);
+            true
+        }
+    }
+}
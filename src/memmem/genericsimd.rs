@@ -1,6 +1,14 @@
 use core::mem::size_of;
 
 use crate::memmem::{util::memcmp, vector::Vector, NeedleInfo};
+#[cfg(feature = "std")]
+use crate::{
+    memmem::{
+        class::{is_match, RarePair},
+        hamming,
+    },
+    ByteSet,
+};
 
 /// The minimum length of a needle required for this algorithm. The minimum
 /// is 2 since a length of 1 should just use memchr and a length of 0 isn't
@@ -264,3 +272,145 @@ fn diff(a: *const u8, b: *const u8) -> usize {
     debug_assert!(a >= b);
     (a as usize) - (b as usize)
 }
+
+/// Returns the minimum length of haystack that is needed for
+/// `fwd_find_class` to work for a particular vector.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) fn class_min_haystack_len<V: Vector>(pair: &RarePair) -> usize {
+    core::cmp::max(pair.rare1i, pair.rare2i) + size_of::<V>()
+}
+
+/// Searches the given haystack for the given class needle, using the two
+/// rarest fixed positions in `pair` to find candidates.
+///
+/// This is the same algorithm as `fwd_find`, except candidates are verified
+/// by checking each haystack byte against the set at the corresponding
+/// position of the needle. Since class needles have no maximum length, the
+/// worst case is multiplicative in the size of the needle and haystack.
+///
+/// # Panics
+///
+/// When the given haystack has a length smaller than
+/// `class_min_haystack_len`.
+///
+/// # Safety
+///
+/// Same as `fwd_find`.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) unsafe fn fwd_find_class<V: Vector>(
+    pair: &RarePair,
+    haystack: &[u8],
+    needle: &[ByteSet],
+) -> Option<usize> {
+    if haystack.len() < needle.len() {
+        return None;
+    }
+    let min_haystack_len = class_min_haystack_len::<V>(pair);
+    assert!(haystack.len() >= min_haystack_len, "haystack too small");
+
+    let rare1chunk = V::splat(pair.rare1);
+    let rare2chunk = V::splat(pair.rare2);
+
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let max_ptr = end_ptr.sub(min_haystack_len);
+    let mut ptr = start_ptr;
+    while ptr <= max_ptr {
+        let m = fwd_find_class_in_chunk(
+            pair, needle, ptr, end_ptr, rare1chunk, rare2chunk, !0,
+        );
+        if let Some(chunki) = m {
+            return Some(matched(start_ptr, ptr, chunki));
+        }
+        ptr = ptr.add(size_of::<V>());
+    }
+    if ptr < end_ptr {
+        if diff(end_ptr, ptr) < needle.len() {
+            return None;
+        }
+        // See fwd_find for how the mask excludes positions that have already
+        // been checked.
+        let overlap = diff(ptr, max_ptr);
+        let mask = !((1 << overlap) - 1);
+        ptr = max_ptr;
+        let m = fwd_find_class_in_chunk(
+            pair, needle, ptr, end_ptr, rare1chunk, rare2chunk, mask,
+        );
+        if let Some(chunki) = m {
+            return Some(matched(start_ptr, ptr, chunki));
+        }
+    }
+    None
+}
+
+/// Search for an occurrence of the two rare fixed bytes of a class needle in
+/// the chunk pointed to by ptr. When an occurrence is found, each byte of the
+/// candidate is checked against the needle.
+///
+/// # Safety
+///
+/// Same as `fwd_find_in_chunk`.
+#[cfg(feature = "std")]
+#[inline(always)]
+unsafe fn fwd_find_class_in_chunk<V: Vector>(
+    pair: &RarePair,
+    needle: &[ByteSet],
+    ptr: *const u8,
+    end_ptr: *const u8,
+    rare1chunk: V,
+    rare2chunk: V,
+    mask: u32,
+) -> Option<usize> {
+    let chunk0 = V::load_unaligned(ptr.add(pair.rare1i));
+    let chunk1 = V::load_unaligned(ptr.add(pair.rare2i));
+
+    let eq0 = chunk0.cmpeq(rare1chunk);
+    let eq1 = chunk1.cmpeq(rare2chunk);
+
+    let mut match_offsets = eq0.and(eq1).movemask() & mask;
+    while match_offsets != 0 {
+        let offset = match_offsets.trailing_zeros() as usize;
+        let ptr = ptr.add(offset);
+        if end_ptr.sub(needle.len()) < ptr {
+            return None;
+        }
+        let chunk = core::slice::from_raw_parts(ptr, needle.len());
+        if is_match(needle, chunk) {
+            return Some(offset);
+        }
+        match_offsets &= match_offsets - 1;
+    }
+    None
+}
+
+/// Count the number of positions at which `a` and `b` differ, stopping
+/// early once the count exceeds `limit`. When stopping early, some value
+/// greater than `limit` is returned. `a` and `b` must have the same length.
+///
+/// # Safety
+///
+/// Same as `fwd_find`.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) unsafe fn count_mismatches<V: Vector>(
+    a: &[u8],
+    b: &[u8],
+    limit: usize,
+) -> usize {
+    debug_assert_eq!(a.len(), b.len());
+    let (aptr, bptr) = (a.as_ptr(), b.as_ptr());
+    let mut count = 0;
+    let mut i = 0;
+    while i + size_of::<V>() <= a.len() {
+        let eq = V::load_unaligned(aptr.add(i))
+            .cmpeq(V::load_unaligned(bptr.add(i)));
+        count += size_of::<V>() - eq.movemask().count_ones() as usize;
+        if count > limit {
+            return count;
+        }
+        i += size_of::<V>();
+    }
+    count + hamming::count_mismatches_fallback(&a[i..], &b[i..], limit - count)
+}
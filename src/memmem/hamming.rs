@@ -0,0 +1,428 @@
+use core::convert::TryInto;
+
+use crate::{cow::CowBytes, memmem::Finder};
+
+/// A searcher for approximate occurrences of a needle, allowing up to a fixed
+/// number of substituted bytes.
+///
+/// A match is a position in the haystack at which the needle occurs with a
+/// [Hamming distance](https://en.wikipedia.org/wiki/Hamming_distance) of at
+/// most `max_distance`. That is, at most `max_distance` bytes differ between
+/// the needle and the `needle.len()` bytes of the haystack starting at that
+/// position. Insertions and deletions are not permitted.
+///
+/// Candidates are found by splitting the needle into `max_distance + 1`
+/// pieces. By the pigeonhole principle, at least one piece must occur
+/// exactly in any match, so each piece is searched for with its own
+/// [`Finder`]. Each candidate is then verified by counting mismatched bytes,
+/// using vectorized comparisons when available.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Complexity
+///
+/// The worst case time complexity of a search is `O(n * m)`, where `n` is the
+/// length of the haystack and `m` is the length of the needle. Searches are
+/// fastest when the needle is long relative to `max_distance`, since the
+/// pieces are then long enough to rarely occur by chance.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::HammingFinder;
+///
+/// let finder = HammingFinder::new("sensor", 1);
+/// let m = finder.find(b"reading from 5ensor 4").unwrap();
+/// assert_eq!(13, m.offset());
+/// assert_eq!(1, m.distance());
+/// ```
+#[derive(Clone, Debug)]
+pub struct HammingFinder<'n> {
+    needle: CowBytes<'n>,
+    max_distance: usize,
+    /// The pieces of the needle, each paired with its offset in the needle.
+    /// This is empty when `max_distance >= needle.len()`, in which case
+    /// every position is a match.
+    pieces: Vec<(usize, Finder<'n>)>,
+}
+
+/// A match reported by a [`HammingFinder`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HammingMatch {
+    offset: usize,
+    distance: usize,
+}
+
+impl HammingMatch {
+    /// Returns the position in the haystack at which this match starts. The
+    /// match always spans the length of the needle.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of bytes that differ between the needle and the
+    /// haystack at this match.
+    #[inline]
+    pub fn distance(&self) -> usize {
+        self.distance
+    }
+}
+
+impl<'n> HammingFinder<'n> {
+    /// Create a new finder for occurrences of `needle` with at most
+    /// `max_distance` substituted bytes.
+    pub fn new<B: ?Sized + AsRef<[u8]>>(
+        needle: &'n B,
+        max_distance: usize,
+    ) -> HammingFinder<'n> {
+        let bytes = needle.as_ref();
+        let n = bytes.len();
+        let mut pieces = vec![];
+        if max_distance < n {
+            let count = max_distance + 1;
+            for i in 0..count {
+                let (start, end) = (i * n / count, (i + 1) * n / count);
+                pieces.push((start, Finder::new(&bytes[start..end])));
+            }
+        }
+        HammingFinder { needle: CowBytes::new(bytes), max_distance, pieces }
+    }
+
+    /// Returns the needle that this finder searches for.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// Returns the maximum number of substituted bytes permitted in a match.
+    #[inline]
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    /// Returns the leftmost match of this needle in the given haystack.
+    ///
+    /// Note that the leftmost match isn't necessarily the closest one. To
+    /// find the closest match, iterate over all of them.
+    pub fn find(&self, haystack: &[u8]) -> Option<HammingMatch> {
+        let mut state = vec![Candidate::Unknown; self.pieces.len()];
+        self.find_at(&mut state, haystack, 0)
+    }
+
+    /// Returns an iterator over all non-overlapping matches of this needle in
+    /// the given haystack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::HammingFinder;
+    ///
+    /// let finder = HammingFinder::new("ACGT", 1);
+    /// let offsets: Vec<(usize, usize)> = finder
+    ///     .find_iter(b"ACGTTCGTAAAAACCT")
+    ///     .map(|m| (m.offset(), m.distance()))
+    ///     .collect();
+    /// assert_eq!(vec![(0, 0), (4, 1), (12, 1)], offsets);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> HammingFindIter<'h, 'a> {
+        HammingFindIter {
+            haystack,
+            finder: self.as_ref(),
+            state: vec![Candidate::Unknown; self.pieces.len()],
+            pos: 0,
+        }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    #[inline]
+    pub fn into_owned(self) -> HammingFinder<'static> {
+        HammingFinder {
+            needle: self.needle.into_owned(),
+            max_distance: self.max_distance,
+            pieces: self
+                .pieces
+                .into_iter()
+                .map(|(off, f)| (off, f.into_owned()))
+                .collect(),
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    #[inline]
+    pub fn as_ref(&self) -> HammingFinder<'_> {
+        HammingFinder {
+            needle: CowBytes::new(self.needle()),
+            max_distance: self.max_distance,
+            pieces: self
+                .pieces
+                .iter()
+                .map(|&(off, ref f)| (off, f.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Returns the leftmost match starting at or after `pos`.
+    ///
+    /// `state` caches the next candidate for each piece, so that a piece
+    /// isn't searched for again until the search moves past its last
+    /// candidate. It must only be reused with increasing values of `pos` on
+    /// the same haystack.
+    fn find_at(
+        &self,
+        state: &mut [Candidate],
+        haystack: &[u8],
+        mut pos: usize,
+    ) -> Option<HammingMatch> {
+        let needle = self.needle();
+        if haystack.len() < needle.len() {
+            return None;
+        }
+        // The last position at which a match can start.
+        let last = haystack.len() - needle.len();
+        if pos > last {
+            return None;
+        }
+        if self.pieces.is_empty() {
+            let distance = count_mismatches(
+                needle,
+                &haystack[pos..pos + needle.len()],
+                !0,
+            );
+            return Some(HammingMatch { offset: pos, distance });
+        }
+        loop {
+            if pos > last {
+                return None;
+            }
+            let mut best: Option<usize> = None;
+            for (cand, &(off, ref piece)) in
+                state.iter_mut().zip(self.pieces.iter())
+            {
+                let start = match *cand {
+                    Candidate::Never => continue,
+                    Candidate::At(start) if start >= pos => start,
+                    _ => {
+                        let window = &haystack
+                            [pos + off..last + off + piece.needle().len()];
+                        match piece.find(window) {
+                            None => {
+                                *cand = Candidate::Never;
+                                continue;
+                            }
+                            Some(i) => {
+                                *cand = Candidate::At(pos + i);
+                                pos + i
+                            }
+                        }
+                    }
+                };
+                best = Some(best.map_or(start, |b| b.min(start)));
+            }
+            let start = best?;
+            let distance = count_mismatches(
+                needle,
+                &haystack[start..start + needle.len()],
+                self.max_distance,
+            );
+            if distance <= self.max_distance {
+                return Some(HammingMatch { offset: start, distance });
+            }
+            pos = start + 1;
+        }
+    }
+}
+
+/// The next candidate match position found by one piece of the needle.
+#[derive(Clone, Copy, Debug)]
+enum Candidate {
+    /// The piece hasn't been searched for yet.
+    Unknown,
+    /// The piece occurs such that a match could start at this position.
+    At(usize),
+    /// The piece doesn't occur anywhere a match could start.
+    Never,
+}
+
+/// An iterator over non-overlapping matches of a [`HammingFinder`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct HammingFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: HammingFinder<'n>,
+    state: Vec<Candidate>,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for HammingFindIter<'h, 'n> {
+    type Item = HammingMatch;
+
+    fn next(&mut self) -> Option<HammingMatch> {
+        let m =
+            self.finder.find_at(&mut self.state, self.haystack, self.pos)?;
+        self.pos = m.offset + core::cmp::max(1, self.finder.needle().len());
+        Some(m)
+    }
+}
+
+/// Count the number of positions at which `a` and `b` differ, stopping early
+/// once the count exceeds `limit`. When stopping early, some value greater
+/// than `limit` is returned. `a` and `b` must have the same length.
+fn count_mismatches(a: &[u8], b: &[u8], limit: usize) -> usize {
+    #[cfg(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
+    {
+        crate::memmem::x86::hamming::count_mismatches(a, b, limit)
+    }
+    #[cfg(not(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    )))]
+    {
+        count_mismatches_fallback(a, b, limit)
+    }
+}
+
+/// Count mismatched bytes one word at a time. See `count_mismatches`.
+pub(crate) fn count_mismatches_fallback(
+    a: &[u8],
+    b: &[u8],
+    limit: usize,
+) -> usize {
+    const LO: u64 = 0x0101010101010101;
+
+    debug_assert_eq!(a.len(), b.len());
+    let mut count = 0;
+    let (mut achunks, mut bchunks) = (a.chunks_exact(8), b.chunks_exact(8));
+    for (x, y) in achunks.by_ref().zip(bchunks.by_ref()) {
+        let x = u64::from_ne_bytes(x.try_into().unwrap());
+        let y = u64::from_ne_bytes(y.try_into().unwrap());
+        // Fold every bit of each byte of the difference into its least
+        // significant bit, so that each differing byte contributes one bit.
+        let mut d = x ^ y;
+        d |= d >> 4;
+        d |= d >> 2;
+        d |= d >> 1;
+        count += (d & LO).count_ones() as usize;
+        if count > limit {
+            return count;
+        }
+    }
+    count
+        + achunks
+            .remainder()
+            .iter()
+            .zip(bchunks.remainder())
+            .filter(|&(x, y)| x != y)
+            .count()
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{count_mismatches, count_mismatches_fallback, HammingFinder};
+
+    fn distance(a: &[u8], b: &[u8]) -> usize {
+        a.iter().zip(b).filter(|&(x, y)| x != y).count()
+    }
+
+    fn naive(needle: &[u8], k: usize, haystack: &[u8]) -> Vec<(usize, usize)> {
+        let mut matches = vec![];
+        let mut pos = 0;
+        while pos + needle.len() <= haystack.len() {
+            let d = distance(needle, &haystack[pos..pos + needle.len()]);
+            if d <= k {
+                matches.push((pos, d));
+                pos += core::cmp::max(1, needle.len());
+            } else {
+                pos += 1;
+            }
+        }
+        matches
+    }
+
+    fn find_all(
+        needle: &[u8],
+        k: usize,
+        haystack: &[u8],
+    ) -> Vec<(usize, usize)> {
+        HammingFinder::new(needle, k)
+            .find_iter(haystack)
+            .map(|m| (m.offset(), m.distance()))
+            .collect()
+    }
+
+    #[test]
+    fn simple() {
+        assert_eq!(vec![(0, 0)], find_all(b"abc", 0, b"abc"));
+        assert_eq!(vec![(1, 1)], find_all(b"abc", 1, b"xabd"));
+        assert_eq!(Vec::<(usize, usize)>::new(), find_all(b"abc", 1, b"xbd"));
+        assert_eq!(vec![(0, 2), (2, 1)], find_all(b"ab", 2, b"xyaz"));
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0)], find_all(b"", 0, b"ab"));
+        assert_eq!(Vec::<(usize, usize)>::new(), find_all(b"abc", 5, b"ab"));
+        assert_eq!(None, HammingFinder::new("abc", 0).find(b""));
+    }
+
+    #[test]
+    fn counts() {
+        let a: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let mut b = a.clone();
+        for i in (0..200).step_by(7) {
+            b[i] ^= 0x80;
+        }
+        for len in 0..200 {
+            let (a, b) = (&a[..len], &b[..len]);
+            let expected = distance(a, b);
+            assert_eq!(expected, count_mismatches(a, b, !0));
+            assert_eq!(expected, count_mismatches_fallback(a, b, !0));
+            if expected > 0 {
+                assert!(count_mismatches(a, b, expected - 1) >= expected);
+            }
+        }
+    }
+
+    #[test]
+    fn owned() {
+        let finder = {
+            let needle = String::from("needle");
+            HammingFinder::new(&needle, 2).into_owned()
+        };
+        assert_eq!(Some(3), finder.find(b"a, noodle").map(|m| m.offset()));
+    }
+
+    quickcheck! {
+        fn qc_matches_naive(
+            needle: Vec<u8>,
+            k: u8,
+            haystack: Vec<u8>
+        ) -> bool {
+            // Use a small alphabet so that approximate matches are likely.
+            let needle: Vec<u8> =
+                needle.iter().take(12).map(|&b| b'a' + b % 3).collect();
+            let haystack: Vec<u8> =
+                haystack.iter().map(|&b| b'a' + b % 3).collect();
+            let k = (k % 4) as usize;
+            let first = naive(&needle, k, &haystack)
+                .first()
+                .cloned();
+            let got = HammingFinder::new(&needle, k)
+                .find(&haystack)
+                .map(|m| (m.offset(), m.distance()));
+            first == got
+                && naive(&needle, k, &haystack)
+                    == find_all(&needle, k, &haystack)
+        }
+    }
+}
@@ -0,0 +1,648 @@
+use crate::{cow::CowBytes, memmem::Finder};
+
+/// A searcher for approximate occurrences of a needle, allowing up to a fixed
+/// number of inserted, deleted or substituted bytes.
+///
+/// A match is a span of the haystack whose
+/// [Levenshtein distance](https://en.wikipedia.org/wiki/Levenshtein_distance)
+/// to the needle is at most `max_distance`. Since neighboring spans usually
+/// match too, only the best match from each group of overlapping candidates is
+/// reported: scanning left to right, once some span ending at a position is
+/// within `max_distance` of the needle, the search keeps extending the end
+/// for as long as that remains true, and reports the end with the smallest
+/// distance. The start of the match is then the one closest to that end that
+/// achieves the same distance. The search resumes at the end of the match.
+///
+/// Distances are computed with the bit-parallel dynamic programming algorithm
+/// of Myers, using the block-based formulation by Hyyrö to support needles of
+/// any length. To avoid running it over the entire haystack, the needle is
+/// split into `max_distance + 1` pieces. By the pigeonhole principle, at
+/// least one piece must occur exactly in any match, so each piece is searched
+/// for with its own [`Finder`], and only the regions around their occurrences
+/// are examined.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Complexity
+///
+/// The worst case time complexity of a search is `O(n * ceil(m / 64))`,
+/// where `n` is the length of the haystack and `m` is the length of the
+/// needle.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::LevenshteinFinder;
+///
+/// let finder = LevenshteinFinder::new("connection reset", 2);
+/// let m = finder.find(b"error: connectionreset by peer").unwrap();
+/// assert_eq!(7..22, m.start()..m.end());
+/// assert_eq!(1, m.distance());
+/// ```
+#[derive(Clone, Debug)]
+pub struct LevenshteinFinder<'n> {
+    needle: CowBytes<'n>,
+    max_distance: usize,
+    /// The pieces of the needle, each paired with its offset in the needle.
+    /// This is empty when `max_distance >= needle.len()`, in which case the
+    /// entire haystack must be examined.
+    pieces: Vec<(usize, Finder<'n>)>,
+    /// For each byte, a bitset of the positions in the needle at which it
+    /// occurs, split into blocks of 64 positions.
+    peq: Vec<u64>,
+    /// The same as `peq`, but for the reversed needle. This is used to find
+    /// the start of a match.
+    peq_rev: Vec<u64>,
+}
+
+/// A match reported by a [`LevenshteinFinder`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LevenshteinMatch {
+    start: usize,
+    end: usize,
+    distance: usize,
+}
+
+impl LevenshteinMatch {
+    /// Returns the position in the haystack at which this match starts.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the position in the haystack at which this match ends,
+    /// exclusive.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the edit distance between the needle and this match.
+    #[inline]
+    pub fn distance(&self) -> usize {
+        self.distance
+    }
+}
+
+impl<'n> LevenshteinFinder<'n> {
+    /// Create a new finder for occurrences of `needle` with at most
+    /// `max_distance` edits.
+    pub fn new<B: ?Sized + AsRef<[u8]>>(
+        needle: &'n B,
+        max_distance: usize,
+    ) -> LevenshteinFinder<'n> {
+        let bytes = needle.as_ref();
+        let n = bytes.len();
+        let mut pieces = vec![];
+        if max_distance < n {
+            let count = max_distance + 1;
+            for i in 0..count {
+                let (start, end) = (i * n / count, (i + 1) * n / count);
+                pieces.push((start, Finder::new(&bytes[start..end])));
+            }
+        }
+        let blocks = block_count(n);
+        let mut peq = vec![0; 256 * blocks];
+        let mut peq_rev = vec![0; 256 * blocks];
+        for (i, &b) in bytes.iter().enumerate() {
+            peq[b as usize * blocks + i / 64] |= 1 << (i % 64);
+            let j = n - 1 - i;
+            peq_rev[b as usize * blocks + j / 64] |= 1 << (j % 64);
+        }
+        LevenshteinFinder {
+            needle: CowBytes::new(bytes),
+            max_distance,
+            pieces,
+            peq,
+            peq_rev,
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// Returns the maximum number of edits permitted in a match.
+    #[inline]
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    /// Returns the first match of this needle in the given haystack.
+    pub fn find(&self, haystack: &[u8]) -> Option<LevenshteinMatch> {
+        self.find_at(&mut Search::new(self), haystack)
+    }
+
+    /// Returns an iterator over all non-overlapping matches of this needle in
+    /// the given haystack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::LevenshteinFinder;
+    ///
+    /// let finder = LevenshteinFinder::new("timeout", 1);
+    /// let matches: Vec<(usize, usize, usize)> = finder
+    ///     .find_iter(b"timout, time out, timeout")
+    ///     .map(|m| (m.start(), m.end(), m.distance()))
+    ///     .collect();
+    /// assert_eq!(vec![(0, 6, 1), (8, 16, 1), (18, 25, 0)], matches);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> LevenshteinFindIter<'h, 'a> {
+        LevenshteinFindIter {
+            haystack,
+            finder: self.as_ref(),
+            search: Search::new(self),
+            done: false,
+        }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    #[inline]
+    pub fn into_owned(self) -> LevenshteinFinder<'static> {
+        LevenshteinFinder {
+            needle: self.needle.into_owned(),
+            max_distance: self.max_distance,
+            pieces: self
+                .pieces
+                .into_iter()
+                .map(|(off, f)| (off, f.into_owned()))
+                .collect(),
+            peq: self.peq,
+            peq_rev: self.peq_rev,
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    #[inline]
+    pub fn as_ref(&self) -> LevenshteinFinder<'_> {
+        LevenshteinFinder {
+            needle: CowBytes::new(self.needle()),
+            max_distance: self.max_distance,
+            pieces: self
+                .pieces
+                .iter()
+                .map(|&(off, ref f)| (off, f.as_ref()))
+                .collect(),
+            peq: self.peq.clone(),
+            peq_rev: self.peq_rev.clone(),
+        }
+    }
+
+    /// Returns the next match at or after `search.pos`.
+    fn find_at(
+        &self,
+        search: &mut Search,
+        haystack: &[u8],
+    ) -> Option<LevenshteinMatch> {
+        if self.needle().is_empty() {
+            if search.pos > haystack.len() {
+                return None;
+            }
+            let pos = search.pos;
+            search.pos += 1;
+            return Some(LevenshteinMatch {
+                start: pos,
+                end: pos,
+                distance: 0,
+            });
+        }
+        loop {
+            let (start, end) = match search.component {
+                Some((start, end)) if end > search.pos => (start, end),
+                _ => {
+                    let c = self.next_component(search, haystack)?;
+                    search.component = Some(c);
+                    c
+                }
+            };
+            let start = core::cmp::max(start, search.pos);
+            if let Some(m) = self.scan(search, haystack, start, end) {
+                search.pos = m.end;
+                return Some(m);
+            }
+            search.pos = end;
+        }
+    }
+
+    /// Find the next region of the haystack in which a match might occur.
+    ///
+    /// Every occurrence of a piece defines a window that contains every
+    /// match containing that occurrence. Overlapping windows are merged, so
+    /// that every match falls entirely within exactly one region.
+    fn next_component(
+        &self,
+        search: &mut Search,
+        haystack: &[u8],
+    ) -> Option<(usize, usize)> {
+        let (m, k) = (self.needle().len(), self.max_distance);
+        if self.pieces.is_empty() {
+            if search.component.is_some() || search.pos >= haystack.len() {
+                return None;
+            }
+            return Some((search.pos, haystack.len()));
+        }
+        // A match starting at or after pos can only contain an occurrence of
+        // a piece at or after pos + off - k.
+        for (piece, &(off, _)) in search.pieces.iter_mut().zip(&self.pieces) {
+            let min = (search.pos + off).saturating_sub(k);
+            piece.cursor = core::cmp::max(piece.cursor, min);
+        }
+        let mut component: Option<(usize, usize)> = None;
+        loop {
+            let mut next: Option<(usize, usize, usize)> = None;
+            for (i, &(off, ref finder)) in self.pieces.iter().enumerate() {
+                let q = match search.pieces[i].next(finder, haystack) {
+                    None => continue,
+                    Some(q) => q,
+                };
+                let start = q.saturating_sub(off + k);
+                let end = core::cmp::min(haystack.len(), q + (m - off) + k);
+                match next {
+                    Some((s, _, _)) if s <= start => {}
+                    _ => next = Some((start, end, i)),
+                }
+            }
+            let (start, end, i) = match next {
+                None => break,
+                Some(next) => next,
+            };
+            component = match component {
+                None => Some((start, end)),
+                Some((cs, ce)) if start <= ce => {
+                    Some((cs, core::cmp::max(ce, end)))
+                }
+                Some(_) => break,
+            };
+            search.pieces[i].cursor += 1;
+        }
+        component
+    }
+
+    /// Scan `haystack[start..end]` for the first group of spans within the
+    /// maximum distance of the needle, and return the best of them.
+    fn scan(
+        &self,
+        search: &mut Search,
+        haystack: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Option<LevenshteinMatch> {
+        let k = self.max_distance;
+        let mut best: Option<(usize, usize)> = None;
+        search.myers.reset();
+        for (i, &b) in haystack[start..end].iter().enumerate() {
+            let score = search.myers.step(&self.peq, b, 0);
+            if score <= k {
+                match best {
+                    Some((_, d)) if d <= score => {}
+                    _ => best = Some((start + i + 1, score)),
+                }
+            } else if best.is_some() {
+                break;
+            }
+        }
+        let (mend, distance) = best?;
+        // Find the shortest span ending at mend with the same distance by
+        // running the reversed needle backwards from the end, where every
+        // byte of the haystack must be accounted for.
+        search.myers.reset();
+        let mut len = 0;
+        let mut score = search.myers.score;
+        let max_len = core::cmp::min(mend - start, self.needle().len() + k);
+        while score != distance && len < max_len {
+            len += 1;
+            score = search.myers.step(&self.peq_rev, haystack[mend - len], 1);
+        }
+        debug_assert_eq!(score, distance);
+        Some(LevenshteinMatch { start: mend - len, end: mend, distance })
+    }
+}
+
+/// The mutable state of a search.
+#[derive(Clone, Debug)]
+struct Search {
+    /// The position at which the next match may start.
+    pos: usize,
+    /// The region currently being examined.
+    component: Option<(usize, usize)>,
+    /// The state of the search for each piece of the needle.
+    pieces: Vec<Piece>,
+    myers: Myers,
+}
+
+impl Search {
+    fn new(finder: &LevenshteinFinder<'_>) -> Search {
+        let piece = Piece { cursor: 0, next: None, searched: false };
+        Search {
+            pos: 0,
+            component: None,
+            pieces: vec![piece; finder.pieces.len()],
+            myers: Myers::new(finder.needle().len()),
+        }
+    }
+}
+
+/// The search state for a piece of the needle.
+#[derive(Clone, Copy, Debug)]
+struct Piece {
+    /// The position at which to look for the next occurrence.
+    cursor: usize,
+    /// The result of the last search. If it found an occurrence at or after
+    /// the cursor, then it's still the next occurrence.
+    next: Option<usize>,
+    /// Whether a search has been run at all.
+    searched: bool,
+}
+
+impl Piece {
+    /// Returns the next occurrence of this piece at or after its cursor.
+    fn next(&mut self, finder: &Finder<'_>, haystack: &[u8]) -> Option<usize> {
+        if self.searched {
+            match self.next {
+                None => return None,
+                Some(q) if q >= self.cursor => return Some(q),
+                Some(_) => {}
+            }
+        }
+        self.searched = true;
+        self.next = if self.cursor > haystack.len() {
+            None
+        } else {
+            finder.find(&haystack[self.cursor..]).map(|i| self.cursor + i)
+        };
+        self.next
+    }
+}
+
+/// The state of the bit-parallel dynamic programming matrix for a needle,
+/// stored as vertical positive and negative deltas in blocks of 64 rows.
+///
+/// Each step consumes one byte of the haystack and returns the distance in
+/// the last row of the new column.
+#[derive(Clone, Debug)]
+struct Myers {
+    pv: Vec<u64>,
+    mv: Vec<u64>,
+    score: usize,
+    /// The bit corresponding to the last row of the needle in the last block.
+    last_bit: u64,
+    len: usize,
+}
+
+impl Myers {
+    fn new(len: usize) -> Myers {
+        let blocks = block_count(len);
+        let last_bit = if len == 0 { 0 } else { 1 << ((len - 1) % 64) };
+        let mut myers = Myers {
+            pv: vec![0; blocks],
+            mv: vec![0; blocks],
+            score: len,
+            last_bit,
+            len,
+        };
+        myers.reset();
+        myers
+    }
+
+    /// Reset to the first column, where the distance in each row is the row
+    /// number.
+    fn reset(&mut self) {
+        for pv in self.pv.iter_mut() {
+            *pv = !0;
+        }
+        for mv in self.mv.iter_mut() {
+            *mv = 0;
+        }
+        self.score = self.len;
+    }
+
+    /// Consume one byte of the haystack.
+    ///
+    /// `hin` is the horizontal delta in the first row. It is `0` when a match
+    /// may start anywhere, and `1` when a match must start at the first byte.
+    #[inline(always)]
+    fn step(&mut self, peq: &[u64], byte: u8, hin: i32) -> usize {
+        let blocks = self.pv.len();
+        let eqs = &peq[byte as usize * blocks..][..blocks];
+        let mut h = hin;
+        for (b, &eq) in eqs.iter().enumerate() {
+            let high = if b + 1 == blocks { self.last_bit } else { 1 << 63 };
+            let (pv, mv) = (self.pv[b], self.mv[b]);
+            let mut eq = eq;
+            let xv = eq | mv;
+            if h < 0 {
+                eq |= 1;
+            }
+            let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+            let mut ph = mv | !(xh | pv);
+            let mut mh = pv & xh;
+            let hout = if ph & high != 0 {
+                1
+            } else if mh & high != 0 {
+                -1
+            } else {
+                0
+            };
+            ph <<= 1;
+            mh <<= 1;
+            if h < 0 {
+                mh |= 1;
+            } else if h > 0 {
+                ph |= 1;
+            }
+            self.pv[b] = mh | !(xv | ph);
+            self.mv[b] = ph & xv;
+            h = hout;
+        }
+        self.score = (self.score as isize + h as isize) as usize;
+        self.score
+    }
+}
+
+/// An iterator over non-overlapping matches of a [`LevenshteinFinder`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct LevenshteinFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: LevenshteinFinder<'n>,
+    search: Search,
+    done: bool,
+}
+
+impl<'h, 'n> Iterator for LevenshteinFindIter<'h, 'n> {
+    type Item = LevenshteinMatch;
+
+    fn next(&mut self) -> Option<LevenshteinMatch> {
+        if self.done {
+            return None;
+        }
+        let m = self.finder.find_at(&mut self.search, self.haystack);
+        if m.is_none() {
+            self.done = true;
+        }
+        m
+    }
+}
+
+/// Returns the number of 64-bit blocks needed for a needle of the given
+/// length. This is always at least 1.
+fn block_count(len: usize) -> usize {
+    core::cmp::max(1, len.saturating_add(63) / 64)
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::LevenshteinFinder;
+
+    /// Returns, for each end position in `haystack[pos..]`, the smallest
+    /// distance between the needle and a span ending there and starting at or
+    /// after pos.
+    fn naive_costs(needle: &[u8], haystack: &[u8]) -> Vec<usize> {
+        let mut col: Vec<usize> = (0..=needle.len()).collect();
+        let mut costs = vec![];
+        for &b in haystack {
+            let mut next = vec![0; needle.len() + 1];
+            for i in 1..=needle.len() {
+                let sub = col[i - 1] + (needle[i - 1] != b) as usize;
+                next[i] = sub.min(col[i] + 1).min(next[i - 1] + 1);
+            }
+            costs.push(next[needle.len()]);
+            col = next;
+        }
+        costs
+    }
+
+    fn anchored(a: &[u8], b: &[u8]) -> usize {
+        let mut col: Vec<usize> = (0..=a.len()).collect();
+        for (j, &c) in b.iter().enumerate() {
+            let mut next = vec![j + 1; a.len() + 1];
+            for i in 1..=a.len() {
+                let sub = col[i - 1] + (a[i - 1] != c) as usize;
+                next[i] = sub.min(col[i] + 1).min(next[i - 1] + 1);
+            }
+            col = next;
+        }
+        col[a.len()]
+    }
+
+    fn naive(
+        needle: &[u8],
+        k: usize,
+        haystack: &[u8],
+    ) -> Vec<(usize, usize, usize)> {
+        let mut matches = vec![];
+        let mut pos = 0;
+        while pos <= haystack.len() {
+            if needle.is_empty() {
+                matches.push((pos, pos, 0));
+                pos += 1;
+                continue;
+            }
+            let costs = naive_costs(needle, &haystack[pos..]);
+            let mut best: Option<(usize, usize)> = None;
+            for (i, &c) in costs.iter().enumerate() {
+                if c <= k {
+                    match best {
+                        Some((_, d)) if d <= c => {}
+                        _ => best = Some((pos + i + 1, c)),
+                    }
+                } else if best.is_some() {
+                    break;
+                }
+            }
+            let (end, d) = match best {
+                None => break,
+                Some(best) => best,
+            };
+            let start = (pos..=end)
+                .rev()
+                .find(|&s| anchored(needle, &haystack[s..end]) == d)
+                .unwrap();
+            matches.push((start, end, d));
+            pos = end;
+        }
+        matches
+    }
+
+    fn find_all(
+        needle: &[u8],
+        k: usize,
+        haystack: &[u8],
+    ) -> Vec<(usize, usize, usize)> {
+        LevenshteinFinder::new(needle, k)
+            .find_iter(haystack)
+            .map(|m| (m.start(), m.end(), m.distance()))
+            .collect()
+    }
+
+    #[test]
+    fn simple() {
+        assert_eq!(vec![(0, 3, 0)], find_all(b"abc", 0, b"abc"));
+        assert_eq!(vec![(1, 3, 1)], find_all(b"abc", 1, b"xacx"));
+        assert_eq!(vec![(1, 3, 1)], find_all(b"abc", 1, b"xabxcx"));
+        assert_eq!(vec![(0, 3, 0), (3, 6, 0)], find_all(b"abc", 1, b"abcabc"));
+        assert_eq!(vec![(0, 0, 0), (1, 1, 0)], find_all(b"", 1, b"a"));
+        assert_eq!(vec![(0, 1, 1)], find_all(b"ab", 2, b"a"));
+        assert_eq!(None, LevenshteinFinder::new("abc", 1).find(b"xyz"));
+        assert_eq!(3, anchored(b"abc", b"xyz"));
+    }
+
+    #[test]
+    fn long_needle() {
+        // Needles longer than 64 bytes use more than one block.
+        let needle: Vec<u8> =
+            (0..200u32).map(|i| b'a' + (i * 7 % 26) as u8).collect();
+        let mut haystack = vec![b'.'; 300];
+        haystack.extend_from_slice(&needle[..100]);
+        haystack.extend_from_slice(&needle[101..150]);
+        haystack.push(b'!');
+        haystack.extend_from_slice(&needle[150..]);
+        haystack.extend_from_slice(&[b'.'; 300]);
+        let expected = naive(&needle, 3, &haystack);
+        assert_eq!(1, expected.len());
+        assert_eq!(2, expected[0].2);
+        assert_eq!(expected, find_all(&needle, 3, &haystack));
+    }
+
+    #[test]
+    fn owned() {
+        let finder = {
+            let needle = String::from("needle");
+            LevenshteinFinder::new(&needle, 1).into_owned()
+        };
+        assert_eq!(Some(3), finder.find(b"a, nedle").map(|m| m.start()));
+    }
+
+    quickcheck! {
+        fn qc_matches_naive(
+            needle: Vec<u8>,
+            k: u8,
+            haystack: Vec<u8>
+        ) -> bool {
+            // Use a small alphabet so that approximate matches are likely.
+            let needle: Vec<u8> =
+                needle.iter().take(10).map(|&b| b'a' + b % 3).collect();
+            let haystack: Vec<u8> =
+                haystack.iter().map(|&b| b'a' + b % 3).collect();
+            let k = (k % 4) as usize;
+            let expected = naive(&needle, k, &haystack);
+            let got = LevenshteinFinder::new(&needle, k)
+                .find(&haystack)
+                .map(|m| (m.start(), m.end(), m.distance()));
+            expected.first().cloned() == got
+                && expected == find_all(&needle, k, &haystack)
+        }
+    }
+}
@@ -11,6 +11,23 @@ Other than being able to work on arbitrary bytes, the primary reason to prefer
 these routines over the standard library routines is that these will generally
 be faster. In some cases, significantly so.
 
+This module also provides a [`GappedFinder`] for searching for a sequence of
+substrings separated by bounded gaps, such as `BEGIN` followed by `END` within
+4096 bytes, and a [`ClassFinder`] for searching for needles where each
+position may match a set of bytes, such as binary signatures with wildcards.
+A [`HammingFinder`] finds approximate occurrences of a needle with up to a
+fixed number of substituted bytes, while a [`LevenshteinFinder`] also
+permits inserted and deleted bytes. These are only available when the `std`
+feature is enabled.
+
+A [`Finder`] can also search columnar string arrays, stored as a values buffer
+and an offsets array, with [`Finder::rows_containing`]. This scans the values
+buffer once instead of starting a new search for every row.
+
+Files can be searched with [`Finder::find_iter_path`] and
+[`Finder::find_iter_file`], which memory map regular files when possible and
+otherwise read them incrementally.
+
 # Example: iterating over substring matches
 
 This example shows how to use [`find_iter`] to find occurrences of a substring
@@ -66,9 +83,22 @@ assert_eq!(None, finder.find(b"quux baz bar"));
 ```
 */
 
+#[cfg(feature = "std")]
+pub use self::class::{ClassFindIter, ClassFinder, ParseSignatureError};
+#[cfg(feature = "std")]
+pub use self::file::FileFindIter;
+#[cfg(feature = "std")]
+pub use self::gapped::{GappedFindIter, GappedFinder};
+#[cfg(feature = "std")]
+pub use self::hamming::{HammingFindIter, HammingFinder, HammingMatch};
+#[cfg(feature = "std")]
+pub use self::levenshtein::{
+    LevenshteinFindIter, LevenshteinFinder, LevenshteinMatch,
+};
 pub use self::prefilter::Prefilter;
 
 use crate::{
+    batch::{self, Offset},
     cow::CowBytes,
     memmem::{
         prefilter::{Pre, PrefilterFn, PrefilterState},
@@ -145,18 +175,66 @@ macro_rules! define_memmem_simple_tests {
     };
 }
 
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+mod aarch64;
 mod byte_frequencies;
-#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+#[cfg(feature = "std")]
+mod class;
+#[cfg(feature = "std")]
+mod columnar;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "std")]
+mod gapped;
+#[cfg(any(
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ),
+    all(target_arch = "aarch64", memchr_runtime_neon),
+    all(target_arch = "wasm32", memchr_runtime_simd128),
+    feature = "portable-simd",
+))]
+// The class and Hamming distance routines are only vectorized on x86.
+#[cfg_attr(
+    any(
+        target_arch = "aarch64",
+        target_arch = "wasm32",
+        memchr_portable_simd,
+    ),
+    allow(dead_code)
+)]
 mod genericsimd;
+#[cfg(feature = "std")]
+mod hamming;
+#[cfg(feature = "std")]
+mod levenshtein;
+// The portable searcher is compiled whenever the feature is enabled, so that
+// it can be tested on all targets, but only used when build.rs selects it.
+#[cfg(all(not(miri), feature = "portable-simd"))]
+#[cfg_attr(not(memchr_portable_simd), allow(dead_code))]
+mod portable;
 mod prefilter;
 mod rabinkarp;
 mod rarebytes;
 mod twoway;
 mod util;
-// SIMD is only supported on x86_64 currently.
-#[cfg(target_arch = "x86_64")]
-mod vector;
-#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+// SIMD is only supported on x86, x86_64, aarch64 and wasm32 currently, and on
+// other targets with the portable-simd feature.
+#[cfg(any(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    all(target_arch = "aarch64", memchr_runtime_neon),
+    all(target_arch = "wasm32", memchr_runtime_simd128),
+    all(not(miri), feature = "portable-simd"),
+))]
+pub(crate) mod vector;
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+mod wasm32;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
 mod x86;
 
 /// Returns an iterator over all occurrences of a substring in a haystack.
@@ -482,6 +560,113 @@ impl<'n> Finder<'n> {
         FindIter::new(haystack, self.as_ref())
     }
 
+    /// Write the offsets of non-overlapping occurrences of this needle in the
+    /// given haystack, starting the search at `start`, to `out`.
+    ///
+    /// This reports the same matches as [`Finder::find_iter`] does on
+    /// `&haystack[start..]`, except offsets are relative to the start of
+    /// `haystack`. It returns the number of offsets written, followed by
+    /// `None` if every match was written, or `Some(i)` if `out` filled up,
+    /// where `i` is the offset of the next match. Passing `i` back as `start`
+    /// continues the search.
+    ///
+    /// See the [`batch`](crate::batch) module for more details.
+    ///
+    /// # Panics
+    ///
+    /// When `start > haystack.len()`, or when a match offset does not fit in
+    /// `T`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"foo bar foo baz foo";
+    /// let finder = Finder::new(b"foo");
+    /// let mut buf = [0u32; 2];
+    /// assert_eq!((2, Some(16)), finder.find_fill(haystack, 0, &mut buf));
+    /// assert_eq!([0, 8], buf);
+    /// assert_eq!((1, None), finder.find_fill(haystack, 16, &mut buf));
+    /// assert_eq!(16, buf[0]);
+    /// ```
+    pub fn find_fill<T: Offset>(
+        &self,
+        haystack: &[u8],
+        start: usize,
+        out: &mut [T],
+    ) -> (usize, Option<usize>) {
+        assert!(
+            start <= haystack.len(),
+            "start must be in bounds of haystack"
+        );
+        let mut prestate = self.searcher.prefilter_state();
+        let step = core::cmp::max(1, self.needle().len());
+        let mut written = 0;
+        let mut pos = start;
+        while pos <= haystack.len() {
+            let i = match self.searcher.find(&mut prestate, &haystack[pos..]) {
+                None => break,
+                Some(i) => pos + i,
+            };
+            if written == out.len() {
+                return (written, Some(i));
+            }
+            out[written] = batch::offset(i);
+            written += 1;
+            pos = i + step;
+        }
+        (written, None)
+    }
+
+    /// Append the offsets of all non-overlapping occurrences of this needle
+    /// in the given haystack to `out`.
+    ///
+    /// This reports the same matches as [`Finder::find_iter`], and returns
+    /// the number of offsets appended.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Panics
+    ///
+    /// When a match offset does not fit in `T`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"foo bar foo baz foo";
+    /// let mut offsets: Vec<u32> = vec![];
+    /// Finder::new(b"foo").find_all_into(haystack, &mut offsets);
+    /// assert_eq!(offsets, vec![0, 8, 16]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn find_all_into<T: Offset>(
+        &self,
+        haystack: &[u8],
+        out: &mut Vec<T>,
+    ) -> usize {
+        let old_len = out.len();
+        let mut prestate = self.searcher.prefilter_state();
+        let step = core::cmp::max(1, self.needle().len());
+        let mut pos = 0;
+        while pos <= haystack.len() {
+            match self.searcher.find(&mut prestate, &haystack[pos..]) {
+                None => break,
+                Some(i) => {
+                    out.push(batch::offset(pos + i));
+                    pos += i + step;
+                }
+            }
+        }
+        out.len() - old_len
+    }
+
     /// Convert this finder into its owned variant, such that it no longer
     /// borrows the needle.
     ///
@@ -771,14 +956,32 @@ enum SearcherKind {
     /// linear time guarantee. In general, it's used when the needle is bigger
     /// than 8 bytes or so.
     TwoWay(twoway::Forward),
-    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    #[cfg(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
     GenericSIMD128(x86::sse::Forward),
-    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    #[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+    GenericSIMD128(aarch64::neon::Forward),
+    #[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+    GenericSIMD128(wasm32::simd128::Forward),
+    #[cfg(all(not(miri), memchr_portable_simd))]
+    GenericSIMD128(portable::Forward),
+    #[cfg(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
     GenericSIMD256(x86::avx::Forward),
 }
 
 impl<'n> Searcher<'n> {
-    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    #[cfg(all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
     fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
         use self::SearcherKind::*;
 
@@ -799,7 +1002,74 @@ impl<'n> Searcher<'n> {
         Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
     }
 
-    #[cfg(not(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)))]
+    #[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
+        use self::SearcherKind::*;
+
+        let ninfo = NeedleInfo::new(needle);
+        let prefn =
+            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if let Some(fwd) = aarch64::neon::Forward::new(&ninfo, needle) {
+            GenericSIMD128(fwd)
+        } else {
+            TwoWay(twoway::Forward::new(needle))
+        };
+        Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
+    }
+
+    #[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
+        use self::SearcherKind::*;
+
+        let ninfo = NeedleInfo::new(needle);
+        let prefn =
+            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if let Some(fwd) = wasm32::simd128::Forward::new(&ninfo, needle)
+        {
+            GenericSIMD128(fwd)
+        } else {
+            TwoWay(twoway::Forward::new(needle))
+        };
+        Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
+    }
+
+    #[cfg(all(not(miri), memchr_portable_simd))]
+    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
+        use self::SearcherKind::*;
+
+        let ninfo = NeedleInfo::new(needle);
+        let prefn =
+            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if let Some(fwd) = portable::Forward::new(&ninfo, needle) {
+            GenericSIMD128(fwd)
+        } else {
+            TwoWay(twoway::Forward::new(needle))
+        };
+        Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
+    }
+
+    #[cfg(not(any(
+        all(
+            not(miri),
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        ),
+        all(not(miri), target_arch = "aarch64", memchr_runtime_neon),
+        all(not(miri), target_arch = "wasm32", memchr_runtime_simd128),
+        all(not(miri), memchr_portable_simd),
+    )))]
     fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
         use self::SearcherKind::*;
 
@@ -844,15 +1114,24 @@ impl<'n> Searcher<'n> {
             Empty => Empty,
             OneByte(b) => OneByte(b),
             TwoWay(tw) => TwoWay(tw),
-            #[cfg(all(
-                not(miri),
-                target_arch = "x86_64",
-                memchr_runtime_simd
+            #[cfg(any(
+                all(
+                    not(miri),
+                    any(target_arch = "x86", target_arch = "x86_64"),
+                    memchr_runtime_simd
+                ),
+                all(not(miri), target_arch = "aarch64", memchr_runtime_neon),
+                all(
+                    not(miri),
+                    target_arch = "wasm32",
+                    memchr_runtime_simd128
+                ),
+                all(not(miri), memchr_portable_simd),
             ))]
             GenericSIMD128(gs) => GenericSIMD128(gs),
             #[cfg(all(
                 not(miri),
-                target_arch = "x86_64",
+                any(target_arch = "x86", target_arch = "x86_64"),
                 memchr_runtime_simd
             ))]
             GenericSIMD256(gs) => GenericSIMD256(gs),
@@ -873,15 +1152,24 @@ impl<'n> Searcher<'n> {
             Empty => Empty,
             OneByte(b) => OneByte(b),
             TwoWay(tw) => TwoWay(tw),
-            #[cfg(all(
-                not(miri),
-                target_arch = "x86_64",
-                memchr_runtime_simd
+            #[cfg(any(
+                all(
+                    not(miri),
+                    any(target_arch = "x86", target_arch = "x86_64"),
+                    memchr_runtime_simd
+                ),
+                all(not(miri), target_arch = "aarch64", memchr_runtime_neon),
+                all(
+                    not(miri),
+                    target_arch = "wasm32",
+                    memchr_runtime_simd128
+                ),
+                all(not(miri), memchr_portable_simd),
             ))]
             GenericSIMD128(gs) => GenericSIMD128(gs),
             #[cfg(all(
                 not(miri),
-                target_arch = "x86_64",
+                any(target_arch = "x86", target_arch = "x86_64"),
                 memchr_runtime_simd
             ))]
             GenericSIMD256(gs) => GenericSIMD256(gs),
@@ -921,10 +1209,19 @@ impl<'n> Searcher<'n> {
                     self.find_tw(tw, state, haystack, needle)
                 }
             }
-            #[cfg(all(
-                not(miri),
-                target_arch = "x86_64",
-                memchr_runtime_simd
+            #[cfg(any(
+                all(
+                    not(miri),
+                    any(target_arch = "x86", target_arch = "x86_64"),
+                    memchr_runtime_simd
+                ),
+                all(not(miri), target_arch = "aarch64", memchr_runtime_neon),
+                all(
+                    not(miri),
+                    target_arch = "wasm32",
+                    memchr_runtime_simd128
+                ),
+                all(not(miri), memchr_portable_simd),
             ))]
             GenericSIMD128(ref gs) => {
                 // The SIMD matcher can't handle particularly short haystacks,
@@ -937,7 +1234,7 @@ impl<'n> Searcher<'n> {
             }
             #[cfg(all(
                 not(miri),
-                target_arch = "x86_64",
+                any(target_arch = "x86", target_arch = "x86_64"),
                 memchr_runtime_simd
             ))]
             GenericSIMD256(ref gs) => {
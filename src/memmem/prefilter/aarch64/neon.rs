@@ -0,0 +1,54 @@
+use core::arch::aarch64::uint8x16_t;
+
+use crate::memmem::{
+    prefilter::{PrefilterFnTy, PrefilterState},
+    NeedleInfo,
+};
+
+// Check that the functions below satisfy the Prefilter function type.
+const _: PrefilterFnTy = find;
+
+/// A NEON accelerated candidate finder for single-substring search.
+///
+/// # Safety
+///
+/// Callers must ensure that NEON is available in the current environment.
+/// This module is only compiled when NEON is enabled at compile time, so this
+/// is always the case.
+pub(crate) unsafe fn find(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    // If the haystack is too small for NEON, then just run memchr on the
+    // rarest byte and be done with it.
+    fn simple_memchr_fallback(
+        _prestate: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        let (rare, _) = ninfo.rarebytes.as_rare_ordered_usize();
+        crate::memchr(needle[rare], haystack).map(|i| i.saturating_sub(rare))
+    }
+    super::super::genericsimd::find::<uint8x16_t>(
+        prestate,
+        ninfo,
+        haystack,
+        needle,
+        simple_memchr_fallback,
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        // SAFETY: super::find is safe to call for all inputs on aarch64 with
+        // NEON.
+        unsafe { PrefilterTest::run_all_tests(super::find) };
+    }
+}
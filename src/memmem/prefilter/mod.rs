@@ -1,14 +1,43 @@
 use crate::memmem::{rarebytes::RareNeedleBytes, NeedleInfo};
 
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+mod aarch64;
 mod fallback;
-#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+#[cfg(any(
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ),
+    all(target_arch = "aarch64", memchr_runtime_neon),
+    all(target_arch = "wasm32", memchr_runtime_simd128),
+    feature = "portable-simd",
+))]
 mod genericsimd;
-#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+// The portable prefilter is compiled whenever the feature is enabled, so that
+// it can be tested on all targets, but only used when build.rs selects it.
+#[cfg(all(not(miri), feature = "portable-simd"))]
+#[cfg_attr(not(memchr_portable_simd), allow(dead_code))]
+mod portable;
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+mod wasm32;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
 mod x86;
 
 /// The maximum frequency rank permitted for the fallback prefilter. If the
 /// rarest byte in the needle has a frequency rank above this value, then no
 /// prefilter is used if the fallback prefilter would otherwise be selected.
+#[cfg_attr(
+    any(
+        all(target_arch = "aarch64", memchr_runtime_neon),
+        all(target_arch = "wasm32", memchr_runtime_simd128),
+        memchr_portable_simd,
+    ),
+    allow(dead_code)
+)]
 const MAX_FALLBACK_RANK: usize = 250;
 
 /// A combination of prefilter effectiveness state, the prefilter function and
@@ -21,6 +50,13 @@ pub(crate) struct Pre<'a> {
     /// State that tracks the effectiveness of a prefilter.
     pub(crate) state: &'a mut PrefilterState,
     /// The actual prefilter function.
+    ///
+    /// This is unused when AVX2 is enabled at compile time, since the AVX2
+    /// prefilter is then called directly.
+    #[cfg_attr(
+        all(not(miri), target_feature = "avx2", memchr_runtime_avx),
+        allow(dead_code)
+    )]
     pub(crate) prefn: PrefilterFn,
     /// Information about a needle, such as its RK hash and rare byte offsets.
     pub(crate) ninfo: &'a NeedleInfo,
@@ -34,7 +70,23 @@ impl<'a> Pre<'a> {
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
-        self.prefn.call(self.state, self.ninfo, haystack, needle)
+        // When AVX2 is enabled at compile time, `forward` always selects the
+        // AVX2 prefilter. So call it directly, which permits it to be inlined
+        // instead of going through a function pointer.
+        #[cfg(all(not(miri), target_feature = "avx2", memchr_runtime_avx))]
+        {
+            // SAFETY: x86::avx::find only requires the avx2 feature, which is
+            // enabled at compile time.
+            unsafe { x86::avx::find(self.state, self.ninfo, haystack, needle) }
+        }
+        #[cfg(not(all(
+            not(miri),
+            target_feature = "avx2",
+            memchr_runtime_avx
+        )))]
+        {
+            self.prefn.call(self.state, self.ninfo, haystack, needle)
+        }
     }
 
     /// Return true if and only if this prefilter should be used.
@@ -65,10 +117,14 @@ impl<'a> Pre<'a> {
 ///
 /// A prefilter function is not safe to create, since not all prefilters are
 /// safe to call in all contexts. (e.g., A prefilter that uses AVX instructions
-/// may only be called on x86_64 CPUs with the relevant AVX feature enabled.)
+/// may only be called on x86 CPUs with the relevant AVX feature enabled.)
 /// Thus, callers must ensure that when a prefilter function is created that it
 /// is safe to call for the current environment.
 #[derive(Clone, Copy)]
+#[cfg_attr(
+    all(not(miri), target_feature = "avx2", memchr_runtime_avx),
+    allow(dead_code)
+)]
 pub(crate) struct PrefilterFn(PrefilterFnTy);
 
 /// The type of a prefilter function. All prefilters must satisfy this
@@ -104,6 +160,10 @@ impl PrefilterFn {
     }
 
     /// Call the underlying prefilter function with the given arguments.
+    #[cfg_attr(
+        all(not(miri), target_feature = "avx2", memchr_runtime_avx),
+        allow(dead_code)
+    )]
     pub fn call(
         self,
         prestate: &mut PrefilterState,
@@ -266,10 +326,14 @@ impl PrefilterState {
 
 /// Determine which prefilter function, if any, to use.
 ///
-/// This only applies to x86_64 when runtime SIMD detection is enabled (which
-/// is the default). In general, we try to use an AVX prefilter, followed by
-/// SSE and then followed by a generic one based on memchr.
-#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+/// This only applies to x86 and x86_64 when runtime SIMD detection is enabled
+/// (which is the default). In general, we try to use an AVX prefilter,
+/// followed by SSE and then followed by a generic one based on memchr.
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
 #[inline(always)]
 pub(crate) fn forward(
     config: &Prefilter,
@@ -280,19 +344,18 @@ pub(crate) fn forward(
         return None;
     }
 
-    #[cfg(feature = "std")]
+    #[cfg(memchr_avx2)]
     {
-        if cfg!(memchr_runtime_avx) {
-            if is_x86_feature_detected!("avx2") {
-                // SAFETY: x86::avx::find only requires the avx2 feature,
-                // which we've just checked above.
-                return unsafe { Some(PrefilterFn::new(x86::avx::find)) };
-            }
+        if crate::arch::avx2_detected() {
+            // SAFETY: x86::avx::find only requires the avx2 feature, which
+            // we've just checked above.
+            return unsafe { Some(PrefilterFn::new(x86::avx::find)) };
         }
     }
     if cfg!(memchr_runtime_sse2) {
         // SAFETY: x86::sse::find only requires the sse2 feature, which is
-        // guaranteed to be available on x86_64.
+        // guaranteed to be available on x86_64, and on x86 when
+        // memchr_runtime_sse2 is set.
         return unsafe { Some(PrefilterFn::new(x86::sse::find)) };
     }
     // Check that our rarest byte has a reasonably low rank. The main issue
@@ -308,9 +371,78 @@ pub(crate) fn forward(
 
 /// Determine which prefilter function, if any, to use.
 ///
-/// Since SIMD is currently only supported on x86_64, this will just select
-/// the fallback prefilter if the rare bytes provided have a low enough rank.
-#[cfg(not(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)))]
+/// This only applies to aarch64 when NEON is enabled at compile time, which
+/// it is on all common aarch64 targets. Since NEON is always available, its
+/// prefilter is always used.
+#[cfg(all(not(miri), target_arch = "aarch64", memchr_runtime_neon))]
+#[inline(always)]
+pub(crate) fn forward(
+    config: &Prefilter,
+    _rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+    // SAFETY: aarch64::neon::find only requires NEON, which is enabled at
+    // compile time.
+    unsafe { Some(PrefilterFn::new(aarch64::neon::find)) }
+}
+
+/// Determine which prefilter function, if any, to use.
+///
+/// This only applies to wasm32 when simd128 is enabled at compile time. As
+/// with NEON, its prefilter is then always used.
+#[cfg(all(not(miri), target_arch = "wasm32", memchr_runtime_simd128))]
+#[inline(always)]
+pub(crate) fn forward(
+    config: &Prefilter,
+    _rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+    // SAFETY: wasm32::simd128::find only requires simd128, which is enabled
+    // at compile time.
+    unsafe { Some(PrefilterFn::new(wasm32::simd128::find)) }
+}
+
+/// Determine which prefilter function, if any, to use.
+///
+/// This only applies when the `portable-simd` feature is enabled and there is
+/// no prefilter that is specific to the target. As with NEON, the core::simd
+/// prefilter is then always used.
+#[cfg(all(not(miri), memchr_portable_simd))]
+#[inline(always)]
+pub(crate) fn forward(
+    config: &Prefilter,
+    _rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+    // SAFETY: portable::find is safe to call in all environments.
+    unsafe { Some(PrefilterFn::new(portable::find)) }
+}
+
+/// Determine which prefilter function, if any, to use.
+///
+/// Since SIMD is currently only supported on x86, x86_64, aarch64 and wasm32
+/// (and on other targets with the `portable-simd` feature), this will just
+/// select the fallback prefilter if the rare bytes provided have a low enough
+/// rank.
+#[cfg(not(any(
+    all(
+        not(miri),
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ),
+    all(not(miri), target_arch = "aarch64", memchr_runtime_neon),
+    all(not(miri), target_arch = "wasm32", memchr_runtime_simd128),
+    all(not(miri), memchr_portable_simd),
+)))]
 #[inline(always)]
 pub(crate) fn forward(
     config: &Prefilter,
@@ -559,4 +691,27 @@ pub(crate) mod tests {
             tests
         }
     }
+
+    // When AVX2 is enabled at compile time, calling a prefilter should never
+    // go through its function pointer.
+    #[test]
+    #[cfg(all(target_feature = "avx2", memchr_runtime_avx))]
+    fn prefilter_static_avx2() {
+        unsafe fn never(
+            _: &mut PrefilterState,
+            _: &NeedleInfo,
+            _: &[u8],
+            _: &[u8],
+        ) -> Option<usize> {
+            panic!("prefilter function pointer should not be called")
+        }
+
+        // SAFETY: never is safe to call in all environments.
+        let prefn = unsafe { PrefilterFn::new(never) };
+        for test in PREFILTER_TEST_SEEDS[0].generate() {
+            let mut state = PrefilterState::new();
+            let mut pre = Pre { state: &mut state, prefn, ninfo: &test.ninfo };
+            assert_eq!(test.output, pre.call(&test.haystack, &test.needle));
+        }
+    }
 }
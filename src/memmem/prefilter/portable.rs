@@ -0,0 +1,53 @@
+use core::simd::u8x16;
+
+use crate::memmem::{
+    prefilter::{PrefilterFnTy, PrefilterState},
+    NeedleInfo,
+};
+
+// Check that the functions below satisfy the Prefilter function type.
+const _: PrefilterFnTy = find;
+
+/// A core::simd accelerated candidate finder for single-substring search.
+///
+/// # Safety
+///
+/// This is safe to call in all environments, since the core::simd vector
+/// operations don't require any target features. It is marked as unsafe to
+/// satisfy the Prefilter function type.
+pub(crate) unsafe fn find(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    // If the haystack is too small for a vector, then just run memchr on the
+    // rarest byte and be done with it.
+    fn simple_memchr_fallback(
+        _prestate: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        let (rare, _) = ninfo.rarebytes.as_rare_ordered_usize();
+        crate::memchr(needle[rare], haystack).map(|i| i.saturating_sub(rare))
+    }
+    super::genericsimd::find::<u8x16>(
+        prestate,
+        ninfo,
+        haystack,
+        needle,
+        simple_memchr_fallback,
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        // SAFETY: super::find is safe to call for all inputs.
+        unsafe { PrefilterTest::run_all_tests(super::find) };
+    }
+}
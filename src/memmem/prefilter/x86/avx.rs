@@ -1,3 +1,6 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m256i;
+#[cfg(target_arch = "x86_64")]
 use core::arch::x86_64::__m256i;
 
 use crate::memmem::{
@@ -30,13 +33,13 @@ pub(crate) unsafe fn find(
     )
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     #[test]
     #[cfg(not(miri))]
     fn prefilter_permutations() {
         use crate::memmem::prefilter::tests::PrefilterTest;
-        if !is_x86_feature_detected!("avx2") {
+        if !crate::arch::avx2_detected() {
             return;
         }
         // SAFETY: The safety of super::find only requires that the current
@@ -1,3 +1,6 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m128i;
+#[cfg(target_arch = "x86_64")]
 use core::arch::x86_64::__m128i;
 
 use crate::memmem::{
@@ -13,7 +16,8 @@ const _: PrefilterFnTy = find;
 /// # Safety
 ///
 /// Callers must ensure that the sse2 CPU feature is enabled in the current
-/// environment. This feature should be enabled in all x86_64 targets.
+/// environment. This feature should be enabled in all x86_64 targets, and
+/// this module is only compiled for x86 targets that enable it.
 #[target_feature(enable = "sse2")]
 pub(crate) unsafe fn find(
     prestate: &mut PrefilterState,
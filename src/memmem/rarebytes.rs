@@ -96,7 +96,12 @@ impl RareNeedleBytes {
     /// second offset. This is useful when the caller doesn't care whether
     /// rare1 is rarer than rare2, but just wants to ensure that they are
     /// ordered with respect to one another.
-    #[cfg(memchr_runtime_simd)]
+    #[cfg(any(
+        memchr_runtime_simd,
+        memchr_runtime_neon,
+        memchr_runtime_simd128,
+        feature = "portable-simd",
+    ))]
     pub(crate) fn as_rare_ordered_usize(&self) -> (usize, usize) {
         let (rare1i, rare2i) = self.as_rare_ordered_u8();
         (rare1i as usize, rare2i as usize)
@@ -104,7 +109,12 @@ impl RareNeedleBytes {
 
     /// Like as_rare_ordered_usize, but returns the offsets as their native
     /// u8 values.
-    #[cfg(memchr_runtime_simd)]
+    #[cfg(any(
+        memchr_runtime_simd,
+        memchr_runtime_neon,
+        memchr_runtime_simd128,
+        feature = "portable-simd",
+    ))]
     pub(crate) fn as_rare_ordered_u8(&self) -> (u8, u8) {
         if self.rare1i <= self.rare2i {
             (self.rare1i, self.rare2i)
@@ -123,6 +133,18 @@ impl RareNeedleBytes {
     /// Return the byte frequency rank of each byte. The higher the rank, the
     /// more frequency the byte is predicted to be. The needle given must be
     /// the same one given to the RareNeedleBytes constructor.
+    ///
+    /// This is only used to pick the fallback prefilter, which is never
+    /// picked on aarch64 with NEON, on wasm32 with simd128 or when the
+    /// core::simd prefilter is used.
+    #[cfg_attr(
+        any(
+            all(target_arch = "aarch64", memchr_runtime_neon),
+            all(target_arch = "wasm32", memchr_runtime_simd128),
+            memchr_portable_simd,
+        ),
+        allow(dead_code)
+    )]
     pub(crate) fn as_ranks(&self, needle: &[u8]) -> (usize, usize) {
         let (b1, b2) = self.as_rare_bytes(needle);
         (rank(b1), rank(b2))
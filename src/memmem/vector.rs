@@ -2,12 +2,15 @@
 ///
 /// The trait is highly constrained to low level vector operations needed. In
 /// general, it was invented mostly to be generic over x86's __m128i and
-/// __m256i types. It's likely that once std::simd becomes a thing, we can
-/// migrate to that since the operations required are quite simple.
+/// __m256i types, and it is also implemented for aarch64's uint8x16_t and
+/// wasm32's v128. With the `portable-simd` feature, it is also implemented for
+/// core::simd's u8x16, which is used on targets without any of the above. It's
+/// likely that once core::simd is stabilized, we can migrate to that since the
+/// operations required are quite simple.
 ///
-/// TODO: Consider moving this trait up a level and using it to implement
-/// memchr as well. The trait might need to grow one or two methods, but
-/// otherwise should be close to sufficient already.
+/// TODO: Consider moving this trait up a level. It is already used to
+/// implement memchr on aarch64 and wasm32, but the x86 memchr routines still
+/// use the vendor intrinsics directly.
 ///
 /// # Safety
 ///
@@ -29,11 +32,28 @@ pub(crate) trait Vector: Copy + core::fmt::Debug {
     unsafe fn cmpeq(self, vector2: Self) -> Self;
     /// _mm_and_si128 or _mm256_and_si256
     unsafe fn and(self, vector2: Self) -> Self;
+    /// _mm_or_si128 or _mm256_or_si256
+    unsafe fn or(self, vector2: Self) -> Self;
+    /// _mm_min_epu8 or _mm256_min_epu8
+    ///
+    /// This is only used by routines that are only vectorized on x86.
+    #[cfg_attr(
+        any(
+            target_arch = "aarch64",
+            target_arch = "wasm32",
+            memchr_portable_simd,
+        ),
+        allow(dead_code)
+    )]
+    unsafe fn min_u8(self, vector2: Self) -> Self;
 }
 
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 mod x86sse {
     use super::Vector;
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
     use core::arch::x86_64::*;
 
     impl Vector for __m128i {
@@ -61,12 +81,25 @@ mod x86sse {
         unsafe fn and(self, vector2: Self) -> __m128i {
             _mm_and_si128(self, vector2)
         }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> __m128i {
+            _mm_or_si128(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn min_u8(self, vector2: Self) -> __m128i {
+            _mm_min_epu8(self, vector2)
+        }
     }
 }
 
-#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[cfg(all(memchr_avx2, any(target_arch = "x86", target_arch = "x86_64")))]
 mod x86avx {
     use super::Vector;
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
     use core::arch::x86_64::*;
 
     impl Vector for __m256i {
@@ -94,5 +127,159 @@ mod x86avx {
         unsafe fn and(self, vector2: Self) -> __m256i {
             _mm256_and_si256(self, vector2)
         }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> __m256i {
+            _mm256_or_si256(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn min_u8(self, vector2: Self) -> __m256i {
+            _mm256_min_epu8(self, vector2)
+        }
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", memchr_runtime_neon))]
+mod aarch64neon {
+    use super::Vector;
+    use core::arch::aarch64::*;
+
+    impl Vector for uint8x16_t {
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> uint8x16_t {
+            vdupq_n_u8(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> uint8x16_t {
+            vld1q_u8(data)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u32 {
+            // NEON has no equivalent of _mm_movemask_epi8. Instead, isolate
+            // the high bit of each lane, shift it to the lane's position
+            // within its half of the vector, and then add up each half.
+            const SHIFTS: [i8; 16] =
+                [0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3, 4, 5, 6, 7];
+            let bits = vshrq_n_u8(self, 7);
+            let bits = vshlq_u8(bits, vld1q_s8(SHIFTS.as_ptr()));
+            let low = vaddv_u8(vget_low_u8(bits)) as u32;
+            let high = vaddv_u8(vget_high_u8(bits)) as u32;
+            low | (high << 8)
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> uint8x16_t {
+            vceqq_u8(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> uint8x16_t {
+            vandq_u8(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> uint8x16_t {
+            vorrq_u8(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn min_u8(self, vector2: Self) -> uint8x16_t {
+            vminq_u8(self, vector2)
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", memchr_runtime_simd128))]
+mod wasm32simd128 {
+    use super::Vector;
+    use core::arch::wasm32::*;
+
+    impl Vector for v128 {
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> v128 {
+            u8x16_splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> v128 {
+            v128_load(data as *const v128)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u32 {
+            u8x16_bitmask(self) as u32
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> v128 {
+            u8x16_eq(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> v128 {
+            v128_and(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> v128 {
+            v128_or(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn min_u8(self, vector2: Self) -> v128 {
+            u8x16_min(self, vector2)
+        }
+    }
+}
+
+#[cfg(all(not(miri), feature = "portable-simd"))]
+mod portable {
+    use super::Vector;
+    use core::simd::{
+        cmp::{SimdOrd, SimdPartialEq, SimdPartialOrd},
+        num::SimdInt,
+        u8x16,
+    };
+
+    // None of these require any target features, but they are unsafe to
+    // satisfy the trait.
+    impl Vector for u8x16 {
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> u8x16 {
+            u8x16::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> u8x16 {
+            core::ptr::read_unaligned(data as *const u8x16)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u32 {
+            self.simd_ge(u8x16::splat(0x80)).to_bitmask() as u32
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> u8x16 {
+            self.simd_eq(vector2).to_simd().cast::<u8>()
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> u8x16 {
+            self & vector2
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> u8x16 {
+            self | vector2
+        }
+
+        #[inline(always)]
+        unsafe fn min_u8(self, vector2: Self) -> u8x16 {
+            self.simd_min(vector2)
+        }
     }
 }
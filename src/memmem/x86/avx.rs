@@ -1,10 +1,13 @@
-#[cfg(not(feature = "std"))]
-pub(crate) use self::nostd::Forward;
-#[cfg(feature = "std")]
-pub(crate) use self::std::Forward;
-
-#[cfg(feature = "std")]
-mod std {
+#[cfg(not(memchr_avx2))]
+pub(crate) use self::disabled::Forward;
+#[cfg(memchr_avx2)]
+pub(crate) use self::enabled::Forward;
+
+#[cfg(memchr_avx2)]
+mod enabled {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::{__m128i, __m256i};
+    #[cfg(target_arch = "x86_64")]
     use core::arch::x86_64::{__m128i, __m256i};
 
     use crate::memmem::{genericsimd, NeedleInfo};
@@ -21,7 +24,7 @@ mod std {
             ninfo: &NeedleInfo,
             needle: &[u8],
         ) -> Option<Forward> {
-            if !cfg!(memchr_runtime_avx) || !is_x86_feature_detected!("avx2") {
+            if !crate::arch::avx2_detected() {
                 return None;
             }
             genericsimd::Forward::new(ninfo, needle).map(Forward)
@@ -69,10 +72,11 @@ mod std {
     }
 }
 
-// We still define the avx "forward" type on nostd to make caller code a bit
-// simpler. This avoids needing a lot more conditional compilation.
-#[cfg(not(feature = "std"))]
-mod nostd {
+// We still define the avx "forward" type when AVX2 can't be detected or
+// enabled at compile time, to make caller code a bit simpler. This avoids
+// needing a lot more conditional compilation.
+#[cfg(not(memchr_avx2))]
+mod disabled {
     use crate::memmem::NeedleInfo;
 
     #[derive(Clone, Copy, Debug)]
@@ -117,7 +121,7 @@ mod tests {
     fn prefilter_permutations() {
         use crate::memmem::prefilter::tests::PrefilterTest;
 
-        if !is_x86_feature_detected!("avx2") {
+        if !crate::arch::avx2_detected() {
             return;
         }
         // SAFETY: The safety of find only requires that the current CPU
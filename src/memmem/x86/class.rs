@@ -0,0 +1,66 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{__m128i, __m256i};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::{
+    memmem::{
+        class::{find_fallback, RarePair},
+        genericsimd,
+    },
+    ByteSet,
+};
+
+/// Search for a class needle using the best vectorized routine available on
+/// the current CPU. Haystacks that are too short for a vector load fall back
+/// to memchr.
+#[inline(always)]
+pub(crate) fn find(
+    pair: &RarePair,
+    haystack: &[u8],
+    needle: &[ByteSet],
+) -> Option<usize> {
+    if haystack.len() < genericsimd::class_min_haystack_len::<__m128i>(pair) {
+        find_fallback(pair, haystack, needle)
+    } else if crate::arch::avx2_detected() {
+        // SAFETY: We just checked that avx2 is available.
+        unsafe { find_avx2(pair, haystack, needle) }
+    } else if cfg!(memchr_runtime_sse2) {
+        // SAFETY: sse2 is enabled on all x86_64 targets, and on all x86
+        // targets that this module is compiled for.
+        unsafe { find_sse2(pair, haystack, needle) }
+    } else {
+        find_fallback(pair, haystack, needle)
+    }
+}
+
+/// # Safety
+///
+/// This is safe to call in all cases since sse2 is guaranteed to be part of
+/// x86_64, and of the x86 targets that this module is compiled for. It is
+/// marked as unsafe because of the target feature attribute.
+#[target_feature(enable = "sse2")]
+unsafe fn find_sse2(
+    pair: &RarePair,
+    haystack: &[u8],
+    needle: &[ByteSet],
+) -> Option<usize> {
+    genericsimd::fwd_find_class::<__m128i>(pair, haystack, needle)
+}
+
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+unsafe fn find_avx2(
+    pair: &RarePair,
+    haystack: &[u8],
+    needle: &[ByteSet],
+) -> Option<usize> {
+    if haystack.len() < genericsimd::class_min_haystack_len::<__m256i>(pair) {
+        genericsimd::fwd_find_class::<__m128i>(pair, haystack, needle)
+    } else {
+        genericsimd::fwd_find_class::<__m256i>(pair, haystack, needle)
+    }
+}
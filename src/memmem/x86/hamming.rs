@@ -0,0 +1,48 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{__m128i, __m256i};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::memmem::{genericsimd, hamming::count_mismatches_fallback};
+
+/// Count the number of positions at which `a` and `b` differ, stopping early
+/// once the count exceeds `limit`, using the best vectorized routine
+/// available on the current CPU.
+#[inline(always)]
+pub(crate) fn count_mismatches(a: &[u8], b: &[u8], limit: usize) -> usize {
+    if a.len() < 16 {
+        count_mismatches_fallback(a, b, limit)
+    } else if crate::arch::avx2_detected() {
+        // SAFETY: We just checked that avx2 is available.
+        unsafe { count_mismatches_avx2(a, b, limit) }
+    } else if cfg!(memchr_runtime_sse2) {
+        // SAFETY: sse2 is enabled on all x86_64 targets, and on all x86
+        // targets that this module is compiled for.
+        unsafe { count_mismatches_sse2(a, b, limit) }
+    } else {
+        count_mismatches_fallback(a, b, limit)
+    }
+}
+
+/// # Safety
+///
+/// This is safe to call in all cases since sse2 is guaranteed to be part of
+/// x86_64, and of the x86 targets that this module is compiled for. It is
+/// marked as unsafe because of the target feature attribute.
+#[target_feature(enable = "sse2")]
+unsafe fn count_mismatches_sse2(a: &[u8], b: &[u8], limit: usize) -> usize {
+    genericsimd::count_mismatches::<__m128i>(a, b, limit)
+}
+
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+unsafe fn count_mismatches_avx2(a: &[u8], b: &[u8], limit: usize) -> usize {
+    if a.len() < 32 {
+        genericsimd::count_mismatches::<__m128i>(a, b, limit)
+    } else {
+        genericsimd::count_mismatches::<__m256i>(a, b, limit)
+    }
+}
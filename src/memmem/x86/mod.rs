@@ -1,2 +1,6 @@
 pub(crate) mod avx;
+#[cfg(feature = "std")]
+pub(crate) mod class;
+#[cfg(feature = "std")]
+pub(crate) mod hamming;
 pub(crate) mod sse;
@@ -1,3 +1,6 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m128i;
+#[cfg(target_arch = "x86_64")]
 use core::arch::x86_64::__m128i;
 
 use crate::memmem::{genericsimd, NeedleInfo};
@@ -31,7 +34,8 @@ impl Forward {
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
-        // SAFETY: sse2 is enabled on all x86_64 targets, so this is always
+        // SAFETY: sse2 is enabled on all x86_64 targets, and on all x86
+        // targets that this module is compiled for, so this is always
         // safe to call.
         unsafe { self.find_impl(haystack, needle) }
     }
@@ -41,8 +45,8 @@ impl Forward {
     /// # Safety
     ///
     /// This is safe to call in all cases since sse2 is guaranteed to be part
-    /// of x86_64. It is marked as unsafe because of the target feature
-    /// attribute.
+    /// of x86_64, and of the x86 targets that this module is compiled for. It
+    /// is marked as unsafe because of the target feature attribute.
     #[target_feature(enable = "sse2")]
     unsafe fn find_impl(
         &self,
@@ -70,7 +74,8 @@ mod tests {
     fn prefilter_permutations() {
         use crate::memmem::prefilter::tests::PrefilterTest;
 
-        // SAFETY: sse2 is enabled on all x86_64 targets, so this is always
+        // SAFETY: sse2 is enabled on all x86_64 targets, and on all x86
+        // targets that this module is compiled for, so this is always
         // safe to call.
         unsafe {
             PrefilterTest::run_all_tests_filter(find, |t| {
@@ -0,0 +1,33 @@
+// This module defines a pure Rust platform independent implementation of the
+// byte pair search. It uses memchr to find occurrences of the first byte and
+// then checks the byte at the given distance.
+
+use crate::memchr::{memchr_iter, memrchr_iter};
+
+/// Returns the first position `i` such that `haystack[i] == a` and
+/// `haystack[i + distance] == b`. `distance` must be less than the length of
+/// the haystack.
+pub fn memchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    debug_assert!(distance < haystack.len());
+    let end = haystack.len() - distance;
+    memchr_iter(a, &haystack[..end]).find(|&i| haystack[i + distance] == b)
+}
+
+/// Returns the last position `i` such that `haystack[i] == a` and
+/// `haystack[i + distance] == b`. `distance` must be less than the length of
+/// the haystack.
+pub fn memrchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    debug_assert!(distance < haystack.len());
+    let end = haystack.len() - distance;
+    memrchr_iter(a, &haystack[..end]).find(|&i| haystack[i + distance] == b)
+}
@@ -0,0 +1,114 @@
+use core::mem::size_of;
+
+use crate::{memmem::vector::Vector, mempair::fallback};
+
+/// Returns the first position `i` such that `haystack[i] == a` and
+/// `haystack[i + distance] == b`. `distance` must be less than the length of
+/// the haystack.
+///
+/// This is the same technique used by the generic SIMD substring searcher:
+/// one vector is loaded at `i` and compared with `a` in every lane, another
+/// is loaded at `i + distance` and compared with `b`, and the lanes where
+/// both comparisons succeed are candidates. Since the pair is the entire
+/// "needle" here, every candidate is a match. The final vector overlaps with
+/// the previous one when the number of positions isn't a multiple of the
+/// vector size, which is fine since no position in the overlap matched.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute.
+/// Therefore, callers must ensure that whatever target feature is being used
+/// supports the vector functions that this function is specialized for. (For
+/// the specific vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn memchr_pair<V: Vector>(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    debug_assert!(distance < haystack.len());
+    // The number of positions at which a pair can start.
+    let len = haystack.len() - distance;
+    if len < size_of::<V>() {
+        return fallback::memchr_pair(a, b, distance, haystack);
+    }
+    let (va, vb) = (V::splat(a), V::splat(b));
+    let ptr = haystack.as_ptr();
+    let mut i = 0;
+    while i + size_of::<V>() <= len {
+        let mask = matches::<V>(ptr.add(i), distance, va, vb);
+        if mask != 0 {
+            return Some(i + mask.trailing_zeros() as usize);
+        }
+        i += size_of::<V>();
+    }
+    if i < len {
+        i = len - size_of::<V>();
+        let mask = matches::<V>(ptr.add(i), distance, va, vb);
+        if mask != 0 {
+            return Some(i + mask.trailing_zeros() as usize);
+        }
+    }
+    None
+}
+
+/// Returns the last position `i` such that `haystack[i] == a` and
+/// `haystack[i + distance] == b`. `distance` must be less than the length of
+/// the haystack.
+///
+/// This is the reverse of `memchr_pair`, where the final vector overlaps with
+/// the start of the haystack.
+///
+/// # Safety
+///
+/// Same as `memchr_pair`.
+#[inline(always)]
+pub(crate) unsafe fn memrchr_pair<V: Vector>(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    debug_assert!(distance < haystack.len());
+    let len = haystack.len() - distance;
+    if len < size_of::<V>() {
+        return fallback::memrchr_pair(a, b, distance, haystack);
+    }
+    let (va, vb) = (V::splat(a), V::splat(b));
+    let ptr = haystack.as_ptr();
+    let mut i = len;
+    while i >= size_of::<V>() {
+        let j = i - size_of::<V>();
+        let mask = matches::<V>(ptr.add(j), distance, va, vb);
+        if mask != 0 {
+            return Some(j + 31 - mask.leading_zeros() as usize);
+        }
+        i = j;
+    }
+    if i > 0 {
+        let mask = matches::<V>(ptr, distance, va, vb);
+        if mask != 0 {
+            return Some(31 - mask.leading_zeros() as usize);
+        }
+    }
+    None
+}
+
+/// Returns a mask of the lanes `i` where `*ptr.add(i) == a` and
+/// `*ptr.add(i + distance) == b`.
+///
+/// It must be safe to read `size_of::<V>() + distance` bytes starting at
+/// `ptr`.
+#[inline(always)]
+unsafe fn matches<V: Vector>(
+    ptr: *const u8,
+    distance: usize,
+    va: V,
+    vb: V,
+) -> u32 {
+    let eqa = V::load_unaligned(ptr).cmpeq(va);
+    let eqb = V::load_unaligned(ptr.add(distance)).cmpeq(vb);
+    eqa.and(eqb).movemask()
+}
@@ -0,0 +1,366 @@
+// This module provides routines for finding a pair of bytes separated by a
+// fixed distance, e.g., `\r\n`, or a tag byte followed by a length byte at a
+// known offset. This is the same search performed by the core loop of the
+// generic SIMD substring searcher, exposed on its own.
+
+use core::iter::Rev;
+
+mod fallback;
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+mod genericsimd;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+pub(crate) mod x86;
+
+#[cfg(not(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+)))]
+use self::fallback as imp;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+use self::x86 as imp;
+
+/// An iterator over all occurrences of a byte pair in a haystack.
+#[inline]
+pub fn memchr_pair_iter(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> MemchrPair<'_> {
+    MemchrPair::new(a, b, distance, haystack)
+}
+
+/// An iterator over all occurrences of a byte pair in a haystack, in reverse.
+#[inline]
+pub fn memrchr_pair_iter(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Rev<MemchrPair<'_>> {
+    MemchrPair::new(a, b, distance, haystack).rev()
+}
+
+/// Search for the first position `i` such that `haystack[i] == a` and
+/// `haystack[i + distance] == b`.
+///
+/// A `distance` of `1` finds two adjacent bytes, such as `\r\n`. A `distance`
+/// of `0` finds occurrences of `a` when `a == b`, and never matches
+/// otherwise.
+///
+/// # Example
+///
+/// This shows how to find the first `\r\n` in a haystack:
+///
+/// ```
+/// use memchr::memchr_pair;
+///
+/// let haystack = b"a\rb\r\nc";
+/// assert_eq!(Some(3), memchr_pair(b'\r', b'\n', 1, haystack));
+/// ```
+///
+/// And this shows how to find a tag byte whose length byte, two bytes later,
+/// is zero:
+///
+/// ```
+/// use memchr::memchr_pair;
+///
+/// let haystack = b"\x01..\x05\x01..\x00";
+/// assert_eq!(Some(4), memchr_pair(0x01, 0x00, 3, haystack));
+/// ```
+#[inline]
+pub fn memchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    if distance >= haystack.len() {
+        return None;
+    }
+    imp::memchr_pair(a, b, distance, haystack)
+}
+
+/// Search for the last position `i` such that `haystack[i] == a` and
+/// `haystack[i + distance] == b`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memrchr_pair;
+///
+/// let haystack = b"a\r\nb\r\nc";
+/// assert_eq!(Some(4), memrchr_pair(b'\r', b'\n', 1, haystack));
+/// ```
+#[inline]
+pub fn memrchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    if distance >= haystack.len() {
+        return None;
+    }
+    imp::memrchr_pair(a, b, distance, haystack)
+}
+
+/// An iterator for `memchr_pair`.
+///
+/// This yields the starting position of every occurrence of the pair,
+/// including occurrences that overlap.
+#[derive(Clone, Debug)]
+pub struct MemchrPair<'a> {
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &'a [u8],
+    // The range of positions at which an occurrence may still start.
+    start: usize,
+    end: usize,
+}
+
+impl<'a> MemchrPair<'a> {
+    /// Creates a new iterator that yields all positions of the pair in
+    /// haystack.
+    #[inline]
+    pub fn new(
+        a: u8,
+        b: u8,
+        distance: usize,
+        haystack: &[u8],
+    ) -> MemchrPair<'_> {
+        let end = haystack.len().saturating_sub(distance);
+        MemchrPair { a, b, distance, haystack, start: 0, end }
+    }
+
+    /// Returns the portion of the haystack in which the remaining occurrences
+    /// are found. This must only be called when `start < end`.
+    #[inline(always)]
+    fn remaining(&self) -> &'a [u8] {
+        &self.haystack[self.start..self.end + self.distance]
+    }
+}
+
+impl<'a> Iterator for MemchrPair<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.start >= self.end {
+            return None;
+        }
+        let i =
+            imp::memchr_pair(self.a, self.b, self.distance, self.remaining());
+        match i {
+            None => {
+                self.start = self.end;
+                None
+            }
+            Some(i) => {
+                let found = self.start + i;
+                self.start = found + 1;
+                Some(found)
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end.saturating_sub(self.start)))
+    }
+}
+
+impl<'a> DoubleEndedIterator for MemchrPair<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        if self.start >= self.end {
+            return None;
+        }
+        let i =
+            imp::memrchr_pair(self.a, self.b, self.distance, self.remaining());
+        match i {
+            None => {
+                self.end = self.start;
+                None
+            }
+            Some(i) => {
+                let found = self.start + i;
+                self.end = found;
+                Some(found)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{
+        fallback, memchr_pair, memchr_pair_iter, memrchr_pair,
+        memrchr_pair_iter,
+    };
+
+    fn naive_all(
+        a: u8,
+        b: u8,
+        distance: usize,
+        haystack: &[u8],
+    ) -> Vec<usize> {
+        (0..haystack.len())
+            .filter(|&i| {
+                i + distance < haystack.len()
+                    && haystack[i] == a
+                    && haystack[i + distance] == b
+            })
+            .collect()
+    }
+
+    /// Run the given forward and reverse routines on haystacks with up to two
+    /// planted occurrences of a pair, for a variety of distances, and compare
+    /// them with a naive implementation.
+    fn run_tests(
+        fwd: impl Fn(u8, u8, usize, &[u8]) -> Option<usize>,
+        rev: impl Fn(u8, u8, usize, &[u8]) -> Option<usize>,
+    ) {
+        for len in 1..80 {
+            for &distance in &[0, 1, 2, 7, 15, 16, 17, 31, 32, 33, 40] {
+                if distance >= len {
+                    continue;
+                }
+                let base = vec![b'z'; len];
+                for i in 0..len - distance {
+                    for j in (i..len - distance).step_by(11) {
+                        let mut h = base.clone();
+                        h[i] = b'a';
+                        h[i + distance] = b'b';
+                        // Plant an `a` without its `b` as a decoy.
+                        if j + distance != i && j != i + distance {
+                            h[j] = b'a';
+                        }
+                        for &(a, b) in
+                            &[(b'a', b'b'), (b'a', b'a'), (b'z', b'b')]
+                        {
+                            let all = naive_all(a, b, distance, &h);
+                            assert_eq!(
+                                all.first().cloned(),
+                                fwd(a, b, distance, &h),
+                                "{:?} {:?} {:?} {:?}",
+                                a,
+                                b,
+                                distance,
+                                h
+                            );
+                            assert_eq!(
+                                all.last().cloned(),
+                                rev(a, b, distance, &h),
+                                "{:?} {:?} {:?} {:?}",
+                                a,
+                                b,
+                                distance,
+                                h
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pair_auto() {
+        run_tests(super::imp::memchr_pair, super::imp::memrchr_pair);
+    }
+
+    #[test]
+    fn pair_fallback() {
+        run_tests(fallback::memchr_pair, fallback::memrchr_pair);
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
+    #[test]
+    fn pair_sse2() {
+        use super::x86::sse2;
+
+        run_tests(
+            |a, b, d, h| unsafe { sse2::memchr_pair(a, b, d, h) },
+            |a, b, d, h| unsafe { sse2::memrchr_pair(a, b, d, h) },
+        );
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_avx
+    ))]
+    #[test]
+    fn pair_avx2() {
+        use super::x86::avx;
+
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        run_tests(
+            |a, b, d, h| unsafe { avx::memchr_pair(a, b, d, h) },
+            |a, b, d, h| unsafe { avx::memrchr_pair(a, b, d, h) },
+        );
+    }
+
+    #[test]
+    fn distance_too_big() {
+        assert_eq!(None, memchr_pair(b'a', b'a', 3, b"aaa"));
+        assert_eq!(None, memrchr_pair(b'a', b'a', usize::MAX, b"aaa"));
+        assert_eq!(0, memchr_pair_iter(b'a', b'a', 5, b"aaa").count());
+    }
+
+    quickcheck! {
+        fn qc_iter(haystack: Vec<u8>, a: u8, b: u8, distance: usize) -> bool {
+            // Use a small alphabet so that matches are likely.
+            let haystack: Vec<u8> = haystack.iter().map(|&x| x % 3).collect();
+            let (a, b, distance) = (a % 3, b % 3, distance % 8);
+            let all = naive_all(a, b, distance, &haystack);
+            let fwd: Vec<usize> =
+                memchr_pair_iter(a, b, distance, &haystack).collect();
+            let mut rev: Vec<usize> =
+                memrchr_pair_iter(a, b, distance, &haystack).collect();
+            rev.reverse();
+            fwd == all && rev == all
+        }
+
+        fn qc_iter_both_ends(haystack: Vec<u8>, distance: usize) -> bool {
+            let haystack: Vec<u8> = haystack.iter().map(|&x| x % 2).collect();
+            let distance = distance % 4;
+            let all = naive_all(0, 1, distance, &haystack);
+            let mut it = memchr_pair_iter(0, 1, distance, &haystack);
+            let (mut front, mut back) = (vec![], vec![]);
+            loop {
+                match it.next() {
+                    None => break,
+                    Some(i) => front.push(i),
+                }
+                match it.next_back() {
+                    None => break,
+                    Some(i) => back.push(i),
+                }
+            }
+            back.reverse();
+            front.extend(back);
+            front == all
+        }
+    }
+}
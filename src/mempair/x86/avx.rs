@@ -0,0 +1,45 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{__m128i, __m256i};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::mempair::genericsimd;
+
+/// An AVX2 accelerated forward byte pair search.
+///
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn memchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() - distance < 32 {
+        genericsimd::memchr_pair::<__m128i>(a, b, distance, haystack)
+    } else {
+        genericsimd::memchr_pair::<__m256i>(a, b, distance, haystack)
+    }
+}
+
+/// An AVX2 accelerated reverse byte pair search.
+///
+/// # Safety
+///
+/// Same as `memchr_pair`.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn memrchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    if haystack.len() - distance < 32 {
+        genericsimd::memrchr_pair::<__m128i>(a, b, distance, haystack)
+    } else {
+        genericsimd::memrchr_pair::<__m256i>(a, b, distance, haystack)
+    }
+}
@@ -0,0 +1,27 @@
+#[cfg(not(all(target_feature = "avx2", memchr_runtime_avx)))]
+use crate::mempair::fallback;
+
+// We only use AVX when we can detect at runtime whether it's available, which
+// requires std or the cpuid feature, or when it's enabled at compile time.
+#[cfg(memchr_avx2)]
+pub(crate) mod avx;
+#[cfg_attr(all(target_feature = "avx2", memchr_runtime_avx), allow(dead_code))]
+pub(crate) mod sse2;
+
+unsafe_ifunc! {
+    MEMCHR_PAIR, memchr_pair_detect,
+    fn memchr_pair(
+        a: u8,
+        b: u8,
+        distance: usize,
+        haystack: &[u8],
+    ) -> Option<usize>;
+
+    MEMRCHR_PAIR, memrchr_pair_detect,
+    fn memrchr_pair(
+        a: u8,
+        b: u8,
+        distance: usize,
+        haystack: &[u8],
+    ) -> Option<usize>;
+}
@@ -0,0 +1,38 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m128i;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::__m128i;
+
+use crate::mempair::genericsimd;
+
+/// An SSE2 accelerated forward byte pair search.
+///
+/// # Safety
+///
+/// Callers must ensure that the sse2 CPU feature is enabled in the current
+/// environment. This feature should be enabled in all x86_64 targets, and
+/// this module is only compiled for x86 targets that enable it.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn memchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    genericsimd::memchr_pair::<__m128i>(a, b, distance, haystack)
+}
+
+/// An SSE2 accelerated reverse byte pair search.
+///
+/// # Safety
+///
+/// Same as `memchr_pair`.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn memrchr_pair(
+    a: u8,
+    b: u8,
+    distance: usize,
+    haystack: &[u8],
+) -> Option<usize> {
+    genericsimd::memrchr_pair::<__m128i>(a, b, distance, haystack)
+}
@@ -0,0 +1,58 @@
+// This module defines a pure Rust platform independent implementation of the
+// run routines. Measuring a run compares one word at a time against a word
+// with every byte set to the run's byte. Finding two adjacent equal bytes is
+// done one byte at a time, since it is only used for very short haystacks on
+// targets with vector support.
+
+use crate::{
+    memchr::fallback::{repeat_byte, USIZE_BYTES},
+    mismatch::fallback::{first_nonzero_byte, last_nonzero_byte, read_usize},
+};
+
+/// Returns the number of bytes at the start of `haystack` equal to `byte`.
+pub fn run_len(byte: u8, haystack: &[u8]) -> usize {
+    let vb = repeat_byte(byte);
+    let ptr = haystack.as_ptr();
+    let mut i = 0;
+
+    unsafe {
+        while i + USIZE_BYTES <= haystack.len() {
+            let x = read_usize(ptr.add(i)) ^ vb;
+            if x != 0 {
+                return i + first_nonzero_byte(x);
+            }
+            i += USIZE_BYTES;
+        }
+    }
+    i + haystack[i..].iter().take_while(|&&b| b == byte).count()
+}
+
+/// Returns the number of bytes at the end of `haystack` equal to `byte`.
+pub fn rrun_len(byte: u8, haystack: &[u8]) -> usize {
+    let vb = repeat_byte(byte);
+    let ptr = haystack.as_ptr();
+    let mut i = haystack.len();
+
+    unsafe {
+        while i >= USIZE_BYTES {
+            let j = i - USIZE_BYTES;
+            let x = read_usize(ptr.add(j)) ^ vb;
+            if x != 0 {
+                return haystack.len() - (j + last_nonzero_byte(x) + 1);
+            }
+            i = j;
+        }
+    }
+    haystack.len() - i
+        + haystack[..i].iter().rev().take_while(|&&b| b == byte).count()
+}
+
+/// Returns the first position `i` such that `haystack[i] == haystack[i + 1]`.
+pub fn find_repeat(haystack: &[u8]) -> Option<usize> {
+    haystack.windows(2).position(|w| w[0] == w[1])
+}
+
+/// Returns the last position `i` such that `haystack[i] == haystack[i + 1]`.
+pub fn rfind_repeat(haystack: &[u8]) -> Option<usize> {
+    haystack.windows(2).rposition(|w| w[0] == w[1])
+}
@@ -0,0 +1,165 @@
+use core::mem::size_of;
+
+use crate::{memmem::vector::Vector, memrun::fallback};
+
+/// Returns the number of bytes at the start of `haystack` equal to `byte`.
+///
+/// Each vector of the haystack is compared with a vector of `byte` in every
+/// lane, and the run ends at the first lane that isn't equal. As with
+/// `mismatch`, the final vector overlaps with the previous one when the
+/// length isn't a multiple of the vector size, which is fine since every
+/// position in the overlap is already known to be equal to `byte`.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute.
+/// Therefore, callers must ensure that whatever target feature is being used
+/// supports the vector functions that this function is specialized for. (For
+/// the specific vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn run_len<V: Vector>(byte: u8, haystack: &[u8]) -> usize {
+    let len = haystack.len();
+    if len < size_of::<V>() {
+        return fallback::run_len(byte, haystack);
+    }
+    let (ptr, vb) = (haystack.as_ptr(), V::splat(byte));
+    let mut i = 0;
+    while i + size_of::<V>() <= len {
+        let mask = not_equal::<V>(V::load_unaligned(ptr.add(i)), vb);
+        if mask != 0 {
+            return i + mask.trailing_zeros() as usize;
+        }
+        i += size_of::<V>();
+    }
+    if i < len {
+        i = len - size_of::<V>();
+        let mask = not_equal::<V>(V::load_unaligned(ptr.add(i)), vb);
+        if mask != 0 {
+            return i + mask.trailing_zeros() as usize;
+        }
+    }
+    len
+}
+
+/// Returns the number of bytes at the end of `haystack` equal to `byte`.
+///
+/// This is the reverse of `run_len`, where the final vector overlaps with the
+/// start of the haystack.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[inline(always)]
+pub(crate) unsafe fn rrun_len<V: Vector>(byte: u8, haystack: &[u8]) -> usize {
+    let len = haystack.len();
+    if len < size_of::<V>() {
+        return fallback::rrun_len(byte, haystack);
+    }
+    let (ptr, vb) = (haystack.as_ptr(), V::splat(byte));
+    let mut i = len;
+    while i >= size_of::<V>() {
+        let j = i - size_of::<V>();
+        let mask = not_equal::<V>(V::load_unaligned(ptr.add(j)), vb);
+        if mask != 0 {
+            return len - (j + last_set(mask) + 1);
+        }
+        i = j;
+    }
+    if i > 0 {
+        let mask = not_equal::<V>(V::load_unaligned(ptr), vb);
+        if mask != 0 {
+            return len - (last_set(mask) + 1);
+        }
+    }
+    len
+}
+
+/// Returns the first position `i` such that `haystack[i] == haystack[i + 1]`.
+///
+/// This compares a vector loaded at `i` with a vector loaded at `i + 1`, so
+/// that each lane compares a byte with its successor.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[inline(always)]
+pub(crate) unsafe fn find_repeat<V: Vector>(haystack: &[u8]) -> Option<usize> {
+    let len = haystack.len();
+    if len < size_of::<V>() + 1 {
+        return fallback::find_repeat(haystack);
+    }
+    let ptr = haystack.as_ptr();
+    let mut i = 0;
+    while i + size_of::<V>() < len {
+        let mask = successor_equal::<V>(ptr.add(i));
+        if mask != 0 {
+            return Some(i + mask.trailing_zeros() as usize);
+        }
+        i += size_of::<V>();
+    }
+    if i + 1 < len {
+        i = len - size_of::<V>() - 1;
+        let mask = successor_equal::<V>(ptr.add(i));
+        if mask != 0 {
+            return Some(i + mask.trailing_zeros() as usize);
+        }
+    }
+    None
+}
+
+/// Returns the last position `i` such that `haystack[i] == haystack[i + 1]`.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[inline(always)]
+pub(crate) unsafe fn rfind_repeat<V: Vector>(
+    haystack: &[u8],
+) -> Option<usize> {
+    let len = haystack.len();
+    if len < size_of::<V>() + 1 {
+        return fallback::rfind_repeat(haystack);
+    }
+    let ptr = haystack.as_ptr();
+    // The number of positions that have a successor.
+    let mut i = len - 1;
+    while i >= size_of::<V>() {
+        let j = i - size_of::<V>();
+        let mask = successor_equal::<V>(ptr.add(j));
+        if mask != 0 {
+            return Some(j + last_set(mask));
+        }
+        i = j;
+    }
+    if i > 0 {
+        let mask = successor_equal::<V>(ptr);
+        if mask != 0 {
+            return Some(last_set(mask));
+        }
+    }
+    None
+}
+
+/// Returns a mask of the lanes in `a` that differ from `b`.
+#[inline(always)]
+unsafe fn not_equal<V: Vector>(a: V, b: V) -> u32 {
+    let all =
+        if size_of::<V>() >= 32 { !0 } else { (1 << size_of::<V>()) - 1 };
+    !a.cmpeq(b).movemask() & all
+}
+
+/// Returns a mask of the lanes whose byte equals the byte that follows it.
+///
+/// It must be safe to read `size_of::<V>() + 1` bytes starting at `ptr`.
+#[inline(always)]
+unsafe fn successor_equal<V: Vector>(ptr: *const u8) -> u32 {
+    V::load_unaligned(ptr).cmpeq(V::load_unaligned(ptr.add(1))).movemask()
+}
+
+/// Returns the index of the highest set bit in `mask`, which must not be
+/// zero.
+#[inline(always)]
+fn last_set(mask: u32) -> usize {
+    31 - mask.leading_zeros() as usize
+}
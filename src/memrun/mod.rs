@@ -0,0 +1,397 @@
+// This module provides routines for finding runs of a repeated byte, such as
+// zero-filled padding or long lines of `=`. Runs of a specific byte are found
+// by using memchr to find a candidate and then measuring how far the run
+// extends with a vectorized "all lanes equal" comparison. Runs of any byte
+// are found by searching for two equal adjacent bytes instead of a specific
+// byte.
+
+use crate::memchr::{memchr, memrchr};
+
+mod fallback;
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+mod genericsimd;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+pub(crate) mod x86;
+
+#[cfg(not(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+)))]
+use self::fallback as imp;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+use self::x86 as imp;
+
+/// Returns the starting position of the first run of at least `min_len`
+/// consecutive occurrences of `byte` in `haystack`.
+///
+/// The position returned is always the start of a maximal run, i.e., the byte
+/// before it (if any) is not `byte`. Use [`run_len`] to find out how long the
+/// run is. If `min_len` is zero, then this always returns `Some(0)`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::find_run;
+///
+/// let haystack = b"a==b====c";
+/// assert_eq!(Some(1), find_run(b'=', 2, haystack));
+/// assert_eq!(Some(4), find_run(b'=', 3, haystack));
+/// assert_eq!(None, find_run(b'=', 5, haystack));
+/// ```
+#[inline]
+pub fn find_run(byte: u8, min_len: usize, haystack: &[u8]) -> Option<usize> {
+    if min_len == 0 {
+        return Some(0);
+    }
+    let mut pos = 0;
+    while let Some(i) = memchr(byte, &haystack[pos..]) {
+        let start = pos + i;
+        let len = imp::run_len(byte, &haystack[start..]);
+        if len >= min_len {
+            return Some(start);
+        }
+        pos = start + len;
+    }
+    None
+}
+
+/// Returns the starting position of the last run of at least `min_len`
+/// consecutive occurrences of `byte` in `haystack`.
+///
+/// The position returned is always the start of a maximal run, i.e., the byte
+/// before it (if any) is not `byte`. If `min_len` is zero, then this always
+/// returns `Some(haystack.len())`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::rfind_run;
+///
+/// let haystack = b"\x00\x00\x00\x00ab\x00\x00c";
+/// assert_eq!(Some(6), rfind_run(0, 2, haystack));
+/// assert_eq!(Some(0), rfind_run(0, 3, haystack));
+/// assert_eq!(None, rfind_run(0, 5, haystack));
+/// ```
+#[inline]
+pub fn rfind_run(byte: u8, min_len: usize, haystack: &[u8]) -> Option<usize> {
+    if min_len == 0 {
+        return Some(haystack.len());
+    }
+    let mut end = haystack.len();
+    while let Some(i) = memrchr(byte, &haystack[..end]) {
+        let len = imp::rrun_len(byte, &haystack[..i + 1]);
+        let start = i + 1 - len;
+        if len >= min_len {
+            return Some(start);
+        }
+        end = start;
+    }
+    None
+}
+
+/// Returns the length of the run of equal bytes starting at `at` in
+/// `haystack`.
+///
+/// That is, this returns the number of consecutive bytes starting at `at`
+/// that are equal to `haystack[at]`. If `at` is equal to `haystack.len()`,
+/// then this returns `0`.
+///
+/// # Panics
+///
+/// This panics if `at > haystack.len()`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::run_len;
+///
+/// let haystack = b"ab----c";
+/// assert_eq!(4, run_len(haystack, 2));
+/// assert_eq!(2, run_len(haystack, 4));
+/// assert_eq!(1, run_len(haystack, 6));
+/// assert_eq!(0, run_len(haystack, 7));
+/// ```
+#[inline]
+pub fn run_len(haystack: &[u8], at: usize) -> usize {
+    let haystack = &haystack[at..];
+    match haystack.first() {
+        None => 0,
+        Some(&byte) => imp::run_len(byte, haystack),
+    }
+}
+
+/// Returns the starting position of the first run of at least `min_len`
+/// consecutive occurrences of any single byte in `haystack`.
+///
+/// The position returned is always the start of a maximal run. If `min_len`
+/// is zero, then this always returns `Some(0)`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::find_any_run;
+///
+/// let haystack = b"abbcdddde";
+/// assert_eq!(Some(1), find_any_run(2, haystack));
+/// assert_eq!(Some(4), find_any_run(3, haystack));
+/// assert_eq!(None, find_any_run(5, haystack));
+/// ```
+#[inline]
+pub fn find_any_run(min_len: usize, haystack: &[u8]) -> Option<usize> {
+    if min_len <= 1 {
+        return if min_len == 0 || !haystack.is_empty() {
+            Some(0)
+        } else {
+            None
+        };
+    }
+    let mut pos = 0;
+    while let Some(i) = imp::find_repeat(&haystack[pos..]) {
+        let start = pos + i;
+        let len = imp::run_len(haystack[start], &haystack[start..]);
+        if len >= min_len {
+            return Some(start);
+        }
+        pos = start + len;
+    }
+    None
+}
+
+/// Returns the starting position of the last run of at least `min_len`
+/// consecutive occurrences of any single byte in `haystack`.
+///
+/// The position returned is always the start of a maximal run. If `min_len`
+/// is zero, then this always returns `Some(haystack.len())`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::rfind_any_run;
+///
+/// let haystack = b"abbbcdde";
+/// assert_eq!(Some(7), rfind_any_run(1, haystack));
+/// assert_eq!(Some(5), rfind_any_run(2, haystack));
+/// assert_eq!(Some(1), rfind_any_run(3, haystack));
+/// assert_eq!(None, rfind_any_run(4, haystack));
+/// ```
+#[inline]
+pub fn rfind_any_run(min_len: usize, haystack: &[u8]) -> Option<usize> {
+    if min_len == 0 {
+        return Some(haystack.len());
+    }
+    if min_len == 1 {
+        let &last = haystack.last()?;
+        return Some(haystack.len() - imp::rrun_len(last, haystack));
+    }
+    let mut end = haystack.len();
+    while let Some(i) = imp::rfind_repeat(&haystack[..end]) {
+        let len = imp::rrun_len(haystack[i], &haystack[..i + 2]);
+        let start = i + 2 - len;
+        if len >= min_len {
+            return Some(start);
+        }
+        end = start;
+    }
+    None
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{fallback, find_any_run, find_run, rfind_any_run, rfind_run};
+
+    /// Returns the start and length of every maximal run in `haystack`.
+    fn runs(haystack: &[u8]) -> Vec<(usize, usize)> {
+        let mut runs = vec![];
+        let mut i = 0;
+        while i < haystack.len() {
+            let len = haystack[i..]
+                .iter()
+                .take_while(|&&b| b == haystack[i])
+                .count();
+            runs.push((i, len));
+            i += len;
+        }
+        runs
+    }
+
+    fn naive_run_len(byte: u8, haystack: &[u8]) -> usize {
+        haystack.iter().take_while(|&&b| b == byte).count()
+    }
+
+    fn naive_rrun_len(byte: u8, haystack: &[u8]) -> usize {
+        haystack.iter().rev().take_while(|&&b| b == byte).count()
+    }
+
+    fn naive_find_repeat(haystack: &[u8]) -> Option<usize> {
+        (1..haystack.len())
+            .find(|&i| haystack[i - 1] == haystack[i])
+            .map(|i| i - 1)
+    }
+
+    fn naive_rfind_repeat(haystack: &[u8]) -> Option<usize> {
+        (1..haystack.len())
+            .rev()
+            .find(|&i| haystack[i - 1] == haystack[i])
+            .map(|i| i - 1)
+    }
+
+    /// Run the given routines on haystacks made of a run of `a` surrounded
+    /// by other bytes, for every run position and length, and compare them
+    /// with naive implementations.
+    fn run_tests(
+        run_len: impl Fn(u8, &[u8]) -> usize,
+        rrun_len: impl Fn(u8, &[u8]) -> usize,
+        find_repeat: impl Fn(&[u8]) -> Option<usize>,
+        rfind_repeat: impl Fn(&[u8]) -> Option<usize>,
+    ) {
+        for len in 0..80 {
+            // A haystack without any two equal adjacent bytes.
+            let base: Vec<u8> =
+                (0..len).map(|i| (i % 2) as u8 + b'x').collect();
+            for start in 0..=len {
+                for end in start..=len {
+                    let mut h = base.clone();
+                    for b in &mut h[start..end] {
+                        *b = b'a';
+                    }
+                    for &byte in b"ax" {
+                        assert_eq!(
+                            naive_run_len(byte, &h[start..]),
+                            run_len(byte, &h[start..]),
+                            "{:?} {:?}",
+                            byte,
+                            h
+                        );
+                        assert_eq!(
+                            naive_rrun_len(byte, &h[..end]),
+                            rrun_len(byte, &h[..end]),
+                            "{:?} {:?}",
+                            byte,
+                            h
+                        );
+                    }
+                    assert_eq!(
+                        naive_find_repeat(&h),
+                        find_repeat(&h),
+                        "{:?}",
+                        h
+                    );
+                    assert_eq!(
+                        naive_rfind_repeat(&h),
+                        rfind_repeat(&h),
+                        "{:?}",
+                        h
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_auto() {
+        run_tests(
+            super::imp::run_len,
+            super::imp::rrun_len,
+            super::imp::find_repeat,
+            super::imp::rfind_repeat,
+        );
+    }
+
+    #[test]
+    fn kernels_fallback() {
+        run_tests(
+            fallback::run_len,
+            fallback::rrun_len,
+            fallback::find_repeat,
+            fallback::rfind_repeat,
+        );
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
+    #[test]
+    fn kernels_sse2() {
+        use super::x86::sse2;
+
+        run_tests(
+            |b, h| unsafe { sse2::run_len(b, h) },
+            |b, h| unsafe { sse2::rrun_len(b, h) },
+            |h| unsafe { sse2::find_repeat(h) },
+            |h| unsafe { sse2::rfind_repeat(h) },
+        );
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_avx
+    ))]
+    #[test]
+    fn kernels_avx2() {
+        use super::x86::avx;
+
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        run_tests(
+            |b, h| unsafe { avx::run_len(b, h) },
+            |b, h| unsafe { avx::rrun_len(b, h) },
+            |h| unsafe { avx::find_repeat(h) },
+            |h| unsafe { avx::rfind_repeat(h) },
+        );
+    }
+
+    #[test]
+    fn zero_min_len() {
+        assert_eq!(Some(0), find_run(b'a', 0, b""));
+        assert_eq!(Some(3), rfind_run(b'a', 0, b"xyz"));
+        assert_eq!(Some(0), find_any_run(0, b""));
+        assert_eq!(Some(3), rfind_any_run(0, b"xyz"));
+        assert_eq!(None, find_any_run(1, b""));
+        assert_eq!(None, rfind_any_run(1, b""));
+    }
+
+    quickcheck! {
+        fn qc_runs(haystack: Vec<u8>, byte: u8, min_len: usize) -> bool {
+            // Use a small alphabet so that runs are likely.
+            let haystack: Vec<u8> = haystack.iter().map(|&b| b % 3).collect();
+            let byte = byte % 3;
+            let min_len = min_len % 8 + 1;
+            let runs = runs(&haystack);
+            let first = |f: &dyn Fn(u8) -> bool| {
+                runs.iter()
+                    .find(|&&(s, len)| len >= min_len && f(haystack[s]))
+                    .map(|&(s, _)| s)
+            };
+            let last = |f: &dyn Fn(u8) -> bool| {
+                runs.iter()
+                    .rev()
+                    .find(|&&(s, len)| len >= min_len && f(haystack[s]))
+                    .map(|&(s, _)| s)
+            };
+            let runs_ok = runs
+                .iter()
+                .all(|&(s, len)| super::run_len(&haystack, s) == len);
+            runs_ok
+                && find_run(byte, min_len, &haystack) == first(&|b| b == byte)
+                && rfind_run(byte, min_len, &haystack) == last(&|b| b == byte)
+                && find_any_run(min_len, &haystack) == first(&|_| true)
+                && rfind_any_run(min_len, &haystack) == last(&|_| true)
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{__m128i, __m256i};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::memrun::genericsimd;
+
+/// An AVX2 accelerated measurement of the run at the start of a haystack.
+///
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn run_len(byte: u8, haystack: &[u8]) -> usize {
+    if haystack.len() < 32 {
+        genericsimd::run_len::<__m128i>(byte, haystack)
+    } else {
+        genericsimd::run_len::<__m256i>(byte, haystack)
+    }
+}
+
+/// An AVX2 accelerated measurement of the run at the end of a haystack.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn rrun_len(byte: u8, haystack: &[u8]) -> usize {
+    if haystack.len() < 32 {
+        genericsimd::rrun_len::<__m128i>(byte, haystack)
+    } else {
+        genericsimd::rrun_len::<__m256i>(byte, haystack)
+    }
+}
+
+/// An AVX2 accelerated search for the first pair of equal adjacent bytes.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn find_repeat(haystack: &[u8]) -> Option<usize> {
+    if haystack.len() < 33 {
+        genericsimd::find_repeat::<__m128i>(haystack)
+    } else {
+        genericsimd::find_repeat::<__m256i>(haystack)
+    }
+}
+
+/// An AVX2 accelerated search for the last pair of equal adjacent bytes.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn rfind_repeat(haystack: &[u8]) -> Option<usize> {
+    if haystack.len() < 33 {
+        genericsimd::rfind_repeat::<__m128i>(haystack)
+    } else {
+        genericsimd::rfind_repeat::<__m256i>(haystack)
+    }
+}
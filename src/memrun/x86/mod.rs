@@ -0,0 +1,23 @@
+#[cfg(not(all(target_feature = "avx2", memchr_runtime_avx)))]
+use crate::memrun::fallback;
+
+// We only use AVX when we can detect at runtime whether it's available, which
+// requires std or the cpuid feature, or when it's enabled at compile time.
+#[cfg(memchr_avx2)]
+pub(crate) mod avx;
+#[cfg_attr(all(target_feature = "avx2", memchr_runtime_avx), allow(dead_code))]
+pub(crate) mod sse2;
+
+unsafe_ifunc! {
+    RUN_LEN, run_len_detect,
+    fn run_len(byte: u8, haystack: &[u8]) -> usize;
+
+    RRUN_LEN, rrun_len_detect,
+    fn rrun_len(byte: u8, haystack: &[u8]) -> usize;
+
+    FIND_REPEAT, find_repeat_detect,
+    fn find_repeat(haystack: &[u8]) -> Option<usize>;
+
+    RFIND_REPEAT, rfind_repeat_detect,
+    fn rfind_repeat(haystack: &[u8]) -> Option<usize>;
+}
@@ -0,0 +1,48 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m128i;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::__m128i;
+
+use crate::memrun::genericsimd;
+
+/// An SSE2 accelerated measurement of the run at the start of a haystack.
+///
+/// # Safety
+///
+/// Callers must ensure that the sse2 CPU feature is enabled in the current
+/// environment. This feature should be enabled in all x86_64 targets, and
+/// this module is only compiled for x86 targets that enable it.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn run_len(byte: u8, haystack: &[u8]) -> usize {
+    genericsimd::run_len::<__m128i>(byte, haystack)
+}
+
+/// An SSE2 accelerated measurement of the run at the end of a haystack.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn rrun_len(byte: u8, haystack: &[u8]) -> usize {
+    genericsimd::rrun_len::<__m128i>(byte, haystack)
+}
+
+/// An SSE2 accelerated search for the first pair of equal adjacent bytes.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn find_repeat(haystack: &[u8]) -> Option<usize> {
+    genericsimd::find_repeat::<__m128i>(haystack)
+}
+
+/// An SSE2 accelerated search for the last pair of equal adjacent bytes.
+///
+/// # Safety
+///
+/// Same as `run_len`.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn rfind_repeat(haystack: &[u8]) -> Option<usize> {
+    genericsimd::rfind_repeat::<__m128i>(haystack)
+}
@@ -0,0 +1,77 @@
+// This module defines a pure Rust platform independent implementation of
+// mismatch search. It compares one word at a time, and uses the position of
+// the first (or last) non-zero byte in the XOR of two words to find the
+// differing byte.
+
+use crate::memchr::fallback::USIZE_BYTES;
+
+/// Returns the index of the first position at which `a` and `b` differ. Both
+/// slices must have the same length.
+pub fn mismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+    let (aptr, bptr) = (a.as_ptr(), b.as_ptr());
+    let mut i = 0;
+
+    unsafe {
+        while i + USIZE_BYTES <= a.len() {
+            let x = read_usize(aptr.add(i)) ^ read_usize(bptr.add(i));
+            if x != 0 {
+                return Some(i + first_nonzero_byte(x));
+            }
+            i += USIZE_BYTES;
+        }
+    }
+    (i..a.len()).find(|&i| a[i] != b[i])
+}
+
+/// Returns the index of the last position at which `a` and `b` differ. Both
+/// slices must have the same length.
+pub fn rmismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+    let (aptr, bptr) = (a.as_ptr(), b.as_ptr());
+    let mut i = a.len();
+
+    unsafe {
+        while i >= USIZE_BYTES {
+            let j = i - USIZE_BYTES;
+            let x = read_usize(aptr.add(j)) ^ read_usize(bptr.add(j));
+            if x != 0 {
+                return Some(j + last_nonzero_byte(x));
+            }
+            i = j;
+        }
+    }
+    (0..i).rev().find(|&i| a[i] != b[i])
+}
+
+/// Read a word from the given pointer, which need not be aligned.
+///
+/// # Safety
+///
+/// It must be safe to read `USIZE_BYTES` bytes starting at `ptr`.
+#[inline(always)]
+pub(crate) unsafe fn read_usize(ptr: *const u8) -> usize {
+    (ptr as *const usize).read_unaligned()
+}
+
+/// Returns the index, in memory order, of the first non-zero byte in `x`.
+/// `x` must not be zero.
+#[inline(always)]
+pub(crate) fn first_nonzero_byte(x: usize) -> usize {
+    if cfg!(target_endian = "little") {
+        (x.trailing_zeros() / 8) as usize
+    } else {
+        (x.leading_zeros() / 8) as usize
+    }
+}
+
+/// Returns the index, in memory order, of the last non-zero byte in `x`.
+/// `x` must not be zero.
+#[inline(always)]
+pub(crate) fn last_nonzero_byte(x: usize) -> usize {
+    if cfg!(target_endian = "little") {
+        USIZE_BYTES - 1 - (x.leading_zeros() / 8) as usize
+    } else {
+        USIZE_BYTES - 1 - (x.trailing_zeros() / 8) as usize
+    }
+}
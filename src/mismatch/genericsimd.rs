@@ -0,0 +1,115 @@
+use core::mem::size_of;
+
+use crate::{memmem::vector::Vector, mismatch::fallback};
+
+/// Returns the index of the first position at which `a` and `b` differ. Both
+/// slices must have the same length.
+///
+/// The slices are compared one vector at a time with unaligned loads, and the
+/// final vector overlaps with the previous one when the length isn't a
+/// multiple of the vector size. Since every position in the overlap was
+/// already found to be equal, the first mismatch in the final vector is
+/// always the correct answer.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute.
+/// Therefore, callers must ensure that whatever target feature is being used
+/// supports the vector functions that this function is specialized for. (For
+/// the specific vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn mismatch<V: Vector>(a: &[u8], b: &[u8]) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+    let len = a.len();
+    if len < size_of::<V>() {
+        return fallback::mismatch(a, b);
+    }
+    let (aptr, bptr) = (a.as_ptr(), b.as_ptr());
+    let mut i = 0;
+    while i + size_of::<V>() <= len {
+        if let Some(j) = first_mismatch::<V>(aptr.add(i), bptr.add(i)) {
+            return Some(i + j);
+        }
+        i += size_of::<V>();
+    }
+    if i < len {
+        i = len - size_of::<V>();
+        if let Some(j) = first_mismatch::<V>(aptr.add(i), bptr.add(i)) {
+            return Some(i + j);
+        }
+    }
+    None
+}
+
+/// Returns the index of the last position at which `a` and `b` differ. Both
+/// slices must have the same length.
+///
+/// This is the reverse of `mismatch`, where the final vector overlaps with
+/// the start of the slices.
+///
+/// # Safety
+///
+/// Same as `mismatch`.
+#[inline(always)]
+pub(crate) unsafe fn rmismatch<V: Vector>(
+    a: &[u8],
+    b: &[u8],
+) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+    let len = a.len();
+    if len < size_of::<V>() {
+        return fallback::rmismatch(a, b);
+    }
+    let (aptr, bptr) = (a.as_ptr(), b.as_ptr());
+    let mut i = len;
+    while i >= size_of::<V>() {
+        let j = i - size_of::<V>();
+        if let Some(k) = last_mismatch::<V>(aptr.add(j), bptr.add(j)) {
+            return Some(j + k);
+        }
+        i = j;
+    }
+    if i > 0 {
+        if let Some(k) = last_mismatch::<V>(aptr, bptr) {
+            return Some(k);
+        }
+    }
+    None
+}
+
+/// Returns a mask of the lanes that differ between the vectors at `a` and
+/// `b`.
+#[inline(always)]
+unsafe fn mismatches<V: Vector>(a: *const u8, b: *const u8) -> u32 {
+    let eq = V::load_unaligned(a).cmpeq(V::load_unaligned(b)).movemask();
+    let all =
+        if size_of::<V>() >= 32 { !0 } else { (1 << size_of::<V>()) - 1 };
+    !eq & all
+}
+
+#[inline(always)]
+unsafe fn first_mismatch<V: Vector>(
+    a: *const u8,
+    b: *const u8,
+) -> Option<usize> {
+    let mask = mismatches::<V>(a, b);
+    if mask == 0 {
+        None
+    } else {
+        Some(mask.trailing_zeros() as usize)
+    }
+}
+
+#[inline(always)]
+unsafe fn last_mismatch<V: Vector>(
+    a: *const u8,
+    b: *const u8,
+) -> Option<usize> {
+    let mask = mismatches::<V>(a, b);
+    if mask == 0 {
+        None
+    } else {
+        Some(31 - mask.leading_zeros() as usize)
+    }
+}
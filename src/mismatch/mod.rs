@@ -0,0 +1,278 @@
+// This module provides routines for finding the first or last position at
+// which two byte strings differ. They're useful on their own for binary diffs
+// and delta checks, and are also a common building block for other routines
+// that need to know how much of a candidate agrees with a needle.
+
+pub(crate) mod fallback;
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+mod genericsimd;
+#[cfg(all(
+    not(miri),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    memchr_runtime_simd
+))]
+pub(crate) mod x86;
+
+/// Returns the index of the first byte at which `a` and `b` differ.
+///
+/// If one slice is a prefix of the other but they have different lengths,
+/// then the length of the shorter slice is returned. If the slices are equal,
+/// then `None` is returned. In other words, when this returns `Some(i)`, `i`
+/// is the length of the longest common prefix of `a` and `b`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::mismatch;
+///
+/// assert_eq!(Some(3), mismatch(b"foobar", b"foo"));
+/// assert_eq!(Some(5), mismatch(b"foobar", b"foobaz"));
+/// assert_eq!(None, mismatch(b"foobar", b"foobar"));
+/// ```
+#[inline]
+pub fn mismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    let n = core::cmp::min(a.len(), b.len());
+    match imp(&a[..n], &b[..n]) {
+        Some(i) => Some(i),
+        None if a.len() != b.len() => Some(n),
+        None => None,
+    }
+}
+
+/// Returns the number of bytes from the end at which `a` and `b` first differ,
+/// when compared starting at the end of each slice.
+///
+/// That is, when this returns `Some(i)`, then `a[a.len() - 1 - i]` and
+/// `b[b.len() - 1 - i]` differ, or one of the slices has length `i`. In either
+/// case, `i` is the length of the longest common suffix of `a` and `b`. If the
+/// slices are equal, then `None` is returned.
+///
+/// # Example
+///
+/// ```
+/// use memchr::rmismatch;
+///
+/// assert_eq!(Some(4), rmismatch(b"foo.txt", b".txt"));
+/// assert_eq!(Some(1), rmismatch(b"foo.txt", b"foo.rst"));
+/// assert_eq!(None, rmismatch(b"foo.txt", b"foo.txt"));
+/// ```
+#[inline]
+pub fn rmismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    let n = core::cmp::min(a.len(), b.len());
+    match imp_rev(&a[a.len() - n..], &b[b.len() - n..]) {
+        Some(i) => Some(n - 1 - i),
+        None if a.len() != b.len() => Some(n),
+        None => None,
+    }
+}
+
+/// Returns the length of the longest common prefix of `a` and `b`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::common_prefix_len;
+///
+/// assert_eq!(5, common_prefix_len(b"/usr/bin", b"/usr/lib"));
+/// assert_eq!(0, common_prefix_len(b"abc", b""));
+/// ```
+#[inline]
+pub fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    mismatch(a, b).unwrap_or(a.len())
+}
+
+/// Returns the length of the longest common suffix of `a` and `b`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::common_suffix_len;
+///
+/// assert_eq!(3, common_suffix_len(b"main.rs", b"lib.rs"));
+/// assert_eq!(3, common_suffix_len(b"abc", b"abc"));
+/// ```
+#[inline]
+pub fn common_suffix_len(a: &[u8], b: &[u8]) -> usize {
+    rmismatch(a, b).unwrap_or(a.len())
+}
+
+/// Returns the first position at which two slices of equal length differ.
+#[inline(always)]
+fn imp(a: &[u8], b: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    {
+        (0..a.len()).find(|&i| a[i] != b[i])
+    }
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
+    {
+        x86::mismatch(a, b)
+    }
+    #[cfg(all(
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(miri),
+    ))]
+    {
+        fallback::mismatch(a, b)
+    }
+}
+
+/// Returns the last position at which two slices of equal length differ.
+#[inline(always)]
+fn imp_rev(a: &[u8], b: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    {
+        (0..a.len()).rev().find(|&i| a[i] != b[i])
+    }
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd,
+        not(miri)
+    ))]
+    {
+        x86::rmismatch(a, b)
+    }
+    #[cfg(all(
+        not(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            memchr_runtime_simd
+        )),
+        not(miri),
+    ))]
+    {
+        fallback::rmismatch(a, b)
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::{
+        common_prefix_len, common_suffix_len, fallback, mismatch, rmismatch,
+    };
+
+    fn naive(a: &[u8], b: &[u8]) -> Option<usize> {
+        (0..a.len()).find(|&i| a[i] != b[i])
+    }
+
+    fn naive_rev(a: &[u8], b: &[u8]) -> Option<usize> {
+        (0..a.len()).rev().find(|&i| a[i] != b[i])
+    }
+
+    /// Run the given forward and reverse routines on equal length slices with
+    /// every combination of up to two differing positions, and compare them
+    /// with naive implementations.
+    fn run_tests(
+        fwd: impl Fn(&[u8], &[u8]) -> Option<usize>,
+        rev: impl Fn(&[u8], &[u8]) -> Option<usize>,
+    ) {
+        for len in 0..100 {
+            let a: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut b = a.clone();
+            assert_eq!(None, fwd(&a, &b));
+            assert_eq!(None, rev(&a, &b));
+            for i in 0..len {
+                b[i] ^= 0x80;
+                for j in i..len {
+                    let old = b[j];
+                    b[j] = 0xFF;
+                    for start in 0..core::cmp::min(len, 34) {
+                        let (a, b) = (&a[start..], &b[start..]);
+                        assert_eq!(naive(a, b), fwd(a, b), "{:?} {:?}", a, b);
+                        assert_eq!(
+                            naive_rev(a, b),
+                            rev(a, b),
+                            "{:?} {:?}",
+                            a,
+                            b
+                        );
+                    }
+                    b[j] = old;
+                }
+                b[i] ^= 0x80;
+            }
+        }
+    }
+
+    #[test]
+    fn mismatch_auto() {
+        run_tests(super::imp, super::imp_rev);
+    }
+
+    #[test]
+    fn mismatch_fallback() {
+        run_tests(fallback::mismatch, fallback::rmismatch);
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_simd
+    ))]
+    #[test]
+    fn mismatch_sse2() {
+        run_tests(
+            |a, b| unsafe { super::x86::sse2::mismatch(a, b) },
+            |a, b| unsafe { super::x86::sse2::rmismatch(a, b) },
+        );
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        memchr_runtime_avx
+    ))]
+    #[test]
+    fn mismatch_avx2() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        run_tests(
+            |a, b| unsafe { super::x86::avx::mismatch(a, b) },
+            |a, b| unsafe { super::x86::avx::rmismatch(a, b) },
+        );
+    }
+
+    #[test]
+    fn different_lengths() {
+        assert_eq!(Some(0), mismatch(b"", b"a"));
+        assert_eq!(Some(0), rmismatch(b"a", b""));
+        assert_eq!(None, mismatch(b"", b""));
+        assert_eq!(0, common_prefix_len(b"", b""));
+        assert_eq!(2, common_prefix_len(b"ab", b"abc"));
+        assert_eq!(2, common_suffix_len(b"bc", b"abc"));
+    }
+
+    quickcheck! {
+        fn qc_prefix_suffix(a: Vec<u8>, b: Vec<u8>) -> bool {
+            // Make the slices share most of their bytes, so that long common
+            // prefixes and suffixes are likely.
+            let b: Vec<u8> = a
+                .iter()
+                .zip(b.iter().chain(core::iter::repeat(&0)))
+                .map(|(&x, &y)| if y % 16 == 0 { y } else { x })
+                .chain(b.iter().skip(a.len()).cloned())
+                .collect();
+            let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+            let suffix = a
+                .iter()
+                .rev()
+                .zip(b.iter().rev())
+                .take_while(|(x, y)| x == y)
+                .count();
+            let equal = a == b;
+            common_prefix_len(&a, &b) == prefix
+                && common_suffix_len(&a, &b) == suffix
+                && mismatch(&a, &b) == if equal { None } else { Some(prefix) }
+                && rmismatch(&a, &b) == if equal { None } else { Some(suffix) }
+        }
+    }
+}
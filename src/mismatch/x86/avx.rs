@@ -0,0 +1,35 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{__m128i, __m256i};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::mismatch::genericsimd;
+
+/// An AVX2 accelerated search for the first mismatch.
+///
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn mismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    if a.len() < 32 {
+        genericsimd::mismatch::<__m128i>(a, b)
+    } else {
+        genericsimd::mismatch::<__m256i>(a, b)
+    }
+}
+
+/// An AVX2 accelerated search for the last mismatch.
+///
+/// # Safety
+///
+/// Same as `mismatch`.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn rmismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    if a.len() < 32 {
+        genericsimd::rmismatch::<__m128i>(a, b)
+    } else {
+        genericsimd::rmismatch::<__m256i>(a, b)
+    }
+}
@@ -0,0 +1,17 @@
+#[cfg(not(all(target_feature = "avx2", memchr_runtime_avx)))]
+use crate::mismatch::fallback;
+
+// We only use AVX when we can detect at runtime whether it's available, which
+// requires std or the cpuid feature, or when it's enabled at compile time.
+#[cfg(memchr_avx2)]
+pub(crate) mod avx;
+#[cfg_attr(all(target_feature = "avx2", memchr_runtime_avx), allow(dead_code))]
+pub(crate) mod sse2;
+
+unsafe_ifunc! {
+    MISMATCH, mismatch_detect,
+    fn mismatch(a: &[u8], b: &[u8]) -> Option<usize>;
+
+    RMISMATCH, rmismatch_detect,
+    fn rmismatch(a: &[u8], b: &[u8]) -> Option<usize>;
+}
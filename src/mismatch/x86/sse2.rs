@@ -0,0 +1,28 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::__m128i;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::__m128i;
+
+use crate::mismatch::genericsimd;
+
+/// An SSE2 accelerated search for the first mismatch.
+///
+/// # Safety
+///
+/// Callers must ensure that the sse2 CPU feature is enabled in the current
+/// environment. This feature should be enabled in all x86_64 targets, and
+/// this module is only compiled for x86 targets that enable it.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn mismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    genericsimd::mismatch::<__m128i>(a, b)
+}
+
+/// An SSE2 accelerated search for the last mismatch.
+///
+/// # Safety
+///
+/// Same as `mismatch`.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn rmismatch(a: &[u8], b: &[u8]) -> Option<usize> {
+    genericsimd::rmismatch::<__m128i>(a, b)
+}
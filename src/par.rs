@@ -0,0 +1,480 @@
+/*!
+This module provides parallel searches over large haystacks using Rayon.
+
+Each routine splits the haystack into chunks and searches them on the Rayon
+thread pool. Substring searches extend each chunk by `needle.len() - 1`
+bytes into the next one, so that matches straddling a seam are found by the
+chunk in which they start. Results are always reported in order, and are
+exactly the same as the corresponding sequential routines report.
+
+For substring search, this means non-overlapping leftmost semantics are
+preserved: each chunk searches independently as if no match precedes it, and
+then the chunks are stitched together in order. When a match from one chunk
+extends past the seam into the next, the next chunk's matches are re-derived
+sequentially from the end of that match until they line up with the matches
+the chunk found on its own, which almost always happens within one or two
+matches. (Needles that overlap themselves in highly periodic haystacks, such
+as `aa` in `aaaa...`, may never line up. Those chunks then fall back to a
+sequential search.)
+
+Haystacks smaller than a few chunks are searched sequentially.
+
+[`find_iter`] reports matches as they are found, which suits haystacks too
+big to collect all of their matches at once, such as a large memory mapped
+file. It searches a bounded window of chunks at a time in parallel, and only
+buffers the matches of that window. [`find_all`] collects every match into a
+`Vec` instead, which is a little faster when that fits in memory.
+
+This is only available when the `rayon` feature is enabled.
+
+# Example
+
+```
+use memchr::{memmem::Finder, par};
+
+let haystack = "foo bar ".repeat(100_000);
+let finder = Finder::new("bar");
+assert_eq!(100_000, par::count(&finder, haystack.as_bytes()));
+assert_eq!(100_000, par::memchr_count(b'f', haystack.as_bytes()));
+
+let expected: Vec<usize> = finder.find_iter(haystack.as_bytes()).collect();
+assert_eq!(expected, par::find_all(&finder, haystack.as_bytes()));
+assert!(par::find_iter(&finder, haystack.as_bytes()).eq(expected));
+```
+*/
+
+use rayon::prelude::*;
+
+use crate::{
+    bitmask,
+    memmem::{self, Finder},
+};
+
+/// The smallest chunk of haystack that is searched by a single task.
+const MIN_CHUNK_LEN: usize = 1 << 16;
+
+/// The number of chunks created per thread, to balance the load when some
+/// chunks take longer to search than others.
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// When counting, the number of matches at the start of each chunk that are
+/// kept for lining up the chunk with the previous one.
+const COUNT_HEAD_LEN: usize = 64;
+
+/// The largest chunk of haystack that is searched by a single task of
+/// [`find_iter`], which bounds the number of matches it buffers.
+const MAX_ITER_CHUNK_LEN: usize = 1 << 22;
+
+/// Returns the number of occurrences of `n1` in `haystack`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::par;
+///
+/// assert_eq!(2, par::memchr_count(b'\n', b"foo\nbar\n"));
+/// ```
+pub fn memchr_count(n1: u8, haystack: &[u8]) -> usize {
+    count_bytes(&[n1], haystack)
+}
+
+/// Returns the number of occurrences of either `n1` or `n2` in `haystack`.
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    count_bytes(&[n1, n2], haystack)
+}
+
+/// Returns the number of occurrences of any of `n1`, `n2` or `n3` in
+/// `haystack`.
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    count_bytes(&[n1, n2, n3], haystack)
+}
+
+/// Returns the starting offsets of all non-overlapping occurrences of the
+/// finder's needle in `haystack`, in order.
+///
+/// This returns the same offsets as `finder.find_iter(haystack)`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{memmem::Finder, par};
+///
+/// let finder = Finder::new("aa");
+/// assert_eq!(vec![0, 2], par::find_all(&finder, b"aaaaa"));
+/// ```
+pub fn find_all(finder: &Finder<'_>, haystack: &[u8]) -> Vec<usize> {
+    let mut all = vec![];
+    search(finder, haystack, Some(&mut all));
+    all
+}
+
+/// Returns an iterator over the starting offsets of all non-overlapping
+/// occurrences of the finder's needle in `haystack`, in order.
+///
+/// This yields the same offsets as `finder.find_iter(haystack)`. Unlike
+/// [`find_all`], this doesn't wait for the whole haystack to be searched
+/// before yielding the first offset.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{memmem::Finder, par};
+///
+/// let finder = Finder::new("aa");
+/// let mut it = par::find_iter(&finder, b"aaaaa");
+/// assert_eq!(Some(0), it.next());
+/// assert_eq!(Some(2), it.next());
+/// assert_eq!(None, it.next());
+/// ```
+pub fn find_iter<'h, 'n>(
+    finder: &'n Finder<'_>,
+    haystack: &'h [u8],
+) -> FindIter<'h, 'n> {
+    FindIter::new(finder.as_ref(), haystack)
+}
+
+/// Returns the number of non-overlapping occurrences of the finder's needle
+/// in `haystack`.
+///
+/// This returns the same count as `finder.find_iter(haystack).count()`.
+pub fn count(finder: &Finder<'_>, haystack: &[u8]) -> usize {
+    search(finder, haystack, None)
+}
+
+/// An iterator over non-overlapping substring matches, searched in parallel.
+///
+/// Matches are reported by the offset at which they begin, in order.
+///
+/// This is created by [`find_iter`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FindIter<'h, 'n> {
+    finder: Finder<'n>,
+    haystack: &'h [u8],
+    /// The iterator used when the haystack is searched sequentially.
+    seq: Option<memmem::FindIter<'h, 'n>>,
+    /// The length of each chunk, when searched in parallel.
+    chunk_len: usize,
+    /// The offset at which the next window of chunks starts.
+    pos: usize,
+    /// The offset at which the next match may start.
+    free: usize,
+    /// The matches of the current window that haven't been yielded yet.
+    matches: std::vec::IntoIter<usize>,
+}
+
+impl<'h, 'n> FindIter<'h, 'n> {
+    fn new(finder: Finder<'n>, haystack: &'h [u8]) -> FindIter<'h, 'n> {
+        let nlen = finder.needle().len();
+        let len = match chunk_len(haystack.len(), nlen) {
+            Some(len) if nlen > 0 => {
+                core::cmp::max(core::cmp::min(len, MAX_ITER_CHUNK_LEN), nlen)
+            }
+            _ => 0,
+        };
+        let seq = if len == 0 {
+            Some(memmem::FindIter::new(haystack, finder.clone()))
+        } else {
+            None
+        };
+        FindIter {
+            finder,
+            haystack,
+            seq,
+            chunk_len: len,
+            pos: 0,
+            free: 0,
+            matches: vec![].into_iter(),
+        }
+    }
+
+    /// Search the next window of chunks in parallel, and buffer its matches.
+    fn search_window(&mut self) {
+        let (finder, haystack, len) =
+            (&self.finder, self.haystack, self.chunk_len);
+        let window = len * rayon::current_num_threads() * CHUNKS_PER_THREAD;
+        let end =
+            core::cmp::min(haystack.len(), self.pos.saturating_add(window));
+        let starts: Vec<usize> = (self.pos..end).step_by(len).collect();
+        let chunks: Vec<Chunk> = starts
+            .par_iter()
+            .map(|&start| {
+                let chunk_end = core::cmp::min(end, start + len);
+                search_chunk(finder, haystack, start, chunk_end, usize::MAX)
+            })
+            .collect();
+        let mut matches = vec![];
+        for chunk in &chunks {
+            stitch(
+                finder,
+                haystack,
+                chunk,
+                &mut self.free,
+                Some(&mut matches),
+            );
+        }
+        self.pos = end;
+        self.matches = matches.into_iter();
+    }
+}
+
+impl<'h, 'n> Iterator for FindIter<'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if let Some(ref mut it) = self.seq {
+            return it.next();
+        }
+        loop {
+            if let Some(i) = self.matches.next() {
+                return Some(i);
+            }
+            if self.pos >= self.haystack.len() {
+                return None;
+            }
+            self.search_window();
+        }
+    }
+}
+
+/// Returns the length of each chunk that the given haystack should be split
+/// into, or `None` if it should be searched sequentially.
+fn chunk_len(haystack_len: usize, needle_len: usize) -> Option<usize> {
+    let chunks = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+    let len = core::cmp::max(haystack_len / chunks, MIN_CHUNK_LEN);
+    let len = core::cmp::max(len, needle_len);
+    if haystack_len < 2 * len {
+        None
+    } else {
+        Some(len)
+    }
+}
+
+fn count_bytes(bytes: &[u8], haystack: &[u8]) -> usize {
+    match chunk_len(haystack.len(), 1) {
+        None => bitmask::count_bytes(bytes, haystack),
+        Some(len) => haystack
+            .par_chunks(len)
+            .map(|c| bitmask::count_bytes(bytes, c))
+            .sum(),
+    }
+}
+
+/// The matches found in a single chunk, as if no match preceded it.
+struct Chunk {
+    /// The offset at which this chunk starts.
+    start: usize,
+    /// The offset at which this chunk ends. Only matches starting before
+    /// this are reported by this chunk.
+    end: usize,
+    /// The total number of matches in this chunk.
+    count: usize,
+    /// The offsets of the first matches in this chunk. When collecting
+    /// matches, these are all of them.
+    head: Vec<usize>,
+    /// The end offset of the last match in this chunk.
+    last_end: Option<usize>,
+}
+
+/// Search the given haystack, returning the number of matches and pushing
+/// them to `all` if provided.
+fn search(
+    finder: &Finder<'_>,
+    haystack: &[u8],
+    mut all: Option<&mut Vec<usize>>,
+) -> usize {
+    let len = match chunk_len(haystack.len(), finder.needle().len()) {
+        Some(len) if !finder.needle().is_empty() => len,
+        _ => {
+            let mut count = 0;
+            for i in finder.find_iter(haystack) {
+                count += 1;
+                if let Some(ref mut all) = all {
+                    all.push(i);
+                }
+            }
+            return count;
+        }
+    };
+    let keep = if all.is_some() { usize::MAX } else { COUNT_HEAD_LEN };
+    let starts: Vec<usize> = (0..haystack.len()).step_by(len).collect();
+    let chunks: Vec<Chunk> = starts
+        .par_iter()
+        .map(|&start| {
+            let end = core::cmp::min(haystack.len(), start + len);
+            search_chunk(finder, haystack, start, end, keep)
+        })
+        .collect();
+
+    let mut count = 0;
+    // The offset at which the next match may start.
+    let mut free = 0;
+    for chunk in &chunks {
+        count +=
+            stitch(finder, haystack, chunk, &mut free, all.as_deref_mut());
+    }
+    count
+}
+
+/// Stitch the matches of `chunk` onto the matches before it, where `free` is
+/// the offset at which the next match may start. This returns the number of
+/// matches in the chunk, pushing them to `all` if provided, and updates
+/// `free`.
+fn stitch(
+    finder: &Finder<'_>,
+    haystack: &[u8],
+    chunk: &Chunk,
+    free: &mut usize,
+    mut all: Option<&mut Vec<usize>>,
+) -> usize {
+    if *free <= chunk.start {
+        if let Some(ref mut all) = all {
+            all.extend_from_slice(&chunk.head);
+        }
+        if let Some(end) = chunk.last_end {
+            *free = end;
+        }
+        return chunk.count;
+    }
+    // The last match of the previous chunk extends into this one, so the
+    // matches at the start of this chunk may be different. Search
+    // sequentially until we find a match that this chunk also found, after
+    // which the rest of this chunk's matches are correct.
+    let nlen = finder.needle().len();
+    let limit = core::cmp::min(haystack.len(), chunk.end + nlen - 1);
+    let mut count = 0;
+    while *free < limit {
+        let p = match finder.find(&haystack[*free..limit]) {
+            None => break,
+            Some(i) => *free + i,
+        };
+        if let Ok(i) = chunk.head.binary_search(&p) {
+            if let Some(ref mut all) = all {
+                all.extend_from_slice(&chunk.head[i..]);
+            }
+            *free = chunk.last_end.unwrap();
+            return count + chunk.count - i;
+        }
+        count += 1;
+        if let Some(ref mut all) = all {
+            all.push(p);
+        }
+        *free = p + nlen;
+    }
+    count
+}
+
+/// Search for all matches starting in `start..end`, as if no match preceded
+/// `start`.
+fn search_chunk(
+    finder: &Finder<'_>,
+    haystack: &[u8],
+    start: usize,
+    end: usize,
+    keep: usize,
+) -> Chunk {
+    let nlen = finder.needle().len();
+    let limit = core::cmp::min(haystack.len(), end + nlen - 1);
+    let mut chunk =
+        Chunk { start, end, count: 0, head: vec![], last_end: None };
+    for i in finder.find_iter(&haystack[start..limit]) {
+        let i = start + i;
+        if i >= end {
+            break;
+        }
+        chunk.count += 1;
+        if chunk.head.len() < keep {
+            chunk.head.push(i);
+        }
+        chunk.last_end = Some(i + nlen);
+    }
+    chunk
+}
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::{
+        count, find_all, find_iter, memchr2_count, memchr_count, search,
+    };
+    use crate::memmem::Finder;
+
+    #[test]
+    fn seams() {
+        // A haystack big enough to be split into several chunks, where
+        // matches of a self-overlapping needle straddle every seam.
+        let haystack = vec![b'a'; 1_000_003];
+        for &needle in &["a", "aa", "aaa", "aaaaaaa"] {
+            let finder = Finder::new(needle);
+            let expected: Vec<usize> = finder.find_iter(&haystack).collect();
+            assert_eq!(expected, find_all(&finder, &haystack), "{}", needle);
+            assert!(find_iter(&finder, &haystack).eq(expected.clone()));
+            assert_eq!(
+                expected.len(),
+                count(&finder, &haystack),
+                "{}",
+                needle
+            );
+        }
+    }
+
+    #[test]
+    fn seams_windows() {
+        // With a single thread, a haystack this big is searched by find_iter
+        // in more than one window, with a match straddling each seam.
+        let haystack = vec![b'a'; 17 << 20];
+        let pool =
+            rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        pool.install(|| {
+            let finder = Finder::new("aaa");
+            let expected = finder.find_iter(&haystack);
+            assert!(find_iter(&finder, &haystack).eq(expected));
+        });
+    }
+
+    #[test]
+    fn seams_mixed() {
+        let mut haystack = vec![];
+        let mut i = 0u64;
+        while haystack.len() < 2_000_000 {
+            // A simple LCG to pick between a few short runs.
+            i = i.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let run = [&b"abab"[..], b"ba", b"aab", b"x"][(i >> 62) as usize];
+            haystack.extend_from_slice(run);
+        }
+        for &needle in &["ab", "aba", "abab", "bab", "xab"] {
+            let finder = Finder::new(needle);
+            let expected: Vec<usize> = finder.find_iter(&haystack).collect();
+            let mut all = vec![];
+            let n = search(&finder, &haystack, Some(&mut all));
+            assert_eq!(expected, all, "{}", needle);
+            assert!(find_iter(&finder, &haystack).eq(expected.clone()));
+            assert_eq!(expected.len(), n, "{}", needle);
+            assert_eq!(
+                expected.len(),
+                count(&finder, &haystack),
+                "{}",
+                needle
+            );
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        let haystack: Vec<u8> =
+            (0..3_000_000).map(|i| (i % 7) as u8).collect();
+        let expected = crate::memchr_iter(3, &haystack).count();
+        assert_eq!(expected, memchr_count(3, &haystack));
+        let expected = crate::memchr2_iter(3, 5, &haystack).count();
+        assert_eq!(expected, memchr2_count(3, 5, &haystack));
+    }
+
+    #[test]
+    fn small() {
+        let finder = Finder::new("");
+        assert_eq!(vec![0, 1, 2], find_all(&finder, b"ab"));
+        assert!(find_iter(&finder, b"ab").eq(vec![0, 1, 2]));
+        assert_eq!(0, memchr_count(b'a', b""));
+    }
+}
@@ -1,5 +1,7 @@
 use quickcheck::quickcheck;
 
+#[cfg(all(not(miri), feature = "portable-simd"))]
+use crate::memchr::portable;
 use crate::{
     memchr,
     memchr::{fallback, naive},
@@ -21,6 +23,14 @@ fn memchr1_fallback_find() {
     }
 }
 
+#[test]
+#[cfg(all(not(miri), feature = "portable-simd"))]
+fn memchr1_portable_find() {
+    for test in memchr_tests() {
+        test.one(false, portable::memchr);
+    }
+}
+
 #[test]
 fn memchr2_find() {
     for test in memchr_tests() {
@@ -35,6 +45,14 @@ fn memchr2_fallback_find() {
     }
 }
 
+#[test]
+#[cfg(all(not(miri), feature = "portable-simd"))]
+fn memchr2_portable_find() {
+    for test in memchr_tests() {
+        test.two(false, portable::memchr2);
+    }
+}
+
 #[test]
 fn memchr3_find() {
     for test in memchr_tests() {
@@ -49,6 +67,14 @@ fn memchr3_fallback_find() {
     }
 }
 
+#[test]
+#[cfg(all(not(miri), feature = "portable-simd"))]
+fn memchr3_portable_find() {
+    for test in memchr_tests() {
+        test.three(false, portable::memchr3);
+    }
+}
+
 #[test]
 fn memrchr1_find() {
     for test in memchr_tests() {
@@ -63,6 +89,14 @@ fn memrchr1_fallback_find() {
     }
 }
 
+#[test]
+#[cfg(all(not(miri), feature = "portable-simd"))]
+fn memrchr1_portable_find() {
+    for test in memchr_tests() {
+        test.one(true, portable::memrchr);
+    }
+}
+
 #[test]
 fn memrchr2_find() {
     for test in memchr_tests() {
@@ -77,6 +111,14 @@ fn memrchr2_fallback_find() {
     }
 }
 
+#[test]
+#[cfg(all(not(miri), feature = "portable-simd"))]
+fn memrchr2_portable_find() {
+    for test in memchr_tests() {
+        test.two(true, portable::memrchr2);
+    }
+}
+
 #[test]
 fn memrchr3_find() {
     for test in memchr_tests() {
@@ -91,6 +133,14 @@ fn memrchr3_fallback_find() {
     }
 }
 
+#[test]
+#[cfg(all(not(miri), feature = "portable-simd"))]
+fn memrchr3_portable_find() {
+    for test in memchr_tests() {
+        test.three(true, portable::memrchr3);
+    }
+}
+
 quickcheck! {
     fn qc_memchr1_matches_naive(n1: u8, corpus: Vec<u8>) -> bool {
         memchr(n1, &corpus) == naive::memchr(n1, &corpus)
@@ -132,3 +182,60 @@ quickcheck! {
         memrchr3(n1, n2, n3, &corpus) == naive::memrchr3(n1, n2, n3, &corpus)
     }
 }
+
+#[cfg(all(not(miri), feature = "portable-simd"))]
+quickcheck! {
+    fn qc_memchr1_portable_matches_naive(n1: u8, corpus: Vec<u8>) -> bool {
+        portable::memchr(n1, &corpus) == naive::memchr(n1, &corpus)
+    }
+}
+
+#[cfg(all(not(miri), feature = "portable-simd"))]
+quickcheck! {
+    fn qc_memchr2_portable_matches_naive(
+        n1: u8, n2: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memchr2(n1, n2, &corpus) == naive::memchr2(n1, n2, &corpus)
+    }
+}
+
+#[cfg(all(not(miri), feature = "portable-simd"))]
+quickcheck! {
+    fn qc_memchr3_portable_matches_naive(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memchr3(n1, n2, n3, &corpus)
+            == naive::memchr3(n1, n2, n3, &corpus)
+    }
+}
+
+#[cfg(all(not(miri), feature = "portable-simd"))]
+quickcheck! {
+    fn qc_memrchr1_portable_matches_naive(n1: u8, corpus: Vec<u8>) -> bool {
+        portable::memrchr(n1, &corpus) == naive::memrchr(n1, &corpus)
+    }
+}
+
+#[cfg(all(not(miri), feature = "portable-simd"))]
+quickcheck! {
+    fn qc_memrchr2_portable_matches_naive(
+        n1: u8, n2: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memrchr2(n1, n2, &corpus)
+            == naive::memrchr2(n1, n2, &corpus)
+    }
+}
+
+#[cfg(all(not(miri), feature = "portable-simd"))]
+quickcheck! {
+    fn qc_memrchr3_portable_matches_naive(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memrchr3(n1, n2, n3, &corpus)
+            == naive::memrchr3(n1, n2, n3, &corpus)
+    }
+}
@@ -0,0 +1,592 @@
+/*!
+This module provides `tr`-style byte transformations: replacing, deleting and
+squeezing bytes, along with converting `\r\n` line endings to `\n`.
+
+Each of these operations is dominated by finding the next byte that needs to
+change. The routines here use `memchr`, `memchr2`, `memchr3` and
+`memchr_pair` to skip over the stretches of a haystack that are left
+untouched, and only do work at the positions that actually change. Deleting
+and squeezing bytes in place moves the untouched stretches with a single
+`copy_within` each.
+
+Every operation comes in three forms:
+
+* An in-place form on `&mut [u8]`. Since a slice cannot shrink, the routines
+  that remove bytes return the new length of the data instead, and leave
+  unspecified bytes after it.
+* An in-place form on `Vec<u8>`, which truncates the vector to its new length.
+  (Only for routines that remove bytes, and only when the `std` feature is
+  enabled.)
+* A copying form that appends the transformed bytes to a `Vec<u8>`. (Only when
+  the `std` feature is enabled.)
+
+Routines that accept a set of bytes accept at most three of them, mirroring
+`memchr3`. An empty set never matches.
+
+# Example: normalizing whitespace
+
+```
+use memchr::transform;
+
+# #[cfg(feature = "std")] {
+let mut data = b"a\tb\r\nc\t\td\r\n".to_vec();
+transform::replace_byte(b'\t', b' ', &mut data);
+transform::crlf_to_lf_vec(&mut data);
+transform::squeeze_byte_vec(b' ', &mut data);
+assert_eq!(&data[..], &b"a b\nc d\n"[..]);
+# }
+```
+*/
+
+use crate::{memchr, memchr2, memchr3, memchr_pair};
+
+/// Search for the first occurrence of any of the given bytes.
+///
+/// # Panics
+///
+/// When more than three bytes are given.
+#[inline(always)]
+fn find_any(needles: &[u8], haystack: &[u8]) -> Option<usize> {
+    match *needles {
+        [] => None,
+        [n1] => memchr(n1, haystack),
+        [n1, n2] => memchr2(n1, n2, haystack),
+        [n1, n2, n3] => memchr3(n1, n2, n3, haystack),
+        _ => panic!(
+            "at most 3 bytes can be given, but {} were given",
+            needles.len()
+        ),
+    }
+}
+
+/// Replace every occurrence of `from` with `to` in `haystack`.
+///
+/// This returns the number of bytes replaced.
+///
+/// # Example
+///
+/// ```
+/// use memchr::transform::replace_byte;
+///
+/// let mut data = *b"a\tb\tc";
+/// assert_eq!(2, replace_byte(b'\t', b' ', &mut data));
+/// assert_eq!(b"a b c", &data);
+/// ```
+pub fn replace_byte(from: u8, to: u8, haystack: &mut [u8]) -> usize {
+    let mut count = 0;
+    let mut at = 0;
+    while let Some(i) = memchr(from, &haystack[at..]) {
+        haystack[at + i] = to;
+        at += i + 1;
+        count += 1;
+    }
+    count
+}
+
+/// Replace bytes in `haystack` according to the given `(from, to)` pairs.
+///
+/// Every byte equal to one of the `from` bytes is replaced by its
+/// corresponding `to` byte. Each byte is replaced at most once, so pairs like
+/// `[(b'a', b'b'), (b'b', b'a')]` swap bytes. If the same `from` byte occurs
+/// in more than one pair, then the first pair wins.
+///
+/// This returns the number of bytes replaced.
+///
+/// # Panics
+///
+/// When more than three pairs are given.
+///
+/// # Example
+///
+/// ```
+/// use memchr::transform::replace_bytes;
+///
+/// let mut data = *b"a\tb\rc";
+/// assert_eq!(2, replace_bytes(&[(b'\t', b' '), (b'\r', b'\n')], &mut data));
+/// assert_eq!(b"a b\nc", &data);
+/// ```
+pub fn replace_bytes(pairs: &[(u8, u8)], haystack: &mut [u8]) -> usize {
+    let (from, len) = from_bytes(pairs);
+    let from = &from[..len];
+    let mut count = 0;
+    let mut at = 0;
+    while let Some(i) = find_any(from, &haystack[at..]) {
+        let pos = at + i;
+        haystack[pos] = translate(pairs, haystack[pos]);
+        at = pos + 1;
+        count += 1;
+    }
+    count
+}
+
+/// Delete every occurrence of any of the given bytes from `haystack`.
+///
+/// The bytes that remain are moved to the front of `haystack`, in order, and
+/// the number of bytes remaining is returned. The contents of `haystack`
+/// after the returned length are unspecified.
+///
+/// Every occurrence is deleted, regardless of the bytes around it. In
+/// particular, deleting `\r` removes lone `\r` bytes too, and not just those
+/// that are part of a `\r\n` line ending. Use [`crlf_to_lf`] to convert line
+/// endings instead.
+///
+/// # Panics
+///
+/// When more than three bytes are given.
+///
+/// # Example
+///
+/// ```
+/// use memchr::transform::delete_bytes;
+///
+/// let mut data = *b"a\r\nb\0\r\n";
+/// let len = delete_bytes(b"\r\0", &mut data);
+/// assert_eq!(b"a\nb\n", &data[..len]);
+/// ```
+pub fn delete_bytes(needles: &[u8], haystack: &mut [u8]) -> usize {
+    let mut write = match find_any(needles, haystack) {
+        None => return haystack.len(),
+        Some(i) => i,
+    };
+    let mut read = write + 1;
+    while read < haystack.len() {
+        let end = find_any(needles, &haystack[read..])
+            .map_or(haystack.len(), |i| read + i);
+        haystack.copy_within(read..end, write);
+        write += end - read;
+        read = end + 1;
+    }
+    write
+}
+
+/// Replace every run of consecutive `byte` in `haystack` with a single
+/// `byte`.
+///
+/// The bytes that remain are moved to the front of `haystack`, in order, and
+/// the number of bytes remaining is returned. The contents of `haystack`
+/// after the returned length are unspecified.
+///
+/// # Example
+///
+/// ```
+/// use memchr::transform::squeeze_byte;
+///
+/// let mut data = *b"a   b  c d";
+/// let len = squeeze_byte(b' ', &mut data);
+/// assert_eq!(b"a b c d", &data[..len]);
+/// ```
+pub fn squeeze_byte(byte: u8, haystack: &mut [u8]) -> usize {
+    let len = haystack.len();
+    let (mut read, mut write) = (0, 0);
+    while let Some(i) = memchr(byte, &haystack[read..]) {
+        // Keep everything up to and including the first byte of the run.
+        let keep = read + i + 1;
+        if read != write {
+            haystack.copy_within(read..keep, write);
+        }
+        write += keep - read;
+        read = keep;
+        while read < len && haystack[read] == byte {
+            read += 1;
+        }
+    }
+    if read != write {
+        haystack.copy_within(read..len, write);
+    }
+    write + (len - read)
+}
+
+/// Replace every `\r\n` in `haystack` with `\n`.
+///
+/// Unlike deleting `\r` with [`delete_bytes`], a `\r` that isn't immediately
+/// followed by `\n` is left as is.
+///
+/// The bytes that remain are moved to the front of `haystack`, in order, and
+/// the number of bytes remaining is returned. The contents of `haystack`
+/// after the returned length are unspecified.
+///
+/// # Example
+///
+/// ```
+/// use memchr::transform::crlf_to_lf;
+///
+/// let mut data = *b"a\r\nb\rc\r\n";
+/// let len = crlf_to_lf(&mut data);
+/// assert_eq!(b"a\nb\rc\n", &data[..len]);
+/// ```
+pub fn crlf_to_lf(haystack: &mut [u8]) -> usize {
+    let mut write = match memchr_pair(b'\r', b'\n', 1, haystack) {
+        None => return haystack.len(),
+        Some(i) => i,
+    };
+    // Skip the `\r`, but keep the `\n` after it.
+    let mut read = write + 1;
+    while read < haystack.len() {
+        let end = memchr_pair(b'\r', b'\n', 1, &haystack[read..])
+            .map_or(haystack.len(), |i| read + i);
+        haystack.copy_within(read..end, write);
+        write += end - read;
+        read = end + 1;
+    }
+    write
+}
+
+/// Like [`delete_bytes`], but truncates the given vector to the remaining
+/// bytes.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Panics
+///
+/// When more than three bytes are given.
+#[cfg(feature = "std")]
+pub fn delete_bytes_vec(needles: &[u8], haystack: &mut Vec<u8>) {
+    let len = delete_bytes(needles, haystack);
+    haystack.truncate(len);
+}
+
+/// Like [`squeeze_byte`], but truncates the given vector to the remaining
+/// bytes.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn squeeze_byte_vec(byte: u8, haystack: &mut Vec<u8>) {
+    let len = squeeze_byte(byte, haystack);
+    haystack.truncate(len);
+}
+
+/// Like [`crlf_to_lf`], but truncates the given vector to the remaining
+/// bytes.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn crlf_to_lf_vec(haystack: &mut Vec<u8>) {
+    let len = crlf_to_lf(haystack);
+    haystack.truncate(len);
+}
+
+/// Like [`replace_bytes`], but appends the result to `dst` instead of
+/// modifying `src`.
+///
+/// This returns the number of bytes replaced.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Panics
+///
+/// When more than three pairs are given.
+#[cfg(feature = "std")]
+pub fn replace_bytes_into(
+    pairs: &[(u8, u8)],
+    src: &[u8],
+    dst: &mut Vec<u8>,
+) -> usize {
+    let (from, len) = from_bytes(pairs);
+    let from = &from[..len];
+    dst.reserve(src.len());
+    let mut count = 0;
+    let mut src = src;
+    while let Some(i) = find_any(from, src) {
+        dst.extend_from_slice(&src[..i]);
+        dst.push(translate(pairs, src[i]));
+        src = &src[i + 1..];
+        count += 1;
+    }
+    dst.extend_from_slice(src);
+    count
+}
+
+/// Like [`delete_bytes`], but appends the result to `dst` instead of
+/// modifying `src`.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Panics
+///
+/// When more than three bytes are given.
+#[cfg(feature = "std")]
+pub fn delete_bytes_into(needles: &[u8], src: &[u8], dst: &mut Vec<u8>) {
+    dst.reserve(src.len());
+    let mut src = src;
+    while let Some(i) = find_any(needles, src) {
+        dst.extend_from_slice(&src[..i]);
+        src = &src[i + 1..];
+    }
+    dst.extend_from_slice(src);
+}
+
+/// Like [`squeeze_byte`], but appends the result to `dst` instead of
+/// modifying `src`.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn squeeze_byte_into(byte: u8, src: &[u8], dst: &mut Vec<u8>) {
+    dst.reserve(src.len());
+    let mut src = src;
+    while let Some(i) = memchr(byte, src) {
+        dst.extend_from_slice(&src[..i + 1]);
+        let mut next = i + 1;
+        while next < src.len() && src[next] == byte {
+            next += 1;
+        }
+        src = &src[next..];
+    }
+    dst.extend_from_slice(src);
+}
+
+/// Like [`crlf_to_lf`], but appends the result to `dst` instead of modifying
+/// `src`.
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+pub fn crlf_to_lf_into(src: &[u8], dst: &mut Vec<u8>) {
+    dst.reserve(src.len());
+    let mut src = src;
+    while let Some(i) = memchr_pair(b'\r', b'\n', 1, src) {
+        dst.extend_from_slice(&src[..i]);
+        src = &src[i + 1..];
+    }
+    dst.extend_from_slice(src);
+}
+
+/// Collect the `from` bytes of the given pairs into a fixed size array,
+/// returning the array and the number of valid bytes in it.
+///
+/// # Panics
+///
+/// When more than three pairs are given.
+#[inline(always)]
+fn from_bytes(pairs: &[(u8, u8)]) -> ([u8; 3], usize) {
+    assert!(
+        pairs.len() <= 3,
+        "at most 3 pairs can be given, but {} were given",
+        pairs.len()
+    );
+    let mut from = [0; 3];
+    for (f, &(b, _)) in from.iter_mut().zip(pairs) {
+        *f = b;
+    }
+    (from, pairs.len())
+}
+
+/// Return the replacement for `byte` from the first pair whose `from` byte
+/// matches. `byte` must match at least one pair.
+#[inline(always)]
+fn translate(pairs: &[(u8, u8)], byte: u8) -> u8 {
+    for &(from, to) in pairs {
+        if from == byte {
+            return to;
+        }
+    }
+    unreachable!("byte {:?} is not in any pair", byte)
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::*;
+
+    fn naive_replace(pairs: &[(u8, u8)], haystack: &[u8]) -> Vec<u8> {
+        haystack
+            .iter()
+            .map(|&b| match pairs.iter().find(|&&(from, _)| from == b) {
+                None => b,
+                Some(&(_, to)) => to,
+            })
+            .collect()
+    }
+
+    fn naive_delete(needles: &[u8], haystack: &[u8]) -> Vec<u8> {
+        haystack.iter().cloned().filter(|b| !needles.contains(b)).collect()
+    }
+
+    fn naive_squeeze(byte: u8, haystack: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = vec![];
+        for &b in haystack {
+            if b == byte && out.last() == Some(&byte) {
+                continue;
+            }
+            out.push(b);
+        }
+        out
+    }
+
+    fn naive_crlf_to_lf(haystack: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        for (i, &b) in haystack.iter().enumerate() {
+            if b == b'\r' && haystack.get(i + 1) == Some(&b'\n') {
+                continue;
+            }
+            out.push(b);
+        }
+        out
+    }
+
+    /// Shrink arbitrary test inputs to a small alphabet so that matches are
+    /// likely to occur.
+    fn small(bytes: &[u8]) -> Vec<u8> {
+        bytes.iter().map(|&b| b % 4).collect()
+    }
+
+    fn check_replace(pairs: &[(u8, u8)], haystack: &[u8]) -> bool {
+        let expected = naive_replace(pairs, haystack);
+        let changed = haystack.iter().zip(&expected).filter(|(a, b)| a != b);
+
+        let mut got = haystack.to_vec();
+        let count = replace_bytes(pairs, &mut got);
+        assert_eq!(expected, got);
+        assert!(count >= changed.count());
+
+        let mut copied = vec![b'x'];
+        assert_eq!(count, replace_bytes_into(pairs, haystack, &mut copied));
+        assert_eq!(b'x', copied[0]);
+        assert_eq!(expected, &copied[1..]);
+        true
+    }
+
+    fn check_delete(needles: &[u8], haystack: &[u8]) -> bool {
+        let expected = naive_delete(needles, haystack);
+
+        let mut got = haystack.to_vec();
+        let len = delete_bytes(needles, &mut got);
+        assert_eq!(expected, &got[..len]);
+
+        let mut got = haystack.to_vec();
+        delete_bytes_vec(needles, &mut got);
+        assert_eq!(expected, got);
+
+        let mut copied = vec![];
+        delete_bytes_into(needles, haystack, &mut copied);
+        assert_eq!(expected, copied);
+        true
+    }
+
+    fn check_squeeze(byte: u8, haystack: &[u8]) -> bool {
+        let expected = naive_squeeze(byte, haystack);
+
+        let mut got = haystack.to_vec();
+        let len = squeeze_byte(byte, &mut got);
+        assert_eq!(expected, &got[..len]);
+
+        let mut got = haystack.to_vec();
+        squeeze_byte_vec(byte, &mut got);
+        assert_eq!(expected, got);
+
+        let mut copied = vec![];
+        squeeze_byte_into(byte, haystack, &mut copied);
+        assert_eq!(expected, copied);
+        true
+    }
+
+    fn check_crlf_to_lf(haystack: &[u8]) -> bool {
+        let expected = naive_crlf_to_lf(haystack);
+
+        let mut got = haystack.to_vec();
+        let len = crlf_to_lf(&mut got);
+        assert_eq!(expected, &got[..len]);
+
+        let mut got = haystack.to_vec();
+        crlf_to_lf_vec(&mut got);
+        assert_eq!(expected, got);
+
+        let mut copied = vec![];
+        crlf_to_lf_into(haystack, &mut copied);
+        assert_eq!(expected, copied);
+        true
+    }
+
+    #[test]
+    fn replace_simple() {
+        let mut data = *b"aaa";
+        assert_eq!(3, replace_byte(b'a', b'b', &mut data));
+        assert_eq!(b"bbb", &data);
+        assert_eq!(0, replace_byte(b'a', b'b', &mut data));
+        assert_eq!(0, replace_byte(b'a', b'b', &mut []));
+
+        check_replace(&[], b"abc");
+        check_replace(&[(b'a', b'b'), (b'b', b'a')], b"abba");
+        check_replace(&[(b'a', b'b'), (b'a', b'c')], b"aaa");
+        check_replace(&[(b'\r', b'\n'), (b'\t', b' '), (0, b'0')], b"\r\t\0");
+    }
+
+    #[test]
+    fn delete_simple() {
+        check_delete(b"", b"abc");
+        check_delete(b"a", b"");
+        check_delete(b"a", b"aaaa");
+        check_delete(b"a", b"abab");
+        check_delete(b"\r\0", b"a\r\nb\0\0\r\n");
+        check_delete(b"xyz", b"xaybzc");
+
+        // Lone `\r` bytes are deleted too, not just those before a `\n`.
+        let mut data = b"a\rb\r\n".to_vec();
+        delete_bytes_vec(b"\r", &mut data);
+        assert_eq!(b"ab\n", &data[..]);
+    }
+
+    #[test]
+    fn crlf_to_lf_simple() {
+        check_crlf_to_lf(b"");
+        check_crlf_to_lf(b"\r");
+        check_crlf_to_lf(b"\n");
+        check_crlf_to_lf(b"\r\n");
+        check_crlf_to_lf(b"\r\r\n\n");
+        check_crlf_to_lf(b"a\r\nb\r\n");
+        check_crlf_to_lf(b"\n\r");
+
+        // Lone `\r` bytes are kept.
+        let mut data = b"a\rb\r\n\r".to_vec();
+        crlf_to_lf_vec(&mut data);
+        assert_eq!(b"a\rb\n\r", &data[..]);
+    }
+
+    #[test]
+    fn squeeze_simple() {
+        check_squeeze(b' ', b"");
+        check_squeeze(b' ', b" ");
+        check_squeeze(b' ', b"    ");
+        check_squeeze(b' ', b"  a  b  ");
+        check_squeeze(b' ', b"a b c");
+    }
+
+    #[test]
+    #[should_panic]
+    fn delete_too_many() {
+        delete_bytes(b"abcd", &mut []);
+    }
+
+    #[test]
+    #[should_panic]
+    fn replace_too_many() {
+        replace_bytes(&[(0, 0), (1, 1), (2, 2), (3, 3)], &mut []);
+    }
+
+    quickcheck! {
+        fn qc_replace_byte(from: u8, to: u8, haystack: Vec<u8>) -> bool {
+            let haystack = small(&haystack);
+            check_replace(&[(from % 4, to)], &haystack)
+        }
+
+        fn qc_replace_bytes(pairs: Vec<(u8, u8)>, haystack: Vec<u8>) -> bool {
+            let pairs: Vec<(u8, u8)> =
+                pairs.into_iter().take(3).map(|(f, t)| (f % 4, t)).collect();
+            check_replace(&pairs, &small(&haystack))
+        }
+
+        fn qc_delete_bytes(needles: Vec<u8>, haystack: Vec<u8>) -> bool {
+            let needles = small(&needles[..needles.len().min(3)]);
+            check_delete(&needles, &small(&haystack))
+        }
+
+        fn qc_squeeze_byte(byte: u8, haystack: Vec<u8>) -> bool {
+            check_squeeze(byte % 4, &small(&haystack))
+        }
+
+        fn qc_crlf_to_lf(haystack: Vec<u8>) -> bool {
+            let haystack: Vec<u8> =
+                haystack.iter().map(|&b| b"\r\na"[b as usize % 3]).collect();
+            check_crlf_to_lf(&haystack)
+        }
+    }
+}